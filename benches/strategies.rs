@@ -0,0 +1,119 @@
+//! Measures how long strategies take to evaluate 1k, 10k and 100k backups, one taken
+//! every hour, using criterion. Run with
+//!
+//! ```sh
+//! cargo bench --bench strategies
+//! ```
+//!
+//! A filter may be given to only measure some of them (e.g. `-- OlderThan/1000`), and
+//! criterion's `--save-baseline` and `--baseline` to compare against an earlier run.
+//!
+//! # Notes
+//!
+//! Baseline:
+//!
+//! | Strategy                      |     1k |    10k |   100k |
+//! | ----------------------------- | ------ | ------ | ------ |
+//! | `OlderThan`                   | 0.9 ms | 175 ms |   36 s |
+//! | `KeepOnePerMonth`             | 1.7 ms | 167 ms |   36 s |
+//! | `OlderThanButKeepOnePerMonth` | 2.2 ms | 340 ms |   60 s |
+//!
+//! All other strategies take less than 11 ms for 100k backups. `OlderThan` removes
+//! each expendable backup from the middle of the input and inserts it at the front of
+//! the result, `KeepOnePerMonth` does likewise for all backups it doesn't keep, both of
+//! which are quadratic.
+//!
+//! After partitioning in a single pass, `OlderThan` takes 58 µs, 0.6 ms and 7.2 ms.
+//! `KeepOnePerMonth` takes 0.13 ms, 1.6 ms and 15 ms, and with both of them linear
+//! `OlderThanButKeepOnePerMonth` takes 0.18 ms, 2.3 ms and 30 ms.
+//!
+//! Measured with criterion, which leaves out cloning the input: `OlderThan` takes
+//! 41 µs, 1.5 ms and 15 ms, `KeepOnePerMonth` 95 µs, 0.77 ms and 13 ms, and
+//! `OlderThanButKeepOnePerMonth` 0.17 ms, 2.4 ms and 50 ms.
+use time::Duration;
+use chrono::{DateTime, Utc};
+use chrono::offset::TimeZone;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use backups_cleaner::BackupFileMeta;
+use backups_cleaner::pruning_strategy::*;
+use backups_cleaner::reporting::Period;
+
+const SIZES: [usize; 3] = [1_000, 10_000, 100_000];
+
+fn reference_time() -> DateTime<Utc> {
+    Utc.ymd(2020, 1, 1).and_hms(0, 0, 0)
+}
+
+/// Returns `n` backups, one every hour up to `reference_time`, in chronological order.
+fn backups(n: usize) -> Vec<BackupFileMeta> {
+    (0..n)
+        .rev()
+        .map(|hours_ago| {
+            let id = format!("backups/{}.dump", hours_ago);
+
+            BackupFileMeta {
+                id: id.clone(),
+                human_readable_id: id,
+                date: reference_time() - Duration::hours(hours_ago as i64),
+                size: 1024 + (hours_ago % 7) as u64,
+                storage_class: None,
+                etag: Some(format!("\"{}\"", hours_ago % 3)),
+                successful: None,
+                retain_until: None,
+                tier: None,
+                version_id: None,
+            }
+        })
+        .collect()
+}
+
+fn strategies() -> Vec<(&'static str, Box<dyn PruningStrategy>)> {
+    vec![
+        ("OlderThan", Box::new(OlderThan::new(Duration::days(30), reference_time()))),
+        ("KeepOnePerMonth", Box::new(KeepOnePerMonth::new(Duration::days(15)))),
+        ("OlderThanButKeepOnePerMonth", Box::new(OlderThanButKeepOnePerMonth::new(
+            reference_time(),
+            Duration::days(14),
+            Duration::days(15),
+            Duration::days(3650),
+        ))),
+        ("KeepLastN", Box::new(KeepLastN::new(100))),
+        ("KeepEveryNth", Box::new(KeepEveryNth::new(10))),
+        ("KeepEvenlySpaced", Box::new(KeepEvenlySpaced::new(100))),
+        ("KeepNewestPerPeriod", Box::new(KeepNewestPerPeriod::new(Period::Day, 30))),
+        ("KeepOnSpecificDays", Box::new(KeepOnSpecificDays::new(vec![1, 15], Duration::days(2)))),
+        ("KeepOnePerQuarter", Box::new(KeepOnePerQuarter::new(Duration::days(15), 1))),
+        ("KeepOnePerWeekday", Box::new(KeepOnePerWeekday::new(reference_time(), Duration::days(28)))),
+        ("CollapseBursts", Box::new(CollapseBursts::new(Duration::minutes(90)))),
+        ("DeduplicateIdentical", Box::new(DeduplicateIdentical::new())),
+        ("KeepExponential", Box::new(KeepExponential::new(reference_time(), 2))),
+    ]
+}
+
+fn bench_strategies(c: &mut Criterion) {
+    let inputs: Vec<(usize, Vec<BackupFileMeta>)> = SIZES.iter().map(|size| (*size, backups(*size))).collect();
+
+    for (name, strategy) in strategies() {
+        let mut group = c.benchmark_group(name);
+
+        for (size, backups) in &inputs {
+            // Fewer samples of the largest inputs, to keep a run short.
+            if *size >= 100_000 {
+                group.sample_size(10);
+            }
+
+            group.bench_with_input(BenchmarkId::from_parameter(size), backups, |bencher, backups| {
+                bencher.iter_batched_ref(
+                    || backups.clone(),
+                    |input| strategy.expendable_backups(input),
+                    BatchSize::LargeInput,
+                )
+            });
+        }
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_strategies);
+criterion_main!(benches);
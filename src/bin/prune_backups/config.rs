@@ -0,0 +1,329 @@
+//! Options that can be given both on the command line and in a TOML config file.
+//! Values given on the command line take precedence over the config file.
+use std::fs;
+use std::path::Path;
+use std::collections::HashSet;
+use serde::{Deserialize, Deserializer};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+
+    /// Region the S3 bucket containing the backups is located in. Pass `auto` to ask
+    /// AWS S3 for it. May be given several times (or as a list in the config file) to
+    /// prune the copies of the backups in each region, see `into_region_settings`.
+    #[structopt(short, long, raw(number_of_values = "1"))]
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub region: Vec<String>,
+
+    /// Name of the S3 bucket the backups are located in. `{region}` is replaced by the
+    /// region, e.g. `backups-{region}`.
+    #[structopt(short, long)]
+    pub bucket: Option<String>,
+
+    /// Prefix of the backups (directory). Defaults to the whole bucket.
+    #[structopt(short, long)]
+    pub prefix: Option<String>,
+
+    /// Leave all backups within `keep_all_within` days unaltered.
+    #[structopt(long)]
+    pub keep_all_within: Option<u16>,
+
+    /// Keep one backup per month within `one_per_month_within` days.
+    #[structopt(long)]
+    pub one_per_month_within: Option<u16>,
+
+    /// Accept backups within `one_per_month_tolerance` days from the 1st of a month as
+    /// that month's backup. Defaults to 15.
+    #[structopt(long)]
+    pub one_per_month_tolerance: Option<u16>,
+
+    /// Never delete the `always_keep_latest` most recent backups.
+    #[structopt(long)]
+    pub always_keep_latest: Option<usize>,
+
+    /// Keep the newest backup of each of the last `keep_daily` days with backups, like
+    /// restic's `--keep-daily`. Replaces `keep_all_within` and `one_per_month_within`.
+    #[structopt(long, alias = "keep-daily")]
+    pub keep_daily: Option<usize>,
+
+    /// Keep the newest backup of each of the last `keep_weekly` weeks with backups.
+    #[structopt(long, alias = "keep-weekly")]
+    pub keep_weekly: Option<usize>,
+
+    /// Keep the newest backup of each of the last `keep_monthly` months with backups.
+    #[structopt(long, alias = "keep-monthly")]
+    pub keep_monthly: Option<usize>,
+
+    /// Keep the newest backup of each of the last `keep_yearly` years with backups.
+    #[structopt(long, alias = "keep-yearly")]
+    pub keep_yearly: Option<usize>,
+}
+
+/// Which backups to keep.
+#[derive(Debug, PartialEq)]
+pub enum Retention {
+
+    /// Keep all backups within `keep_all_within` days, and one per month within
+    /// `one_per_month_within` days.
+    Windows {
+        keep_all_within: u16,
+        one_per_month_within: u16,
+        one_per_month_tolerance: u16,
+    },
+
+    /// Keep the newest backup of each of the given number of most recent days, weeks,
+    /// months and years with backups, as restic and borg do.
+    PerPeriod {
+        daily: usize,
+        weekly: usize,
+        monthly: usize,
+        yearly: usize,
+    },
+}
+
+/// The options a run is performed with, after merging command line and config file.
+#[derive(Debug, PartialEq)]
+pub struct Settings {
+    pub region: String,
+    pub bucket: String,
+    pub prefix: String,
+    pub retention: Retention,
+    pub always_keep_latest: Option<usize>,
+}
+
+/// Accepts a single value as well as a list of them.
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
+}
+
+impl Config {
+
+    /// Reads the config file at `path`. The error describes the offending field, if
+    /// the file is invalid.
+    pub fn from_file(path: &Path) -> Result<Config, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|error| format!("Could not read config file {}: {}", path.display(), error))?;
+
+        toml::from_str(&contents)
+            .map_err(|error| format!("Invalid config file {}: {}", path.display(), error))
+    }
+
+    /// Returns a config that takes all values present in `self`, and the remaining
+    /// ones from `fallback`.
+    pub fn or(self, fallback: Config) -> Config {
+        Config {
+            region: if self.region.is_empty() { fallback.region } else { self.region },
+            bucket: self.bucket.or(fallback.bucket),
+            prefix: self.prefix.or(fallback.prefix),
+            keep_all_within: self.keep_all_within.or(fallback.keep_all_within),
+            one_per_month_within: self.one_per_month_within.or(fallback.one_per_month_within),
+            one_per_month_tolerance: self.one_per_month_tolerance.or(fallback.one_per_month_tolerance),
+            always_keep_latest: self.always_keep_latest.or(fallback.always_keep_latest),
+            keep_daily: self.keep_daily.or(fallback.keep_daily),
+            keep_weekly: self.keep_weekly.or(fallback.keep_weekly),
+            keep_monthly: self.keep_monthly.or(fallback.keep_monthly),
+            keep_yearly: self.keep_yearly.or(fallback.keep_yearly),
+        }
+    }
+
+    /// Fills in defaults and fails, if a required value is missing.
+    pub fn into_settings(self) -> Result<Settings, String> {
+        let missing = |name: &str| format!("Missing `{}`: pass --{} or set it in the config file.", name, name);
+
+        let per_period = [self.keep_daily, self.keep_weekly, self.keep_monthly, self.keep_yearly];
+
+        let retention = if per_period.iter().any(Option::is_some) {
+            if self.keep_all_within.is_some() || self.one_per_month_within.is_some() {
+                return Err(String::from(
+                    "`keep_daily`, `keep_weekly`, `keep_monthly` and `keep_yearly` can't be combined with `keep_all_within` or `one_per_month_within`."
+                ));
+            }
+
+            Retention::PerPeriod {
+                daily: self.keep_daily.unwrap_or(0),
+                weekly: self.keep_weekly.unwrap_or(0),
+                monthly: self.keep_monthly.unwrap_or(0),
+                yearly: self.keep_yearly.unwrap_or(0),
+            }
+        }
+        else {
+            Retention::Windows {
+                keep_all_within: self.keep_all_within.ok_or_else(|| missing("keep_all_within"))?,
+                one_per_month_within: self.one_per_month_within.ok_or_else(|| missing("one_per_month_within"))?,
+                one_per_month_tolerance: self.one_per_month_tolerance.unwrap_or(15),
+            }
+        };
+
+        let region = match self.region.as_slice() {
+            [] => return Err(missing("region")),
+            [region] => region.clone(),
+            _ => return Err(String::from("Several regions can only be pruned by `apply`.")),
+        };
+
+        Ok(Settings {
+            bucket: self.bucket.ok_or_else(|| missing("bucket"))?.replace("{region}", &region),
+            region,
+            prefix: self.prefix.unwrap_or_default(),
+            retention,
+            always_keep_latest: self.always_keep_latest,
+        })
+    }
+
+    /// Returns the settings for each region like `into_settings`. Fails, if a region is
+    /// given twice, or if the bucket doesn't depend on the region, as bucket names are
+    /// unique across all regions.
+    pub fn into_region_settings(self) -> Result<Vec<Settings>, String> {
+        if self.bucket.as_ref().is_some_and(|bucket| !bucket.contains("{region}")) {
+            return Err(String::from("`bucket` needs to contain `{region}` to prune several regions."));
+        }
+
+        let mut regions = HashSet::new();
+
+        self.region
+            .iter()
+            .map(|region| {
+                if !regions.insert(region) {
+                    return Err(format!("The region {} is given more than once.", region));
+                }
+
+                Config {
+                    region: vec![region.clone()],
+                    ..self.clone()
+                }.into_settings()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Config {
+        Config::from_iter(Some("prune_backups").iter().chain(args.iter()))
+    }
+
+    fn parse_file(contents: &str) -> Result<Config, String> {
+        toml::from_str(contents).map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn test_command_line_takes_precedence_over_config_file() {
+        let file = parse_file(r#"
+            region = "eu-west-2"
+            bucket = "my-database-backups"
+            keep_all_within = 14
+            one_per_month_within = 365
+        "#).unwrap();
+        let args = parse_args(&["--bucket", "other-backups", "--keep_all_within", "7"]);
+
+        assert_eq!(args.or(file).into_settings().unwrap(), Settings {
+            region: String::from("eu-west-2"),
+            bucket: String::from("other-backups"),
+            prefix: String::new(),
+            retention: Retention::Windows {
+                keep_all_within: 7,
+                one_per_month_within: 365,
+                one_per_month_tolerance: 15,
+            },
+            always_keep_latest: None,
+        });
+    }
+
+    #[test]
+    fn test_config_file_values_are_used_when_not_given_on_the_command_line() {
+        let file = parse_file(r#"
+            prefix = "backups/"
+            one_per_month_tolerance = 3
+            always_keep_latest = 5
+        "#).unwrap();
+        let args = parse_args(&["-r", "eu-west-2", "-b", "my-database-backups", "--keep_all_within", "7", "--one_per_month_within", "30"]);
+
+        let settings = args.or(file).into_settings().unwrap();
+
+        assert_eq!(settings.prefix, "backups/");
+        assert_eq!(settings.retention, Retention::Windows {
+            keep_all_within: 7,
+            one_per_month_within: 30,
+            one_per_month_tolerance: 3,
+        });
+        assert_eq!(settings.always_keep_latest, Some(5));
+    }
+
+    #[test]
+    fn test_into_settings_when_a_required_value_is_missing() {
+        let args = parse_args(&["-r", "eu-west-2", "--keep_all_within", "7", "--one_per_month_within", "30"]);
+
+        assert_eq!(
+            args.or(Config::default()).into_settings(),
+            Err(String::from("Missing `bucket`: pass --bucket or set it in the config file.")),
+        );
+    }
+
+    #[test]
+    fn test_into_settings_with_restic_style_flags() {
+        let file = parse_file("keep_yearly = 3").unwrap();
+        let args = parse_args(&["-r", "eu-west-2", "-b", "my-database-backups", "--keep-daily", "7", "--keep_monthly", "12"]);
+
+        assert_eq!(args.or(file).into_settings().unwrap().retention, Retention::PerPeriod {
+            daily: 7,
+            weekly: 0,
+            monthly: 12,
+            yearly: 3,
+        });
+    }
+
+    #[test]
+    fn test_into_settings_with_restic_style_flags_and_windows() {
+        let args = parse_args(&["-r", "eu-west-2", "-b", "my-database-backups", "--keep_daily", "7", "--keep_all_within", "7"]);
+
+        assert!(args.or(Config::default()).into_settings().is_err());
+    }
+
+    #[test]
+    fn test_into_region_settings() {
+        let file = parse_file(r#"
+            region = ["eu-west-2", "us-east-1"]
+            keep_daily = 7
+        "#).unwrap();
+        let args = parse_args(&["-b", "backups-{region}"]);
+
+        let settings = args.or(file).into_region_settings().unwrap();
+
+        assert_eq!(settings.len(), 2);
+        assert_eq!((settings[0].region.as_str(), settings[0].bucket.as_str()), ("eu-west-2", "backups-eu-west-2"));
+        assert_eq!((settings[1].region.as_str(), settings[1].bucket.as_str()), ("us-east-1", "backups-us-east-1"));
+    }
+
+    #[test]
+    fn test_into_region_settings_when_invalid() {
+        let args = parse_args(&["-r", "eu-west-2", "-r", "us-east-1", "-b", "backups", "--keep_daily", "7"]);
+        assert_eq!(args.region, vec!["eu-west-2", "us-east-1"]);
+        assert!(args.clone().into_region_settings().is_err());
+        assert_eq!(args.into_settings(), Err(String::from("Several regions can only be pruned by `apply`.")));
+
+        let args = parse_args(&["-r", "eu-west-2", "-r", "eu-west-2", "-b", "backups-{region}", "--keep_daily", "7"]);
+        assert_eq!(args.into_region_settings(), Err(String::from("The region eu-west-2 is given more than once.")));
+
+        assert_eq!(parse_file("region = \"eu-west-2\"").unwrap().region, vec!["eu-west-2"]);
+    }
+
+    #[test]
+    fn test_invalid_config_file_names_the_offending_field() {
+        assert!(parse_file("keep_all_within = \"two weeks\"").unwrap_err().contains("keep_all_within"));
+        assert!(parse_file("bukcet = \"my-database-backups\"").unwrap_err().contains("bukcet"));
+    }
+}
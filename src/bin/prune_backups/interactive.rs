@@ -0,0 +1,149 @@
+//! Lets the user decide on each expendable backup individually.
+use std::io::{BufRead, Write};
+use backups_cleaner::BackupFileMeta;
+
+/// The backups the user decided on.
+#[derive(Debug, Default)]
+pub struct Selection {
+    pub to_delete: Vec<BackupFileMeta>,
+    pub to_keep: Vec<BackupFileMeta>,
+}
+
+/// Formats `bytes` using binary prefixes, e.g. `1.5 MiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit < units.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} B", bytes)
+    }
+    else {
+        format!("{:.1} {}", value, units[unit])
+    }
+}
+
+/// Asks whether to keep or delete each of the `expendable_backups`, reading answers
+/// from `input`. Returns `None`, if the user quit or `input` ended before all backups
+/// have been decided on.
+pub fn select<R: BufRead, W: Write>(
+    expendable_backups: Vec<BackupFileMeta>,
+    mut input: R,
+    mut output: W,
+) -> Option<Selection> {
+    let mut selection = Selection::default();
+    let total = expendable_backups.len();
+
+    for (i, backup) in expendable_backups.into_iter().enumerate() {
+        loop {
+            write!(
+                output,
+                "[{}/{}] {} ({}, {}) - [k]eep, [d]elete or [q]uit? ",
+                i + 1,
+                total,
+                backup.human_readable_id,
+                backup.date.to_rfc3339(),
+                format_bytes(backup.size),
+            ).unwrap();
+            output.flush().unwrap();
+
+            let mut answer = String::new();
+            if input.read_line(&mut answer).unwrap() == 0 {
+                return None;
+            }
+
+            match answer.trim() {
+                "k" => {
+                    selection.to_keep.push(backup);
+                    break;
+                },
+                "d" => {
+                    selection.to_delete.push(backup);
+                    break;
+                },
+                "q" => return None,
+                _ => continue,
+            }
+        }
+    }
+
+    Some(selection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn build_meta(id: &str) -> BackupFileMeta {
+        BackupFileMeta {
+            size: 1536,
+            ..crate::tests::build_meta(id, Utc::now())
+        }
+    }
+
+    fn ids(backups: &[BackupFileMeta]) -> Vec<&str> {
+        backups.iter().map(|backup| backup.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_select() {
+        let mut output = vec![];
+
+        let selection = select(
+            vec![build_meta("A"), build_meta("B"), build_meta("C")],
+            "d\nx\nk\nd\n".as_bytes(),
+            &mut output,
+        ).unwrap();
+
+        assert_eq!(ids(&selection.to_delete), vec!["A", "C"]);
+        assert_eq!(ids(&selection.to_keep), vec!["B"]);
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("[1/3] A ("));
+        assert!(output.contains("1.5 KiB"));
+
+        // The invalid answer leads to asking again.
+        assert_eq!(output.matches("[2/3] B").count(), 2);
+    }
+
+    #[test]
+    fn test_select_when_the_user_quits() {
+        let selection = select(vec![build_meta("A"), build_meta("B")], "d\nq\n".as_bytes(), vec![]);
+
+        assert!(selection.is_none());
+    }
+
+    #[test]
+    fn test_select_when_the_input_ends() {
+        let selection = select(vec![build_meta("A"), build_meta("B")], "d\n".as_bytes(), vec![]);
+
+        assert!(selection.is_none());
+    }
+
+    #[test]
+    fn test_select_with_redacted_backups() {
+        let mut backup = build_meta("db.acme-corp.internal/2014-07-01.dump");
+        backup.redact();
+        let mut output = vec![];
+
+        select(vec![backup], "k\n".as_bytes(), &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("***1.dump"));
+        assert!(!output.contains("acme-corp"));
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1024), "1.0 KiB");
+        assert_eq!(format_bytes(5 * 1024 * 1024 * 1024), "5.0 GiB");
+    }
+}
@@ -0,0 +1,121 @@
+//! Audit of the kept backups written by `--audit_kept`, telling for each of them why
+//! it's retained, as the receipts only tell which backups have been deleted.
+use serde::Serialize;
+use chrono::{DateTime, Utc};
+use backups_cleaner::BackupFileMeta;
+use backups_cleaner::pruning_strategy::PruningStrategy;
+
+/// A kept backup along with the reason for keeping it.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct KeptBackup {
+    pub id: String,
+
+    /// When the backup has been stored, or `None`, if it has never been listed, as its
+    /// key is protected.
+    pub date: Option<DateTime<Utc>>,
+    pub reason: String,
+
+    /// The retention policy, as described in receipts.
+    pub strategy: String,
+}
+
+/// Returns why each of the `kept_backups` is kept by `pruning_strategy`, oldest first,
+/// followed by the never listed backups of `protected_keys`.
+pub fn kept_backups(
+    pruning_strategy: &dyn PruningStrategy,
+    kept_backups: &[BackupFileMeta],
+    expendable_backups: &[BackupFileMeta],
+    protected_keys: &[String],
+    strategy: &str,
+) -> Vec<KeptBackup> {
+    let all_backups: Vec<BackupFileMeta> = kept_backups.iter().chain(expendable_backups).cloned().collect();
+
+    let mut listed: Vec<KeptBackup> = kept_backups.iter().map(|backup| KeptBackup {
+        id: backup.human_readable_id.clone(),
+        date: Some(backup.date),
+        reason: pruning_strategy
+            .explain(backup, &all_backups)
+            .unwrap_or_else(|| String::from("The strategy doesn't tell why.")),
+        strategy: String::from(strategy),
+    }).collect();
+    listed.sort_by_key(|kept_backup| kept_backup.date);
+
+    let protected = protected_keys.iter().map(|key| KeptBackup {
+        id: key.clone(),
+        date: None,
+        reason: String::from("Its key is protected by --protect_key, so it's never listed nor deleted."),
+        strategy: String::from(strategy),
+    });
+
+    listed.into_iter().chain(protected).collect()
+}
+
+/// Serializes `kept_backups` as JSON Lines, i.e. one object per line.
+pub fn to_jsonl(kept_backups: &[KeptBackup]) -> String {
+    kept_backups
+        .iter()
+        .map(|kept_backup| serde_json::to_string(kept_backup).expect("A kept backup can always be serialized.") + "\n")
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backups_cleaner::pruning_strategy::{AlwaysKeepLatest, OlderThanButKeepOnePerMonth, Pin};
+    use chrono::offset::TimeZone;
+    use time::Duration;
+    use crate::tests::build_meta;
+
+    #[test]
+    fn test_kept_backups() {
+        let pruning_strategy = Pin::new(
+            Box::new(AlwaysKeepLatest::new(
+                Box::new(OlderThanButKeepOnePerMonth::new(
+                    Utc.ymd(2014, 7, 15).and_hms(0, 0, 0),
+                    Duration::days(1),
+                    Duration::days(3),
+                    Duration::days(90),
+                )),
+                2,
+            )),
+            vec![String::from("K")],
+        );
+        let mut stored_backups = vec![
+            build_meta("N", Utc.ymd(2014, 3, 1).and_hms(0, 0, 0)),
+            build_meta("K", Utc.ymd(2014, 4, 3).and_hms(0, 0, 0)),
+            build_meta("M", Utc.ymd(2014, 6, 1).and_hms(0, 0, 0)),
+            build_meta("J", Utc.ymd(2014, 6, 5).and_hms(0, 0, 0)),
+            build_meta("L", Utc.ymd(2014, 7, 10).and_hms(0, 0, 0)),
+            build_meta("T", Utc.ymd(2014, 7, 14).and_hms(12, 0, 0)),
+        ];
+        let expendable_backups = pruning_strategy.expendable_backups(&mut stored_backups);
+        let protected_keys = vec![String::from("latest.dump")];
+
+        let kept = kept_backups(&pruning_strategy, &stored_backups, &expendable_backups, &protected_keys, "keep_all_within=1d");
+
+        let reasons: Vec<(&str, &str)> = kept.iter().map(|kept| (kept.id.as_str(), kept.reason.as_str())).collect();
+        assert_eq!(reasons, vec![
+            ("K", "It's pinned, so it's kept."),
+            ("M", "It's older than keep_all_within (1 days), but the one closest to 2014-06-01 within one_per_month_tolerance (3 days), so it's kept."),
+            ("L", "It's one of the 2 latest backups (always_keep_latest), so it's kept."),
+            ("T", "It's within keep_all_within (1 days), so it's kept."),
+            ("latest.dump", "Its key is protected by --protect_key, so it's never listed nor deleted."),
+        ]);
+        assert!(kept.iter().all(|kept| kept.strategy == "keep_all_within=1d"));
+    }
+
+    #[test]
+    fn test_to_jsonl() {
+        let kept = vec![KeptBackup {
+            id: String::from("A"),
+            date: Some(Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            reason: String::from("It's pinned, so it's kept."),
+            strategy: String::from("keep_daily=7, keep_weekly=0, keep_monthly=0, keep_yearly=0"),
+        }];
+
+        assert_eq!(
+            to_jsonl(&kept),
+            "{\"id\":\"A\",\"date\":\"2014-07-01T00:00:00Z\",\"reason\":\"It's pinned, so it's kept.\",\"strategy\":\"keep_daily=7, keep_weekly=0, keep_monthly=0, keep_yearly=0\"}\n",
+        );
+    }
+}
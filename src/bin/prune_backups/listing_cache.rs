@@ -0,0 +1,168 @@
+//! Keeps the listing of the stored backups on disk, so repeated dry runs don't list
+//! the whole bucket each time.
+use std::fs;
+use std::path::PathBuf;
+use serde::{Serialize, Deserialize};
+use time::Duration;
+use chrono::{DateTime, Utc};
+use backups_cleaner::BackupFileMeta;
+use backups_cleaner::storage_client::{StorageClient, StorageError};
+
+#[derive(Serialize, Deserialize)]
+struct CachedListing {
+    listed_at: DateTime<Utc>,
+    backups: Vec<BackupFileMeta>,
+}
+
+pub struct ListingCache {
+    path: PathBuf,
+    ttl: Duration,
+}
+
+impl ListingCache {
+
+    pub fn new(path: PathBuf, ttl: Duration) -> ListingCache {
+        ListingCache {
+            path,
+            ttl,
+        }
+    }
+
+    /// Returns the stored backups of `storage_client`. Takes them from the cache, if
+    /// `use_cached` and they've been listed less than `ttl` before `now`. Otherwise
+    /// lists them and updates the cache.
+    pub fn stored_backups(
+        &self,
+        storage_client: &dyn StorageClient,
+        now: DateTime<Utc>,
+        use_cached: bool,
+    ) -> Result<Vec<BackupFileMeta>, StorageError> {
+        if use_cached {
+            if let Some(backups) = self.load(now) {
+                return Ok(backups);
+            }
+        }
+
+        let backups = storage_client.stored_backups()?;
+
+        // The cache only saves time, so failing to write it mustn't fail the run.
+        if let Err(error) = self.store(&backups, now) {
+            eprintln!("Could not cache the listing in {}: {}", self.path.display(), error);
+        }
+
+        Ok(backups)
+    }
+
+    fn load(&self, now: DateTime<Utc>) -> Option<Vec<BackupFileMeta>> {
+        let contents = fs::read_to_string(&self.path).ok()?;
+        let cached_listing: CachedListing = toml::from_str(&contents).ok()?;
+
+        if now.signed_duration_since(cached_listing.listed_at) < self.ttl {
+            Some(cached_listing.backups)
+        }
+        else {
+            None
+        }
+    }
+
+    fn store(&self, backups: &[BackupFileMeta], now: DateTime<Utc>) -> Result<(), String> {
+        let cached_listing = CachedListing {
+            listed_at: now,
+            backups: backups.to_vec(),
+        };
+        let contents = toml::to_string(&cached_listing).map_err(|error| error.to_string())?;
+
+        super::write_atomically(&self.path, contents).map_err(|error| error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use chrono::offset::TimeZone;
+    use backups_cleaner::storage_client::{InMemory, DeletionReport};
+
+    /// Counts how often the backups have been listed.
+    struct CountingClient {
+        inner: InMemory,
+        listings: AtomicUsize,
+    }
+
+    impl StorageClient for CountingClient {
+
+        fn stored_backups(&self) -> Result<Vec<BackupFileMeta>, StorageError> {
+            self.listings.fetch_add(1, Ordering::SeqCst);
+            self.inner.stored_backups()
+        }
+
+        fn delete_backups(&self, backups: Vec<BackupFileMeta>) -> DeletionReport {
+            self.inner.delete_backups(backups)
+        }
+    }
+
+    fn client() -> CountingClient {
+        CountingClient {
+            inner: InMemory::new(vec![BackupFileMeta {
+                size: 1024,
+                storage_class: Some(String::from("STANDARD")),
+                ..crate::tests::build_meta("backups/2014-07-01.dump", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0))
+            }]),
+            listings: AtomicUsize::new(0),
+        }
+    }
+
+    fn cache(name: &str) -> ListingCache {
+        let path = std::env::temp_dir().join(format!("backups_cleaner-{}-{}.toml", name, process::id()));
+        let _ = fs::remove_file(&path);
+
+        ListingCache::new(path, Duration::hours(1))
+    }
+
+    #[test]
+    fn test_stored_backups_within_ttl() {
+        let client = client();
+        let cache = cache("within_ttl");
+        let now = Utc.ymd(2014, 7, 2).and_hms(0, 0, 0);
+
+        let first = cache.stored_backups(&client, now, true).unwrap();
+        let second = cache.stored_backups(&client, now + Duration::minutes(30), true).unwrap();
+
+        assert_eq!(client.listings.load(Ordering::SeqCst), 1);
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].id, first[0].id);
+        assert_eq!(second[0].date, first[0].date);
+        assert_eq!(second[0].storage_class, Some(String::from("STANDARD")));
+
+        let _ = fs::remove_file(&cache.path);
+    }
+
+    #[test]
+    fn test_stored_backups_after_ttl() {
+        let client = client();
+        let cache = cache("after_ttl");
+        let now = Utc.ymd(2014, 7, 2).and_hms(0, 0, 0);
+
+        cache.stored_backups(&client, now, true).unwrap();
+        cache.stored_backups(&client, now + Duration::hours(2), true).unwrap();
+
+        assert_eq!(client.listings.load(Ordering::SeqCst), 2);
+
+        let _ = fs::remove_file(&cache.path);
+    }
+
+    #[test]
+    fn test_stored_backups_without_using_the_cache() {
+        let client = client();
+        let cache = cache("bypassed");
+        let now = Utc.ymd(2014, 7, 2).and_hms(0, 0, 0);
+
+        cache.stored_backups(&client, now, true).unwrap();
+        cache.stored_backups(&client, now, false).unwrap();
+
+        assert_eq!(client.listings.load(Ordering::SeqCst), 2);
+
+        let _ = fs::remove_file(&cache.path);
+    }
+}
@@ -0,0 +1,2605 @@
+mod config;
+mod interactive;
+mod kept_audit;
+mod listing_cache;
+mod plan;
+mod script;
+mod targets;
+
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
+use structopt::StructOpt;
+use time::Duration;
+use chrono::{DateTime, NaiveDate, Utc};
+use backups_cleaner::{BackupFileMeta, PruneSummary, detect_future_backups};
+use backups_cleaner::storage_client;
+use backups_cleaner::storage_client::{StorageClient, StorageError};
+use backups_cleaner::pruning_strategy;
+use backups_cleaner::pruning_strategy::{PruningStrategy, StrategyConfigError};
+use backups_cleaner::metrics::Metrics;
+use backups_cleaner::reporting;
+use backups_cleaner::lifecycle;
+use backups_cleaner::manifest;
+use backups_cleaner::comparison;
+use backups_cleaner::receipt::Receipt;
+use backups_cleaner::reporting::Period;
+use config::{Config, Settings, Retention};
+use listing_cache::ListingCache;
+
+/// Exit codes, so scripts can tell failures apart. Invalid options and any other
+/// errors exit with 1.
+const EXIT_AUTHENTICATION_FAILURE: i32 = 2;
+const EXIT_NETWORK_FAILURE: i32 = 3;
+const EXIT_PARTIAL_DELETION: i32 = 4;
+const EXIT_BUCKET_NOT_FOUND: i32 = 5;
+const EXIT_STALE_BACKUPS: i32 = 6;
+const EXIT_UNVERIFIED_BACKUPS: i32 = 7;
+const EXIT_DELETION_FAILED: i32 = 8;
+
+/// How far backups may be dated after the reference time, before the clocks are
+/// considered skewed.
+const CLOCK_SKEW_TOLERANCE_MINUTES: i64 = 5;
+
+/// Set by `--quiet`.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Prints like `println!`, unless `--quiet` has been given.
+macro_rules! info {
+    ($($argument:tt)*) => {
+        if !QUIET.load(Ordering::Relaxed) {
+            println!($($argument)*);
+        }
+    };
+}
+
+/// Prunes backups in three steps: `list` them, `plan` which ones to delete and `apply`
+/// the plan.
+#[derive(StructOpt, Debug)]
+#[structopt(name = "Backups Cleaner")]
+#[allow(clippy::large_enum_variant)] // Parsed only once.
+enum Command {
+
+    /// Print the stored backups along with how many have been stored per period,
+    /// without deleting anything.
+    #[structopt(name = "list")]
+    List(Opt),
+
+    /// Print which backups the retention policy considers expendable, without deleting
+    /// anything.
+    #[structopt(name = "plan")]
+    Plan(PlanOpt),
+
+    /// Delete the expendable backups, or exactly the ones of a plan written by `plan`.
+    /// Runs without a subcommand apply.
+    #[structopt(name = "apply")]
+    Apply(ApplyOpt),
+}
+
+/// Options shared by all subcommands: Where the backups are stored and the retention
+/// policy.
+#[derive(StructOpt, Debug)]
+struct Opt {
+    /// Only print errors, prompts and explicitly requested output (e.g. `--stats`).
+    #[structopt(short, long)]
+    quiet: bool,
+
+    /// Read options from this TOML config file. Options given on the command line
+    /// take precedence.
+    #[structopt(long = "config", parse(from_os_str))]
+    config_file: Option<PathBuf>,
+
+    #[structopt(flatten)]
+    config: Config,
+
+    /// Location of the backups as a URL such as `s3://BUCKET/PREFIX?region=REGION`,
+    /// instead of `--region`, `--bucket` and `--prefix`. Without a region, it's asked for
+    /// like `--region auto`. Only `s3://` URLs are supported so far.
+    #[structopt(
+        long,
+        parse(try_from_str = "parse_target"),
+        raw(conflicts_with_all = r#"&["region", "bucket", "prefix"]"#)
+    )]
+    target: Option<(String, String, String)>,
+
+    /// Evaluate backup ages relative to this time instead of now. Either an RFC 3339
+    /// timestamp or a date (`YYYY-MM-DD`, meaning midnight UTC).
+    #[structopt(long, parse(try_from_str = "parse_date"))]
+    reference_time: Option<DateTime<Utc>>,
+
+    /// Abort without pruning, if the newest backup is older than this (e.g. `36h` or
+    /// `2d`), as backups seem to have stopped arriving.
+    #[structopt(long, parse(try_from_str = "parse_duration"))]
+    max_staleness: Option<Duration>,
+
+    /// Abort without pruning, if any backup is dated after the reference time, instead
+    /// of only warning. That hints at a wrong clock, making decisions by age unsafe.
+    #[structopt(long)]
+    strict_clock: bool,
+
+    /// Only prune the backups of this month (`YYYY-MM`), leaving all others untouched.
+    #[structopt(long, parse(try_from_str = "parse_month"), conflicts_with = "only_year")]
+    only_month: Option<DateTime<Utc>>,
+
+    /// Only prune the backups of this year (`YYYY`), leaving all others untouched.
+    #[structopt(long, parse(try_from_str = "parse_year"))]
+    only_year: Option<DateTime<Utc>>,
+
+    /// Never delete backups dated within `START..END` (inclusive). Both ends are
+    /// either RFC 3339 timestamps or dates (`YYYY-MM-DD`, meaning midnight UTC for
+    /// START, and the end of that day in UTC for END). May be given several times.
+    #[structopt(long, parse(try_from_str = "parse_date_range"))]
+    quarantine: Vec<(DateTime<Utc>, DateTime<Utc>)>,
+
+    /// Never delete backups stored in this storage class (e.g. `GLACIER`). May be given
+    /// several times.
+    #[structopt(long)]
+    skip_storage_class: Vec<String>,
+
+    /// Delete at most this many bytes (e.g. `500M` or `1G`, meaning GiB) per run, oldest
+    /// backups first. The remaining expendable backups are kept until the next run.
+    #[structopt(long, parse(try_from_str = "parse_bytes"))]
+    max_delete_bytes: Option<u64>,
+
+    /// Number of objects to list per request to AWS S3 (1 to 1000). Smaller pages need
+    /// more requests, but cope better with unstable connections.
+    #[structopt(long, parse(try_from_str = "parse_page_size"))]
+    page_size: Option<usize>,
+
+    /// Never delete the backups referenced by this restore manifest, a JSON object
+    /// listing their ids under `backups` (e.g. `s3://my-bucket/manifests/restore.json`).
+    /// Aborts, if it can't be read.
+    #[structopt(long, parse(try_from_str = "parse_s3_url"))]
+    manifest: Option<(String, String)>,
+
+    /// Never delete the backups listed in this file, one key per line (e.g. the ones
+    /// currently being restore-tested). Lines starting with `#` are ignored. The file is
+    /// read again on each run, so removing a backup from it lifts the protection. Aborts,
+    /// if it can't be read.
+    #[structopt(long, parse(from_os_str))]
+    in_use_file: Option<PathBuf>,
+
+    /// Mask backup names in all output, leaving only their last few characters
+    /// visible (e.g. `***1.dump`).
+    #[structopt(long)]
+    redact: bool,
+
+    /// Refuse to proceed, if the bucket holds more than this many objects under the
+    /// prefix, e.g. as the prefix is wrong. Listing stops as soon as the cap is exceeded.
+    #[structopt(long)]
+    max_list: Option<usize>,
+
+    /// Only list the backups whose keys sort after this one (e.g. the newest backup
+    /// of the last run). Only use this, if keys sort chronologically, e.g. start with
+    /// the date, as other backups would be missed.
+    #[structopt(long)]
+    start_after: Option<String>,
+
+    /// Treat objects with this suffix (e.g. `.success`) as markers of successful runs.
+    /// Backups without a marker are always considered expendable.
+    #[structopt(long)]
+    success_sidecar: Option<String>,
+
+    /// Never delete backups whose object metadata with this key (e.g. `retain-until`
+    /// for `x-amz-meta-retain-until`) is a time in the future. Requests the metadata of
+    /// each backup separately, which takes a lot longer.
+    #[structopt(long)]
+    retain_until_metadata: Option<String>,
+
+    /// Read the retention tier of each backup from its object tag with this key (e.g.
+    /// `backup-tier`), see `--tier`. Requests the tags of each backup separately, which
+    /// takes a lot longer.
+    #[structopt(long)]
+    tier_tag: Option<String>,
+
+    /// Keep backups tagged with a tier by `--tier_tag` according to `TIER=RETENTION`
+    /// (e.g. `monthly=forever` or `daily=7d`) instead of the retention policy. May be
+    /// given several times.
+    #[structopt(long, parse(try_from_str = "parse_tier"), requires = "tier_tag")]
+    tier: Vec<(String, pruning_strategy::TierRetention)>,
+
+    /// Delete the object with the key of each deleted backup followed by `.` and this
+    /// suffix (e.g. `sha256` for `1.dump.sha256`) along with it. Such objects aren't
+    /// considered backups themselves. May be given several times.
+    #[structopt(long)]
+    companion: Vec<String>,
+
+    /// Never consider nor delete the object with exactly this key (e.g.
+    /// `backups/latest.dump`), whatever the strategy decides. May be given several
+    /// times.
+    #[structopt(long)]
+    protect_key: Vec<String>,
+
+    /// Save the listing of the stored backups to this file and reuse it on dry runs
+    /// within `--cache_ttl`, instead of listing the bucket again. Runs deleting backups
+    /// always list the bucket.
+    #[structopt(long, parse(from_os_str))]
+    cache_listing: Option<PathBuf>,
+
+    /// Remember the kept backups in this file, and on the next run only evaluate the
+    /// backups that changed since. Only `apply` runs that went through update the file,
+    /// `plan`, `--dry_run` and declined or failing runs don't. Only strategies purely
+    /// based on age benefit, all others still evaluate all backups. Not supported for
+    /// `--targets` or several regions.
+    #[structopt(long, parse(from_os_str))]
+    incremental: Option<PathBuf>,
+
+    /// Delete at most this percentage (0 to 100) of the backups kept by the previous
+    /// `--incremental` run, or of the stored ones, if there's none. The remaining
+    /// expendable backups are kept until later runs, newest backups last.
+    #[structopt(long, requires = "incremental")]
+    max_reduction_pct: Option<u8>,
+
+    /// How long a listing saved by `--cache_listing` is reused (e.g. `30m` or `1h`).
+    #[structopt(long, parse(try_from_str = "parse_duration"), default_value = "1h")]
+    cache_ttl: Duration,
+
+    /// Print how many backups have been stored per day, week, month and year.
+    #[structopt(long)]
+    stats: bool,
+
+    /// Print which period the kept backups cover, the longest gap between them and how
+    /// many have been kept per month, to check the policy leaves no unexpected gaps.
+    #[structopt(long)]
+    coverage: bool,
+
+    /// Print why the backup with this id is kept or expendable, e.g. which age band it
+    /// falls into.
+    #[structopt(long)]
+    explain_backup: Option<String>,
+
+    /// Write why each kept backup is retained (e.g. being pinned or the one of its
+    /// month) to this file, as JSON Lines along with the retention policy.
+    #[structopt(long, parse(from_os_str))]
+    audit_kept: Option<PathBuf>,
+
+    /// Print how long listing, evaluating the strategy and deleting took.
+    #[structopt(long)]
+    timings: bool,
+
+    /// Write metrics on the run to this file, in the Prometheus text format (e.g. for
+    /// node_exporter's textfile collector).
+    #[structopt(long, parse(from_os_str))]
+    metrics_out: Option<PathBuf>,
+
+    /// Write a summary of the run to this file, as JSON.
+    #[structopt(long, parse(from_os_str))]
+    summary_json: Option<PathBuf>,
+}
+
+#[derive(StructOpt, Debug)]
+struct PlanOpt {
+
+    #[structopt(flatten)]
+    common: Opt,
+
+    /// Print an AWS S3 lifecycle configuration (JSON) implementing the retention policy
+    /// instead of pruning. Only works for policies purely based on age, i.e. without
+    /// monthly backups being kept or any backups being protected otherwise.
+    #[structopt(long)]
+    emit_lifecycle: bool,
+
+    /// Compare the strategy with the one described by the retention options in this TOML
+    /// file (e.g. `keep_all_within` or `keep_daily`), printing which backups only one of
+    /// them keeps. Deletes nothing.
+    #[structopt(long, parse(from_os_str))]
+    compare_strategy: Option<PathBuf>,
+
+    /// Write the plan to this file, to be executed by `apply --plan`.
+    #[structopt(long, parse(from_os_str))]
+    out: Option<PathBuf>,
+}
+
+#[derive(StructOpt, Debug)]
+struct ApplyOpt {
+
+    #[structopt(flatten)]
+    common: Opt,
+
+    /// Asking for confirmation will be skipped, if this flag is provided. Required, if
+    /// stdin isn't a terminal to confirm on (e.g. when run by cron), while stdout may be
+    /// redirected either way.
+    #[structopt(short = "y", long)]
+    skip_confirmation: bool,
+
+    /// Ask whether to keep or delete each expendable backup, instead of confirming
+    /// all of them at once. Quitting deletes nothing.
+    #[structopt(short, long, conflicts_with = "skip_confirmation")]
+    interactive: bool,
+
+    /// List the expendable backups without deleting anything.
+    #[structopt(long, conflicts_with = "interactive")]
+    dry_run: bool,
+
+    /// Abort without deleting anything, if the confirmation hasn't been given within
+    /// this many seconds. Waits indefinitely by default.
+    #[structopt(long)]
+    confirm_timeout: Option<u64>,
+
+    /// Write a shell script deleting the expendable backups using the AWS CLI to this
+    /// file, instead of deleting them.
+    #[structopt(long, parse(from_os_str), conflicts_with = "interactive")]
+    emit_script: Option<PathBuf>,
+
+    /// Tag expendable backups with `KEY=VALUE` (e.g. `lifecycle=expired`) instead of
+    /// deleting them, so they can be reviewed and deleted separately.
+    #[structopt(long, parse(try_from_str = "parse_tag"))]
+    tag_expired: Option<(String, String)>,
+
+    /// After deleting, write a receipt listing the deleted backups to this location (e.g.
+    /// `s3://audit-bucket/receipts/`), named after the time of the run. Failing to write
+    /// it only warns, as the backups are gone already.
+    #[structopt(long, parse(try_from_str = "parse_s3_url"))]
+    receipt_dest: Option<(String, String)>,
+
+    /// After pruning, write a manifest listing the backups still stored (ids, dates and
+    /// sizes) to this location (e.g. `s3://inventory-bucket/backups.json`), replacing the
+    /// previous one. Backups that failed to be deleted are listed. Failing to write it
+    /// only warns.
+    #[structopt(
+        long,
+        parse(try_from_str = "parse_s3_url"),
+        raw(conflicts_with_all = r#"&["dry_run", "emit_script", "targets"]"#)
+    )]
+    write_manifest: Option<(String, String)>,
+
+    /// After deleting, also delete folder markers (zero-byte objects ending in `/`) left
+    /// without any backups within them.
+    #[structopt(long)]
+    prune_empty_markers: bool,
+
+    /// Serial number or ARN of the MFA device to authenticate deletes with, for buckets
+    /// with MFA delete enabled. Requires `--mfa_token`.
+    #[structopt(long, requires = "mfa_token")]
+    mfa_serial: Option<String>,
+
+    /// Current code of the MFA device given by `--mfa_serial`.
+    #[structopt(long, requires = "mfa_serial")]
+    mfa_token: Option<String>,
+
+    /// Stop sending delete requests once the run has taken this many seconds, e.g. to
+    /// finish before cron kills the job. The request in flight is finished, and the
+    /// oldest expendable backups are deleted first, leaving the newest ones for the next
+    /// run.
+    #[structopt(long)]
+    time_budget: Option<u64>,
+
+    /// Check each backup to be kept still exists and its content hasn't changed since
+    /// listing, by requesting its metadata. Aborts without deleting anything, if any of
+    /// them fails the check.
+    #[structopt(long)]
+    verify_kept: bool,
+
+    /// Allow deleting the newest backup. Otherwise, runs abort without deleting
+    /// anything, if the newest backup is expendable, as that's most likely due to a
+    /// misconfigured strategy or reference time. For `--targets` and several regions,
+    /// only the offending ones are left as they are.
+    #[structopt(long)]
+    allow_delete_newest: bool,
+
+    /// Delete the backups of this plan written by `plan --out`, instead of evaluating
+    /// the retention policy again. Backups that have changed since planning are kept.
+    #[structopt(long, parse(from_os_str), conflicts_with = "incremental")]
+    plan: Option<PathBuf>,
+
+    /// Abort without deleting anything, unless the expendable backups match this token
+    /// printed by `plan`, i.e. they are exactly the ones that have been reviewed.
+    #[structopt(long)]
+    approval_token: Option<String>,
+
+    /// Abort without deleting anything, unless the expendable backups total `MIN..MAX`
+    /// bytes (inclusive, e.g. `3G..5G`), i.e. about as much as has been approved.
+    #[structopt(long, parse(try_from_str = "parse_bytes_range"))]
+    expect_bytes_freed: Option<(u64, u64)>,
+
+    /// Prune each of the buckets listed by this TOML file, instead of the one given by
+    /// the other options, which serve as defaults for what the targets leave out. Doesn't
+    /// ask for confirmation, so requires `--skip_confirmation`.
+    #[structopt(
+        long,
+        parse(from_os_str),
+        requires = "skip_confirmation",
+        raw(conflicts_with_all = r#"&["plan", "dry_run", "emit_script", "approval_token", "expect_bytes_freed", "verify_kept"]"#)
+    )]
+    targets: Option<PathBuf>,
+
+    /// Delete exactly the backups whose ids are read from stdin (one per line, skipping
+    /// blank lines and comments), instead of evaluating the retention policy, e.g. for
+    /// cleanups decided by other systems. Ids that aren't among the stored backups are
+    /// warned about and skipped. Requires `--skip_confirmation`, as stdin can't be used
+    /// to confirm then.
+    #[structopt(
+        long,
+        requires = "skip_confirmation",
+        raw(conflicts_with_all = r#"&["plan", "interactive", "targets", "incremental"]"#)
+    )]
+    delete_ids_from_stdin: bool,
+
+    /// Number of `--targets` (or regions) pruned at the same time.
+    #[structopt(long, default_value = "1", parse(try_from_str = "parse_concurrency"))]
+    concurrency: usize,
+}
+
+fn parse_date(date: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(date_time) = date.parse::<DateTime<Utc>>() {
+        return Ok(date_time);
+    }
+
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(|date| DateTime::from_utc(date.and_hms(0, 0, 0), Utc))
+        .map_err(|_| format!("'{}' is neither an RFC 3339 timestamp nor a date (YYYY-MM-DD)", date))
+}
+
+/// Parses `YYYY-MM` into midnight UTC of the month's first day.
+fn parse_month(month: &str) -> Result<DateTime<Utc>, String> {
+    NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+        .map(|date| DateTime::<Utc>::from_utc(date.and_hms(0, 0, 0), Utc))
+        .map_err(|_| format!("'{}' is not a month of the form YYYY-MM", month))
+}
+
+/// Parses `YYYY` into midnight UTC of the year's first day.
+fn parse_year(year: &str) -> Result<DateTime<Utc>, String> {
+    year.parse::<i32>()
+        .ok()
+        .and_then(|year| NaiveDate::from_ymd_opt(year, 1, 1))
+        .map(|date| DateTime::<Utc>::from_utc(date.and_hms(0, 0, 0), Utc))
+        .ok_or_else(|| format!("'{}' is not a year of the form YYYY", year))
+}
+
+/// Parses durations like `90s`, `30m`, `36h`, `2d` or `1w`.
+fn parse_duration(duration: &str) -> Result<Duration, String> {
+    let invalid = || format!("'{}' is not a duration, such as 36h or 2d", duration);
+    let (amount, unit) = duration.split_at(duration.char_indices().last().map_or(0, |(index, _)| index));
+    let amount = amount.parse::<i64>().map_err(|_| invalid())?;
+
+    match unit {
+        "s" => Ok(Duration::seconds(amount)),
+        "m" => Ok(Duration::minutes(amount)),
+        "h" => Ok(Duration::hours(amount)),
+        "d" => Ok(Duration::days(amount)),
+        "w" => Ok(Duration::weeks(amount)),
+        _ => Err(invalid()),
+    }
+}
+
+fn parse_tier(tier: &str) -> Result<(String, pruning_strategy::TierRetention), String> {
+    match tier.find('=') {
+        Some(index) if index > 0 => {
+            let retention = match &tier[index + 1..] {
+                "forever" => pruning_strategy::TierRetention::Forever,
+                duration => pruning_strategy::TierRetention::For(parse_duration(duration)?),
+            };
+
+            Ok((String::from(&tier[..index]), retention))
+        },
+        _ => Err(String::from("Expected TIER=RETENTION, e.g. `daily=7d`")),
+    }
+}
+
+fn parse_bytes(bytes: &str) -> Result<u64, String> {
+    let invalid = || format!("'{}' is not a size, such as 500M or 1G", bytes);
+    let digits = bytes.find(|character: char| !character.is_ascii_digit()).unwrap_or(bytes.len());
+    let amount = bytes[..digits].parse::<u64>().map_err(|_| invalid())?;
+    let factor: u64 = match &bytes[digits..] {
+        "" | "B" => 1,
+        "K" | "KiB" => 1 << 10,
+        "M" | "MiB" => 1 << 20,
+        "G" | "GiB" => 1 << 30,
+        "T" | "TiB" => 1 << 40,
+        _ => return Err(invalid()),
+    };
+
+    amount.checked_mul(factor).ok_or_else(invalid)
+}
+
+fn parse_bytes_range(range: &str) -> Result<(u64, u64), String> {
+    let mut sizes = range.splitn(2, "..");
+    let min = parse_bytes(sizes.next().unwrap())?;
+    let max = match sizes.next() {
+        Some(max) => parse_bytes(max)?,
+        None => return Err(format!("'{}' is not of the form MIN..MAX", range)),
+    };
+
+    if min > max {
+        return Err(format!("'{}' ends before it starts", range));
+    }
+
+    Ok((min, max))
+}
+
+fn parse_page_size(page_size: &str) -> Result<usize, String> {
+    match page_size.parse::<usize>() {
+        Ok(page_size) if (1..=storage_client::AwsS3::MAX_KEYS_PER_REQUEST).contains(&page_size) => Ok(page_size),
+        _ => Err(format!("'{}' is not a number from 1 to 1000", page_size)),
+    }
+}
+
+fn parse_concurrency(concurrency: &str) -> Result<usize, String> {
+    match concurrency.parse::<usize>() {
+        Ok(concurrency) if concurrency > 0 => Ok(concurrency),
+        _ => Err(String::from("Must be at least 1")),
+    }
+}
+
+fn parse_tag(tag: &str) -> Result<(String, String), String> {
+    match tag.find('=') {
+        Some(index) if index > 0 => Ok((String::from(&tag[..index]), String::from(&tag[index + 1..]))),
+        _ => Err(format!("'{}' is not of the form KEY=VALUE", tag)),
+    }
+}
+
+fn parse_s3_url(url: &str) -> Result<(String, String), String> {
+    let error = || format!("'{}' is not of the form s3://BUCKET/KEY", url);
+    let path = url.strip_prefix("s3://").ok_or_else(error)?;
+
+    match path.find('/') {
+        Some(index) if index > 0 && index + 1 < path.len() => Ok((String::from(&path[..index]), String::from(&path[index + 1..]))),
+        _ => Err(error()),
+    }
+}
+
+/// Returns the region, bucket and prefix of the backups at `url`.
+fn parse_target(url: &str) -> Result<(String, String, String), String> {
+    match storage_client::StorageUrl::parse(url)? {
+        storage_client::StorageUrl::AwsS3 { region, bucket, prefix } => Ok((region.unwrap_or_else(|| String::from("auto")), bucket, prefix)),
+        _ => Err(format!("'{}' is not an s3:// URL, which is the only kind supported so far", url)),
+    }
+}
+
+fn parse_date_range(range: &str) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+    let mut dates = range.splitn(2, "..");
+    let start = parse_date(dates.next().unwrap())?;
+    let end = match dates.next() {
+        Some(end) if end.parse::<DateTime<Utc>>().is_err() => {
+            // A date includes the whole day.
+            parse_date(end)? + Duration::days(1) - Duration::nanoseconds(1)
+        },
+        Some(end) => parse_date(end)?,
+        None => return Err(format!("'{}' is not of the form START..END", range)),
+    };
+
+    if start > end {
+        return Err(format!("'{}' ends before it starts", range));
+    }
+
+    Ok((start, end))
+}
+
+/// Fails, if `input` isn't a terminal, e.g. when run by cron, as asking for confirmation
+/// would block with nobody there to answer. Only the input the answer is read from
+/// (stdin) is checked, as output redirected elsewhere (e.g. piped into `tee`) still
+/// leaves someone at the terminal to answer.
+fn check_confirmable(input: &impl IsTerminal) -> Result<(), String> {
+    if input.is_terminal() {
+        Ok(())
+    }
+    else {
+        Err(String::from("Not deleting anything, as there is no terminal to confirm on. Pass --skip_confirmation (-y) to delete without confirmation."))
+    }
+}
+
+/// Waits for the user to confirm by entering `y` on `input`. Waiting longer than
+/// `timeout` counts as not confirming.
+fn confirmed<R: BufRead + Send + 'static>(input: R, timeout: Option<std::time::Duration>) -> bool {
+    let (sender, receiver) = mpsc::channel();
+
+    // Reading can't be interrupted, so it's done in a thread that is left behind on timeout.
+    thread::spawn(move || {
+        let answer = input.lines().next().map(|line| line.map(|line| line == "y").unwrap_or(false));
+        let _ = sender.send(answer.unwrap_or(false));
+    });
+
+    match timeout {
+        Some(timeout) => match receiver.recv_timeout(timeout) {
+            Ok(answer) => answer,
+            Err(_) => {
+                eprintln!("No answer within {} seconds.", timeout.as_secs());
+                false
+            },
+        },
+        None => receiver.recv().unwrap_or(false),
+    }
+}
+
+/// Writes `contents` to a temporary file first and moves it to `path` afterwards,
+/// so readers never see a partially written file.
+fn write_atomically(path: &PathBuf, contents: String) -> io::Result<()> {
+    let mut temporary_path = path.clone().into_os_string();
+    temporary_path.push(".tmp");
+
+    fs::write(&temporary_path, contents)?;
+    fs::rename(&temporary_path, path)
+}
+
+fn print_stats(backups: &[BackupFileMeta]) {
+    for (period, name) in &[(Period::Day, "day"), (Period::Week, "week"), (Period::Month, "month"), (Period::Year, "year")] {
+        println!("Backups per {}:", name);
+
+        for (key, backups) in reporting::group_by_period(backups, *period) {
+            println!("  {}: {}", key, backups.len());
+        }
+    }
+}
+
+fn print_coverage(kept_backups: &[BackupFileMeta]) {
+    let coverage = reporting::coverage(kept_backups);
+    let (oldest, newest) = match (coverage.oldest, coverage.newest) {
+        (Some(oldest), Some(newest)) => (oldest, newest),
+        _ => {
+            println!("No backups are kept.");
+            return;
+        },
+    };
+
+    println!(
+        "Kept backups cover {} to {}, with gaps of up to {} days.",
+        oldest.format("%Y-%m-%d"),
+        newest.format("%Y-%m-%d"),
+        coverage.max_gap.num_days()
+    );
+    println!("Kept backups per month:");
+    for (key, count) in &coverage.per_month_counts {
+        println!("  {}: {}", key, count);
+    }
+}
+
+/// Prints each phase's duration, along with the number of objects it handled per second.
+fn print_timings(summary: &PruneSummary) {
+    let timings = &summary.timings;
+    for (phase, duration, objects) in &[
+        ("Listing", timings.listing, summary.total_found),
+        ("Evaluation", timings.evaluation, summary.total_found),
+        ("Deletion", timings.deletion, summary.deleted + summary.failed),
+    ] {
+        let seconds = duration.as_secs_f64();
+        if seconds > 0.0 {
+            println!("{}: {:.3}s ({:.0} objects/s)", phase, seconds, *objects as f64 / seconds);
+        }
+        else {
+            println!("{}: {:.3}s", phase, seconds);
+        }
+    }
+}
+
+/// Merges the options given on the command line with the ones from the config file.
+fn merged_config(opt: &Opt) -> Result<Config, String> {
+    let file_config = match &opt.config_file {
+        Some(path) => Config::from_file(path)?,
+        None => Config::default(),
+    };
+
+    let config = match &opt.target {
+        Some((region, bucket, prefix)) => Config {
+            region: vec![region.clone()],
+            bucket: Some(bucket.clone()),
+            prefix: Some(prefix.clone()),
+            ..opt.config.clone()
+        },
+        None => opt.config.clone(),
+    };
+
+    Ok(config.or(file_config))
+}
+
+fn settings(opt: &Opt) -> Result<Settings, String> {
+    merged_config(opt)?.into_settings()
+}
+
+/// Returns the settings of `--compare_strategy`: The retention options are taken from
+/// the file at `path` only, where to find the backups from the other options.
+fn candidate_settings(opt: &Opt, path: &Path) -> Result<Settings, String> {
+    let config = merged_config(opt)?;
+
+    Config {
+        region: config.region,
+        bucket: config.bucket,
+        prefix: config.prefix,
+        ..Config::from_file(path)?
+    }.into_settings()
+}
+
+fn print_comparison(comparison: &comparison::StrategyComparison, candidate: &str, total: usize) {
+    println!("Kept by the given options, but deleted by {}:", candidate);
+    for backup in &comparison.kept_only_by_first {
+        println!("  {}", backup.human_readable_id);
+    }
+    println!("Kept by {}, but deleted by the given options:", candidate);
+    for backup in &comparison.kept_only_by_second {
+        println!("  {}", backup.human_readable_id);
+    }
+    println!(
+        "The given options would delete {} of {} backups, {} would delete {}.",
+        comparison.deleted_by_first,
+        total,
+        candidate,
+        comparison.deleted_by_second
+    );
+}
+
+/// Builds the equivalent of restic's `--keep-daily`, `--keep-weekly`, `--keep-monthly`
+/// and `--keep-yearly`: A backup is kept, if any of the counts keeps it.
+fn per_period_strategy(daily: usize, weekly: usize, monthly: usize, yearly: usize) -> Box<dyn PruningStrategy> {
+    let strategies: Vec<Box<dyn PruningStrategy>> = vec![
+        Box::new(pruning_strategy::KeepNewestPerPeriod::new(Period::Day, daily)),
+        Box::new(pruning_strategy::KeepNewestPerPeriod::new(Period::Week, weekly)),
+        Box::new(pruning_strategy::KeepNewestPerPeriod::new(Period::Month, monthly)),
+        Box::new(pruning_strategy::KeepNewestPerPeriod::new(Period::Year, yearly)),
+    ];
+
+    Box::new(pruning_strategy::CompositeStrategy::new(pruning_strategy::Combinator::And, strategies))
+}
+
+/// Builds the strategy described by the options, evaluating ages relative to
+/// `reference_time`.
+fn build_pruning_strategy(
+    opt: &Opt,
+    settings: &Settings,
+    reference_time: DateTime<Utc>,
+) -> Result<Box<dyn PruningStrategy>, StrategyConfigError> {
+    let mut pruning_strategy: Box<dyn PruningStrategy> = match settings.retention {
+        Retention::Windows { keep_all_within, one_per_month_within, one_per_month_tolerance } => Box::new(
+            pruning_strategy::OlderThanButKeepOnePerMonth::try_new(
+                reference_time,
+                Duration::days(keep_all_within as i64),
+                Duration::days(one_per_month_tolerance as i64),
+                Duration::days(one_per_month_within as i64),
+            )?
+        ),
+        Retention::PerPeriod { daily, weekly, monthly, yearly } => per_period_strategy(daily, weekly, monthly, yearly),
+    };
+    if !opt.tier.is_empty() {
+        pruning_strategy = Box::new(pruning_strategy::RespectTierTag::new(
+            pruning_strategy,
+            reference_time,
+            opt.tier.iter().cloned().collect(),
+        ));
+    }
+    if opt.success_sidecar.is_some() {
+        pruning_strategy = Box::new(pruning_strategy::DropUnsuccessful::new(pruning_strategy));
+    }
+    if opt.retain_until_metadata.is_some() {
+        pruning_strategy = Box::new(pruning_strategy::RespectRetainUntil::new(pruning_strategy, reference_time));
+    }
+    if !opt.quarantine.is_empty() {
+        pruning_strategy = Box::new(pruning_strategy::Quarantine::try_new(pruning_strategy, opt.quarantine.clone())?);
+    }
+    if !opt.skip_storage_class.is_empty() {
+        pruning_strategy = Box::new(pruning_strategy::SkipStorageClass::new(pruning_strategy, opt.skip_storage_class.clone()));
+    }
+    if let Some(n) = settings.always_keep_latest {
+        pruning_strategy = Box::new(pruning_strategy::AlwaysKeepLatest::new(pruning_strategy, n));
+    }
+    if let Some(month) = opt.only_month {
+        pruning_strategy = Box::new(pruning_strategy::OnlyPeriod::month(pruning_strategy, month));
+    }
+    if let Some(year) = opt.only_year {
+        pruning_strategy = Box::new(pruning_strategy::OnlyPeriod::year(pruning_strategy, year));
+    }
+    if let Some(max_bytes) = opt.max_delete_bytes {
+        pruning_strategy = Box::new(pruning_strategy::MaxDeleteBytes::new(pruning_strategy, max_bytes));
+    }
+
+    Ok(pruning_strategy)
+}
+
+/// Reads the snapshot written by the previous `--incremental` run. Returns `None` to
+/// start from scratch, if there is none or it can't be read.
+fn read_snapshot(path: &Path) -> Option<pruning_strategy::Snapshot> {
+    let contents = fs::read_to_string(path).ok()?;
+
+    toml::from_str(&contents)
+        .map_err(|error| eprintln!("Ignoring the invalid snapshot {}: {}", path.display(), error))
+        .ok()
+}
+
+/// Returns a client for another `bucket` than the one containing the backups, e.g. the
+/// one holding the manifest. It's assumed to be located in the same region.
+fn client_for_bucket(settings: &Settings, bucket: &str) -> Result<storage_client::AwsS3, StorageError> {
+    if settings.region == "auto" {
+        storage_client::AwsS3::new_autodetect(String::from(bucket), String::new())
+    }
+    else {
+        Ok(storage_client::AwsS3::new(settings.region.clone(), String::from(bucket), String::new()))
+    }
+}
+
+/// Describes the retention policy, for receipts.
+fn describe_strategy(settings: &Settings) -> String {
+    let mut description = match settings.retention {
+        Retention::Windows { keep_all_within, one_per_month_within, one_per_month_tolerance } => format!(
+            "keep_all_within={}d, one_per_month_within={}d, one_per_month_tolerance={}d",
+            keep_all_within,
+            one_per_month_within,
+            one_per_month_tolerance
+        ),
+        Retention::PerPeriod { daily, weekly, monthly, yearly } => format!(
+            "keep_daily={}, keep_weekly={}, keep_monthly={}, keep_yearly={}",
+            daily,
+            weekly,
+            monthly,
+            yearly
+        ),
+    };
+    if let Some(n) = settings.always_keep_latest {
+        description.push_str(&format!(", always_keep_latest={}", n));
+    }
+
+    description
+}
+
+/// Writes a receipt of the deletions in `deletion_report` to `key_prefix` in `bucket`.
+/// Only warns on failure.
+fn write_receipt(settings: &Settings, deletion_report: &storage_client::DeletionReport, bucket: &str, key_prefix: &str) {
+    let receipt = Receipt::new(deletion_report, describe_strategy(settings), Utc::now());
+    let key = receipt.key(key_prefix);
+
+    match client_for_bucket(settings, bucket).and_then(|storage_client| storage_client.put_object(&key, receipt.to_json())) {
+        Ok(()) => info!("Wrote a receipt to s3://{}/{}.", bucket, key),
+        Err(error) => eprintln!("Could not write the receipt to s3://{}/{}: {}", bucket, key, error),
+    }
+}
+
+/// Writes a manifest of the `remaining_backups` to `key` in `bucket`, only warning on
+/// failure, like `write_receipt`.
+fn write_inventory(settings: &Settings, remaining_backups: &[BackupFileMeta], bucket: &str, key: &str) {
+    let inventory = manifest::inventory(remaining_backups, Utc::now());
+
+    match client_for_bucket(settings, bucket).and_then(|storage_client| storage_client.put_object(key, inventory)) {
+        Ok(()) => info!("Wrote a manifest of {} backups to s3://{}/{}.", remaining_backups.len(), bucket, key),
+        Err(error) => eprintln!("Could not write the manifest to s3://{}/{}: {}", bucket, key, error),
+    }
+}
+
+/// Returns the ids referenced by the manifest stored under `key` in `bucket`. Exits, if
+/// it can't be read, rather than risking to delete referenced backups.
+fn manifest_ids(settings: &Settings, bucket: &str, key: &str) -> Vec<String> {
+    let contents = client_for_bucket(settings, bucket)
+        .and_then(|storage_client| storage_client.object_contents(key))
+        .unwrap_or_else(|error| exit_with(exit_code(&error), &format!("Could not read the manifest: {} Not pruning.", error)));
+
+    manifest::referenced_ids(&contents).unwrap_or_else(|error| exit_with_error(&format!("{} Not pruning.", error)))
+}
+
+/// Returns the ids listed one per line in `contents` (e.g. of the `--in_use_file` or
+/// read for `--delete_ids_from_stdin`), skipping blank lines and comments.
+fn parse_in_use_ids(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect()
+}
+
+fn in_use_ids(path: &Path) -> Vec<String> {
+    let contents = fs::read_to_string(path)
+        .unwrap_or_else(|error| exit_with_error(&format!("Could not read {}: {} Not pruning.", path.display(), error)));
+
+    parse_in_use_ids(&contents)
+}
+
+/// Returns a strategy considering exactly the backups with the given `ids` expendable.
+fn listed_ids_strategy(ids: Vec<String>) -> pruning_strategy::CustomStrategy {
+    let ids: HashSet<String> = ids.into_iter().collect();
+
+    pruning_strategy::CustomStrategy::new(Box::new(move |backup, _| !ids.contains(&backup.id)))
+}
+
+/// Returns the `ids` that aren't among the `stored_backups`, e.g. as they're mistyped.
+fn missing_ids<'a>(ids: &'a [String], stored_backups: &[BackupFileMeta]) -> Vec<&'a str> {
+    let stored_ids: HashSet<&str> = stored_backups.iter().map(|backup| backup.id.as_str()).collect();
+
+    ids.iter().map(String::as_str).filter(|id| !stored_ids.contains(id)).collect()
+}
+
+/// Fails, if the newest of the `backups` is older than `max_staleness`, or if there
+/// are no backups at all.
+fn check_staleness(backups: &[BackupFileMeta], reference_time: DateTime<Utc>, max_staleness: Duration) -> Result<(), String> {
+    match backups.iter().map(|backup| backup.date).max() {
+        Some(newest) if reference_time.signed_duration_since(newest) <= max_staleness => Ok(()),
+        Some(newest) => Err(format!("The newest backup is from {}, backups seem to have stopped arriving.", newest.to_rfc3339())),
+        None => Err(String::from("There are no backups at all.")),
+    }
+}
+
+/// Fails, if `token` wasn't printed by `plan` for exactly the `expendable_backups`.
+fn check_approval_token(token: &str, expendable_backups: &[BackupFileMeta]) -> Result<(), String> {
+    let expected = plan::approval_token(expendable_backups);
+
+    if token.trim().eq_ignore_ascii_case(&expected) {
+        Ok(())
+    }
+    else {
+        Err(format!(
+            "The approval token {} doesn't match the expendable backups (token {}), they have changed since planning.",
+            token.trim(),
+            expected
+        ))
+    }
+}
+
+/// Fails, if the `expendable_backups` don't total `min..=max` bytes, e.g. as the stored
+/// backups have changed materially since the deletion has been approved.
+fn check_bytes_freed((min, max): (u64, u64), expendable_backups: &[BackupFileMeta]) -> Result<(), String> {
+    let total: u64 = expendable_backups.iter().map(|backup| backup.size).sum();
+
+    if (min..=max).contains(&total) {
+        Ok(())
+    }
+    else {
+        Err(format!(
+            "The expendable backups total {}, which is outside the expected {}..{}.",
+            interactive::format_bytes(total),
+            interactive::format_bytes(min),
+            interactive::format_bytes(max)
+        ))
+    }
+}
+
+/// Fails, if the newest backup is among the `expendable_backups`, i.e. none of the
+/// `kept_backups` is dated as late.
+fn check_newest_kept(kept_backups: &[BackupFileMeta], expendable_backups: &[BackupFileMeta]) -> Result<(), String> {
+    let newest_kept = kept_backups.iter().map(|backup| backup.date).max();
+
+    match (expendable_backups.iter().max_by_key(|backup| backup.date), newest_kept) {
+        (Some(newest), Some(newest_kept)) if newest_kept >= newest.date => Ok(()),
+        (Some(newest), _) => Err(format!(
+            "The newest backup {} would be deleted, which is most likely a mistake. Pass --allow_delete_newest, if it isn't.",
+            newest.human_readable_id
+        )),
+        (None, _) => Ok(()),
+    }
+}
+
+/// Fails, if any of the `backups` is dated after `reference_time` by more than a few
+/// minutes.
+fn check_clock(backups: &[BackupFileMeta], reference_time: DateTime<Utc>) -> Result<(), String> {
+    let future_backups = detect_future_backups(backups, reference_time, Duration::minutes(CLOCK_SKEW_TOLERANCE_MINUTES));
+
+    match future_backups.iter().max_by_key(|backup| backup.date) {
+        Some(newest) => Err(format!(
+            "{} backups are dated after {}, up to {} ({}). Check the clocks.",
+            future_backups.len(),
+            reference_time.to_rfc3339(),
+            newest.date.to_rfc3339(),
+            newest.human_readable_id
+        )),
+        None => Ok(()),
+    }
+}
+
+fn exit_with_error(message: &str) -> ! {
+    exit_with(1, message);
+}
+
+fn exit_with(code: i32, message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(code);
+}
+
+fn exit_code(error: &StorageError) -> i32 {
+    match error {
+        StorageError::AccessDenied(_) | StorageError::Authentication(_) => EXIT_AUTHENTICATION_FAILURE,
+        StorageError::Network(_) => EXIT_NETWORK_FAILURE,
+        StorageError::BucketNotFound(_) => EXIT_BUCKET_NOT_FOUND,
+        StorageError::Request(_) | StorageError::TooManyObjects(..) => 1,
+    }
+}
+
+/// Inserts `apply` as subcommand, if none is given, so invocations predating the
+/// subcommands keep working.
+fn with_default_subcommand(mut args: Vec<OsString>) -> Vec<OsString> {
+    let has_subcommand = args.get(1).is_some_and(|argument| {
+        ["list", "plan", "apply", "help", "-h", "--help", "-V", "--version"].iter().any(|known| argument == known)
+    });
+    if !has_subcommand {
+        args.insert(1.min(args.len()), OsString::from("apply"));
+    }
+
+    args
+}
+
+fn main() {
+    match Command::from_iter(with_default_subcommand(std::env::args_os().collect())) {
+        Command::List(opt) => list(&opt),
+        Command::Plan(plan_opt) => plan(&plan_opt),
+        Command::Apply(apply_opt) => apply(&apply_opt),
+    }
+}
+
+/// Returns the client for the bucket containing the backups, configured by the
+/// options shared by all subcommands.
+fn storage_client(opt: &Opt, settings: &Settings) -> storage_client::AwsS3 {
+    try_storage_client(opt, settings).unwrap_or_else(|error| exit_with(exit_code(&error), &error.to_string()))
+}
+
+/// Like `storage_client`, but fails instead of exiting, if the region can't be
+/// detected.
+fn try_storage_client(opt: &Opt, settings: &Settings) -> Result<storage_client::AwsS3, StorageError> {
+    let mut storage_client = if settings.region == "auto" {
+        storage_client::AwsS3::new_autodetect(settings.bucket.clone(), settings.prefix.clone())?
+    }
+    else {
+        storage_client::AwsS3::new(
+            settings.region.clone(),
+            settings.bucket.clone(),
+            settings.prefix.clone()
+        )
+    };
+    if let Some(page_size) = opt.page_size {
+        storage_client = storage_client.with_page_size(page_size);
+    }
+    if let Some(suffix) = &opt.success_sidecar {
+        storage_client = storage_client.with_success_sidecar(suffix.clone());
+    }
+    if let Some(key) = &opt.retain_until_metadata {
+        storage_client = storage_client.with_retain_until_metadata(key.clone());
+    }
+    if let Some(key) = &opt.tier_tag {
+        storage_client = storage_client.with_tier_tag(key.clone());
+    }
+    if let Some(max_list) = opt.max_list {
+        storage_client = storage_client.with_max_list(max_list);
+    }
+    if let Some(key) = &opt.start_after {
+        storage_client = storage_client.with_start_after(key.clone());
+    }
+    if !opt.companion.is_empty() {
+        storage_client = storage_client.with_companions(opt.companion.clone());
+    }
+    if !opt.protect_key.is_empty() {
+        storage_client = storage_client.with_protected_keys(opt.protect_key.clone());
+    }
+
+    Ok(storage_client)
+}
+
+/// Builds the strategy described by the options like `build_pruning_strategy`, also
+/// protecting the backups of `--manifest` and `--in_use_file` and evaluating
+/// incrementally. Exits, if the options are invalid.
+fn full_pruning_strategy(opt: &Opt, settings: &Settings, reference_time: DateTime<Utc>) -> Box<dyn PruningStrategy> {
+    let pruning_strategy = build_pruning_strategy(opt, settings, reference_time)
+        .unwrap_or_else(|error| exit_with_error(&error.to_string()));
+    let manifest_ids = opt.manifest.as_ref().map(|(bucket, key)| manifest_ids(settings, bucket, key));
+
+    with_safeguards(opt, pruning_strategy, manifest_ids, reference_time)
+}
+
+/// Wraps `pruning_strategy` to keep the backups referenced by the `--manifest` (whose
+/// `manifest_ids` have been read already) and the `--in_use_file`, and to only evaluate
+/// the backups changed since the last `--incremental` run, if given.
+fn with_safeguards(
+    opt: &Opt,
+    mut pruning_strategy: Box<dyn PruningStrategy>,
+    manifest_ids: Option<Vec<String>>,
+    reference_time: DateTime<Utc>,
+) -> Box<dyn PruningStrategy> {
+    if let Some(manifest_ids) = manifest_ids {
+        pruning_strategy = Box::new(pruning_strategy::Pin::new(pruning_strategy, manifest_ids));
+    }
+    pruning_strategy = pin_in_use(opt, pruning_strategy);
+    if let Some(path) = &opt.incremental {
+        let snapshot = read_snapshot(path);
+        let previously_kept = snapshot.as_ref().map(|snapshot| snapshot.kept.len());
+        pruning_strategy = Box::new(pruning_strategy::Incremental::new(pruning_strategy, snapshot.unwrap_or_default(), reference_time));
+
+        if let Some(max_reduction_pct) = opt.max_reduction_pct {
+            // Without a previous run, the stored backups are the baseline.
+            let max_reduction = match previously_kept {
+                Some(previously_kept) => pruning_strategy::MaxReduction::try_new(pruning_strategy, previously_kept, max_reduction_pct),
+                None => pruning_strategy::MaxReduction::try_relative_to_stored(pruning_strategy, max_reduction_pct),
+            };
+            pruning_strategy = Box::new(max_reduction.unwrap_or_else(|error| exit_with_error(&error.to_string())));
+        }
+    }
+
+    pruning_strategy
+}
+
+/// Wraps `pruning_strategy` to keep the backups of `--in_use_file`, if given.
+fn pin_in_use(opt: &Opt, pruning_strategy: Box<dyn PruningStrategy>) -> Box<dyn PruningStrategy> {
+    match &opt.in_use_file {
+        Some(path) => Box::new(pruning_strategy::Pin::new(pruning_strategy, in_use_ids(path))),
+        None => pruning_strategy,
+    }
+}
+
+/// Lists the stored backups, taking them from `--cache_listing` if `use_cached`. Exits,
+/// if they can't be listed or are stale.
+fn list_backups(
+    opt: &Opt,
+    storage_client: &storage_client::AwsS3,
+    reference_time: DateTime<Utc>,
+    use_cached: bool,
+    summary: &mut PruneSummary,
+) -> Vec<BackupFileMeta> {
+    let listing = CheckedListing { opt, storage_client, reference_time, use_cached };
+
+    let started = Instant::now();
+    let stored_backups = listing.stored_backups().unwrap_or_else(|error| exit_with(exit_code(&error), &error.to_string()));
+    summary.timings.listing = started.elapsed();
+    summary.total_found = stored_backups.len();
+    summary.kept = stored_backups.len();
+
+    stored_backups
+}
+
+/// The backups of `storage_client`, listed as by `list_backups` for pruning them with
+/// `backups_cleaner::prune_selected`. Exits, if pruning them has to be aborted, see
+/// `check_listed_backups`.
+struct CheckedListing<'a> {
+    opt: &'a Opt,
+    storage_client: &'a storage_client::AwsS3,
+    reference_time: DateTime<Utc>,
+    use_cached: bool,
+}
+
+impl StorageClient for CheckedListing<'_> {
+
+    fn stored_backups(&self) -> Result<Vec<BackupFileMeta>, StorageError> {
+        let mut stored_backups = match &self.opt.cache_listing {
+            Some(path) => ListingCache::new(path.clone(), self.opt.cache_ttl).stored_backups(self.storage_client, Utc::now(), self.use_cached),
+            None => self.storage_client.stored_backups(),
+        }?;
+        info!("Found {} backups.", stored_backups.len());
+
+        match check_listed_backups(self.opt, &mut stored_backups, self.reference_time) {
+            Ok(Some(warning)) => eprintln!("Warning: {}", warning),
+            Ok(None) => {},
+            Err((code, error)) => exit_with(code, &error),
+        }
+
+        Ok(stored_backups)
+    }
+
+    fn delete_backups(&self, backups: Vec<BackupFileMeta>) -> storage_client::DeletionReport {
+        self.storage_client.delete_backups(backups)
+    }
+}
+
+/// Redacts the `listed_backups` for `--redact`, before checking them for staleness and
+/// skewed clocks, so the messages don't tell their names either. Returns a warning, if
+/// the clocks seem skewed, or the exit code and error, if pruning has to be aborted.
+fn check_listed_backups(
+    opt: &Opt,
+    listed_backups: &mut [BackupFileMeta],
+    reference_time: DateTime<Utc>,
+) -> Result<Option<String>, (i32, String)> {
+    if opt.redact {
+        listed_backups.iter_mut().for_each(BackupFileMeta::redact);
+    }
+
+    if let Some(max_staleness) = opt.max_staleness {
+        check_staleness(listed_backups, reference_time, max_staleness)
+            .map_err(|error| (EXIT_STALE_BACKUPS, format!("{} Not pruning.", error)))?;
+    }
+
+    match check_clock(listed_backups, reference_time) {
+        Err(error) if opt.strict_clock => Err((1, format!("{} Not pruning.", error))),
+        Err(error) => Ok(Some(error)),
+        Ok(()) => Ok(None),
+    }
+}
+
+/// Returns the backups `pruning_strategy` considers expendable, removing them from
+/// `stored_backups`. Audits the kept ones for `--audit_kept` as kept by the `strategy`
+/// described.
+fn evaluate(
+    opt: &Opt,
+    pruning_strategy: &dyn PruningStrategy,
+    strategy: &str,
+    stored_backups: &mut Vec<BackupFileMeta>,
+    summary: &mut PruneSummary,
+) -> Vec<BackupFileMeta> {
+    let started = Instant::now();
+    let expendable_backups = pruning_strategy.expendable_backups(stored_backups);
+    summary.timings.evaluation = started.elapsed();
+    summary.kept = stored_backups.len();
+    report_evaluation(opt, pruning_strategy, strategy, stored_backups, &expendable_backups);
+
+    expendable_backups
+}
+
+/// Writes what `--explain_backup`, `--coverage` and `--audit_kept` ask to on how
+/// `pruning_strategy` has split the backups into `kept_backups` and
+/// `expendable_backups`.
+fn report_evaluation(
+    opt: &Opt,
+    pruning_strategy: &dyn PruningStrategy,
+    strategy: &str,
+    kept_backups: &[BackupFileMeta],
+    expendable_backups: &[BackupFileMeta],
+) {
+    if let Some(id) = &opt.explain_backup {
+        println!("{}", explanation(id, pruning_strategy, kept_backups, expendable_backups));
+    }
+
+    if opt.coverage {
+        print_coverage(kept_backups);
+    }
+
+    if let Some(path) = &opt.audit_kept {
+        let kept_backups = kept_audit::kept_backups(pruning_strategy, kept_backups, expendable_backups, &opt.protect_key, strategy);
+        write_atomically(path, kept_audit::to_jsonl(&kept_backups))
+            .unwrap_or_else(|error| exit_with_error(&format!("Could not write {}: {}", path.display(), error)));
+    }
+}
+
+/// Remembers the `remaining_backups` of a run for the next `--incremental` run, so only
+/// runs that actually pruned are taken into account.
+fn save_snapshot(path: &PathBuf, remaining_backups: &[BackupFileMeta]) {
+    let snapshot = pruning_strategy::Snapshot::of(remaining_backups);
+    let stored = toml::to_string(&snapshot)
+        .map_err(|error| error.to_string())
+        .and_then(|contents| write_atomically(path, contents).map_err(|error| error.to_string()));
+    if let Err(error) = stored {
+        eprintln!("Could not write the snapshot to {}: {}", path.display(), error);
+    }
+}
+
+/// Describes whether the backup with `id` is kept or expendable and why, as far as
+/// `pruning_strategy` can tell.
+fn explanation(
+    id: &str,
+    pruning_strategy: &dyn PruningStrategy,
+    kept_backups: &[BackupFileMeta],
+    expendable_backups: &[BackupFileMeta],
+) -> String {
+    let all_backups: Vec<BackupFileMeta> = kept_backups.iter().chain(expendable_backups).cloned().collect();
+    let backup = match all_backups.iter().find(|backup| backup.id == id) {
+        Some(backup) => backup,
+        None => return format!("{} is not among the stored backups.", id),
+    };
+    let fate = if kept_backups.iter().any(|kept| kept.id == id) { "kept" } else { "expendable" };
+    let reason = pruning_strategy
+        .explain(backup, &all_backups)
+        .unwrap_or_else(|| String::from("The strategy doesn't tell why."));
+
+    format!("{} is {}. {}", backup.human_readable_id, fate, reason)
+}
+
+fn print_expendable_backups(expendable_backups: &[BackupFileMeta], number_of_kept_backups: usize) {
+    if expendable_backups.is_empty() {
+        info!("No expendible backups found.");
+        return;
+    }
+
+    for backup in expendable_backups {
+        info!("Would delete {}", backup.human_readable_id);
+    }
+    info!(
+        "Would delete {} of {} backups.",
+        expendable_backups.len(),
+        expendable_backups.len() + number_of_kept_backups
+    );
+}
+
+/// Writes the output requested by the options shared by all subcommands, and exits,
+/// if any backups failed to be deleted.
+fn finish(opt: &Opt, settings: &Settings, summary: &PruneSummary, metrics: &Metrics) {
+    if opt.timings {
+        print_timings(summary);
+    }
+
+    if let Some(metrics_out) = &opt.metrics_out {
+        write_atomically(metrics_out, metrics.to_prometheus(&settings.bucket, &settings.prefix))
+            .unwrap_or_else(|error| exit_with_error(&format!("Could not write {}: {}", metrics_out.display(), error)));
+    }
+
+    if let Some(summary_out) = &opt.summary_json {
+        let json = serde_json::to_string_pretty(summary).expect("The summary can always be serialized.");
+        write_atomically(summary_out, json)
+            .unwrap_or_else(|error| exit_with_error(&format!("Could not write {}: {}", summary_out.display(), error)));
+    }
+
+    if let Some(code) = deletion_exit_code(summary) {
+        process::exit(code);
+    }
+}
+
+/// Returns the code to exit with, if deleting some (`EXIT_PARTIAL_DELETION`) or all
+/// (`EXIT_DELETION_FAILED`) of the expendable backups failed.
+fn deletion_exit_code(summary: &PruneSummary) -> Option<i32> {
+    match (summary.deleted, summary.failed) {
+        (_, 0) => None,
+        (0, _) => Some(EXIT_DELETION_FAILED),
+        _ => Some(EXIT_PARTIAL_DELETION),
+    }
+}
+
+fn list(opt: &Opt) {
+    QUIET.store(opt.quiet, Ordering::Relaxed);
+
+    let settings = settings(opt).unwrap_or_else(|error| exit_with_error(&error));
+    let storage_client = storage_client(opt, &settings);
+    let reference_time = opt.reference_time.unwrap_or_else(Utc::now);
+
+    let mut summary = PruneSummary::default();
+    let mut stored_backups = list_backups(opt, &storage_client, reference_time, true, &mut summary);
+    stored_backups.sort_by_key(|backup| backup.date);
+
+    for backup in &stored_backups {
+        println!("{}  {:>10}  {}", backup.date.to_rfc3339(), interactive::format_bytes(backup.size), backup.human_readable_id);
+    }
+    print_stats(&stored_backups);
+
+    finish(opt, &settings, &summary, &Metrics::new(&stored_backups, &[]));
+}
+
+fn plan(plan_opt: &PlanOpt) {
+    let opt = &plan_opt.common;
+    QUIET.store(opt.quiet, Ordering::Relaxed);
+
+    let settings = settings(opt).unwrap_or_else(|error| exit_with_error(&error));
+    let reference_time = opt.reference_time.unwrap_or_else(Utc::now);
+
+    if plan_opt.emit_lifecycle {
+        let pruning_strategy = build_pruning_strategy(opt, &settings, reference_time)
+            .unwrap_or_else(|error| exit_with_error(&error.to_string()));
+        match lifecycle::lifecycle_configuration(pruning_strategy.as_ref(), &settings.prefix) {
+            Ok(lifecycle_configuration) => print!("{}", lifecycle_configuration),
+            Err(error) => exit_with_error(&error),
+        }
+        return;
+    }
+
+    let storage_client = storage_client(opt, &settings);
+    let pruning_strategy = full_pruning_strategy(opt, &settings, reference_time);
+    let candidate_strategy = plan_opt.compare_strategy.as_ref().map(|path| {
+        candidate_settings(opt, path)
+            .and_then(|settings| build_pruning_strategy(opt, &settings, reference_time).map_err(|error| error.to_string()))
+            .unwrap_or_else(|error| exit_with_error(&format!("Invalid strategy in {}: {}", path.display(), error)))
+    });
+
+    let mut summary = PruneSummary::default();
+    let mut stored_backups = list_backups(opt, &storage_client, reference_time, true, &mut summary);
+
+    if opt.stats {
+        print_stats(&stored_backups);
+    }
+
+    if let (Some(candidate_strategy), Some(path)) = (&candidate_strategy, &plan_opt.compare_strategy) {
+        let comparison = comparison::compare(&stored_backups, pruning_strategy.as_ref(), candidate_strategy.as_ref());
+        print_comparison(&comparison, &path.display().to_string(), stored_backups.len());
+        return;
+    }
+
+    let expendable_backups = evaluate(opt, pruning_strategy.as_ref(), &describe_strategy(&settings), &mut stored_backups, &mut summary);
+    print_expendable_backups(&expendable_backups, stored_backups.len());
+    println!("Approval token: {}", plan::approval_token(&expendable_backups));
+    let metrics = Metrics::new(&stored_backups, &expendable_backups);
+
+    if let Some(out) = &plan_opt.out {
+        let plan = plan::Plan {
+            created_at: Utc::now(),
+            bucket: settings.bucket.clone(),
+            prefix: settings.prefix.clone(),
+            expendable: expendable_backups,
+        };
+        write_atomically(out, plan.to_json())
+            .unwrap_or_else(|error| exit_with_error(&format!("Could not write {}: {}", out.display(), error)));
+        info!("Wrote the plan to {}.", out.display());
+    }
+
+    finish(opt, &settings, &summary, &metrics);
+}
+
+/// Reads the plan at `path`. Exits, if it can't be read or is meant for another bucket.
+fn read_plan(path: &Path, settings: &Settings) -> plan::Plan {
+    let plan = fs::read_to_string(path)
+        .map_err(|error| error.to_string())
+        .and_then(|contents| plan::Plan::from_json(&contents))
+        .unwrap_or_else(|error| exit_with_error(&format!("Could not read the plan {}: {} Not pruning.", path.display(), error)));
+
+    if plan.bucket != settings.bucket || plan.prefix != settings.prefix {
+        exit_with_error(&format!(
+            "The plan {} is meant for s3://{}/{}, not s3://{}/{}. Not pruning.",
+            path.display(),
+            plan.bucket,
+            plan.prefix,
+            settings.bucket,
+            settings.prefix
+        ));
+    }
+
+    plan
+}
+
+/// Prunes each of the `--targets` listed in the file at `path`, printing the outcome for
+/// each of them. Exits, if the file is invalid, before pruning anything.
+fn apply_targets(apply_opt: &ApplyOpt, path: &Path) {
+    check_several_targets(apply_opt).unwrap_or_else(|error| exit_with_error(&error));
+    let all_settings = merged_config(&apply_opt.common)
+        .and_then(|fallback| targets::Targets::from_file(path)?.into_settings(&fallback))
+        .unwrap_or_else(|error| exit_with_error(&error));
+
+    apply_each(apply_opt, &all_settings, targets::location);
+}
+
+/// Prunes the copy of the backups in each of the regions given, keying the results by
+/// region. Each region is listed on its own, as replication may lag behind.
+fn apply_regions(apply_opt: &ApplyOpt, config: Config) {
+    check_several_targets(apply_opt).unwrap_or_else(|error| exit_with_error(&error));
+    let all_settings = config.into_region_settings().unwrap_or_else(|error| exit_with_error(&error));
+
+    apply_each(apply_opt, &all_settings, |settings| settings.region.clone());
+}
+
+/// Fails, if an option of `apply_opt` doesn't support pruning several targets (or
+/// regions) at once.
+fn check_several_targets(apply_opt: &ApplyOpt) -> Result<(), String> {
+    if !apply_opt.skip_confirmation {
+        return Err(String::from("Pruning several targets or regions requires --skip_confirmation."));
+    }
+
+    let unsupported = [
+        ("--plan", apply_opt.plan.is_some()),
+        ("--dry_run", apply_opt.dry_run),
+        ("--emit_script", apply_opt.emit_script.is_some()),
+        ("--approval_token", apply_opt.approval_token.is_some()),
+        ("--expect_bytes_freed", apply_opt.expect_bytes_freed.is_some()),
+        ("--verify_kept", apply_opt.verify_kept),
+        ("--delete_ids_from_stdin", apply_opt.delete_ids_from_stdin),
+        ("--write_manifest", apply_opt.write_manifest.is_some()),
+
+        // A single snapshot can't track the backups of several targets.
+        ("--max_reduction_pct", apply_opt.common.max_reduction_pct.is_some()),
+        ("--incremental", apply_opt.common.incremental.is_some()),
+    ];
+    match unsupported.iter().find(|(_, given)| *given) {
+        Some((option, _)) => Err(format!("{} can't be combined with --targets or several regions.", option)),
+        None => Ok(()),
+    }
+}
+
+/// Returns the strategy pruning the target described by `settings`, with the same
+/// safeguards as a single target, see `with_safeguards`.
+fn target_strategy(
+    opt: &Opt,
+    settings: &Settings,
+    manifest_ids: Option<Vec<String>>,
+    reference_time: DateTime<Utc>,
+) -> Result<Box<dyn PruningStrategy>, String> {
+    let pruning_strategy = build_pruning_strategy(opt, settings, reference_time).map_err(|error| error.to_string())?;
+
+    Ok(with_safeguards(opt, pruning_strategy, manifest_ids, reference_time))
+}
+
+/// Prunes the backups described by each of `all_settings` concurrently, reporting the
+/// results by `label`, e.g. its location.
+fn apply_each(apply_opt: &ApplyOpt, all_settings: &[Settings], label: impl Fn(&Settings) -> String) {
+    let opt = &apply_opt.common;
+    let reference_time = opt.reference_time.unwrap_or_else(Utc::now);
+
+    let mut targets: Vec<targets::Target> = vec![];
+    let mut report = targets::TargetsReport::new();
+    for settings in all_settings {
+        let manifest_ids = opt.manifest.as_ref().map(|(bucket, key)| manifest_ids(settings, bucket, key));
+        let pruning_strategy = target_strategy(opt, settings, manifest_ids, reference_time)
+            .unwrap_or_else(|error| exit_with_error(&format!("Invalid target {}: {}", label(settings), error)));
+
+        match try_storage_client(opt, settings) {
+            Ok(mut storage_client) => {
+                if let Some((key, value)) = &apply_opt.tag_expired {
+                    storage_client = storage_client.with_tag_instead_of_delete(key.clone(), value.clone());
+                }
+                if apply_opt.prune_empty_markers {
+                    storage_client = storage_client.with_prune_empty_markers();
+                }
+                if let (Some(serial), Some(token)) = (&apply_opt.mfa_serial, &apply_opt.mfa_token) {
+                    storage_client = storage_client.with_mfa(serial.clone(), token.clone());
+                }
+                if let Some(time_budget) = apply_opt.time_budget {
+                    storage_client = storage_client.with_time_budget(std::time::Duration::from_secs(time_budget));
+                }
+                targets.push((label(settings), Box::new(storage_client), pruning_strategy));
+            },
+            Err(error) => {
+                report.insert(label(settings), Err(error.to_string()));
+            },
+        }
+    }
+
+    info!("Pruning {} targets...", all_settings.len());
+    report.append(&mut targets::prune_targets(targets, apply_opt.concurrency, apply_opt.allow_delete_newest));
+
+    for (location, result) in &report {
+        match result {
+            Ok(summary) if summary.remaining > 0 => info!(
+                "{}: Deleted {} of {} backups, {} failed, {} left for the next run.",
+                location,
+                summary.deleted,
+                summary.total_found,
+                summary.failed,
+                summary.remaining
+            ),
+            Ok(summary) => info!(
+                "{}: Deleted {} of {} backups, {} failed.",
+                location,
+                summary.deleted,
+                summary.total_found,
+                summary.failed
+            ),
+            Err(error) => eprintln!("{}: {}", location, error),
+        }
+    }
+
+    if targets::partially_failed(&report) {
+        process::exit(EXIT_PARTIAL_DELETION);
+    }
+}
+
+fn apply(apply_opt: &ApplyOpt) {
+    let opt = &apply_opt.common;
+    QUIET.store(opt.quiet, Ordering::Relaxed);
+
+    if let Some(path) = &apply_opt.targets {
+        return apply_targets(apply_opt, path);
+    }
+    if let Ok(config) = merged_config(opt) {
+        if config.region.len() > 1 {
+            return apply_regions(apply_opt, config);
+        }
+    }
+
+    let settings = settings(opt).unwrap_or_else(|error| exit_with_error(&error));
+
+    let mut storage_client = storage_client(opt, &settings);
+    if let Some((key, value)) = &apply_opt.tag_expired {
+        storage_client = storage_client.with_tag_instead_of_delete(key.clone(), value.clone());
+    }
+    if apply_opt.prune_empty_markers {
+        storage_client = storage_client.with_prune_empty_markers();
+    }
+    if let (Some(serial), Some(token)) = (&apply_opt.mfa_serial, &apply_opt.mfa_token) {
+        storage_client = storage_client.with_mfa(serial.clone(), token.clone());
+    }
+    if let Some(time_budget) = apply_opt.time_budget {
+        storage_client = storage_client.with_time_budget(std::time::Duration::from_secs(time_budget));
+    }
+    let reference_time = opt.reference_time.unwrap_or_else(Utc::now);
+    let plan = apply_opt.plan.as_ref().map(|path| read_plan(path, &settings));
+    let number_of_planned_backups = plan.as_ref().map(|plan| plan.expendable.len());
+    let ids_to_delete = if apply_opt.delete_ids_from_stdin {
+        let mut contents = String::new();
+        io::stdin().read_to_string(&mut contents)
+            .unwrap_or_else(|error| exit_with_error(&format!("Could not read the ids from stdin: {} Not pruning.", error)));
+        Some(parse_in_use_ids(&contents))
+    }
+    else {
+        None
+    };
+    let pruning_strategy: Box<dyn PruningStrategy> = match (plan, &ids_to_delete) {
+        // Backups may have come into use since planning.
+        (Some(plan), _) => pin_in_use(opt, Box::new(plan.into_strategy())),
+        (None, Some(ids)) => pin_in_use(opt, Box::new(listed_ids_strategy(ids.clone()))),
+        (None, None) => full_pruning_strategy(opt, &settings, reference_time),
+    };
+
+    let strategy = match &apply_opt.plan {
+        Some(path) => format!("plan {}", path.display()),
+        None if apply_opt.delete_ids_from_stdin => String::from("ids read from stdin"),
+        None => describe_strategy(&settings),
+    };
+    let listing = CheckedListing { opt, storage_client: &storage_client, reference_time, use_cached: apply_opt.dry_run };
+    let mut metrics = Metrics::new(&[], &[]);
+    let mut listed_backups = vec![];
+
+    // Whether the run went through, neither dry nor declined nor failing to delete.
+    let mut pruned = false;
+    let mut deleting = false;
+
+    let selected = backups_cleaner::prune_selected(&listing, pruning_strategy.as_ref(), |stored_backups, expendable_backups| {
+        if ids_to_delete.is_some() || opt.stats || apply_opt.write_manifest.is_some() || opt.incremental.is_some() {
+            listed_backups = stored_backups.iter().chain(expendable_backups.iter()).cloned().collect();
+        }
+        if let Some(ids) = &ids_to_delete {
+            for id in missing_ids(ids, &listed_backups) {
+                eprintln!("Warning: {} is not among the stored backups, skipping it.", id);
+            }
+        }
+
+        if opt.stats {
+            print_stats(&listed_backups);
+        }
+
+        report_evaluation(opt, pruning_strategy.as_ref(), &strategy, stored_backups, &expendable_backups);
+        if let Some(number_of_planned_backups) = number_of_planned_backups {
+            if number_of_planned_backups > expendable_backups.len() {
+                info!(
+                    "Keeping {} planned backups, as they have changed, are gone or are in use since planning.",
+                    number_of_planned_backups - expendable_backups.len()
+                );
+            }
+        }
+        if let Some(token) = &apply_opt.approval_token {
+            if let Err(error) = check_approval_token(token, &expendable_backups) {
+                exit_with_error(&format!("{} Not pruning.", error));
+            }
+        }
+        if let Some(expected) = apply_opt.expect_bytes_freed {
+            if let Err(error) = check_bytes_freed(expected, &expendable_backups) {
+                exit_with_error(&format!("{} Not pruning.", error));
+            }
+        }
+        if !apply_opt.allow_delete_newest && !apply_opt.dry_run {
+            if let Err(error) = check_newest_kept(stored_backups, &expendable_backups) {
+                exit_with_error(&format!("{} Not pruning.", error));
+            }
+        }
+        metrics = Metrics::new(stored_backups, &expendable_backups);
+
+        if apply_opt.verify_kept {
+            let failures = storage_client.verify_backups(stored_backups);
+            for (backup, failure) in &failures {
+                eprintln!("Kept backup {} failed verification: {}", backup.human_readable_id, failure);
+            }
+            if !failures.is_empty() {
+                exit_with(EXIT_UNVERIFIED_BACKUPS, &format!("{} of the kept backups failed verification. Not pruning.", failures.len()));
+            }
+            info!("Verified {} kept backups.", stored_backups.len());
+        }
+
+        if let Some(script_out) = &apply_opt.emit_script {
+            write_atomically(script_out, script::delete_script(&settings.bucket, &expendable_backups))
+                .unwrap_or_else(|error| exit_with_error(&format!("Could not write {}: {}", script_out.display(), error)));
+            info!("Wrote commands deleting {} backups to {}.", expendable_backups.len(), script_out.display());
+            return vec![];
+        }
+        if expendable_backups.is_empty() || apply_opt.dry_run {
+            print_expendable_backups(&expendable_backups, stored_backups.len());
+            pruned = !apply_opt.dry_run;
+            return vec![];
+        }
+
+        let backups_to_delete = if apply_opt.interactive {
+            let stdin = io::stdin();
+            match interactive::select(expendable_backups, stdin.lock(), io::stdout()) {
+                Some(selection) => {
+                    info!("Keeping {} of the expendable backups.", selection.to_keep.len());
+                    selection.to_delete
+                },
+                None => {
+                    println!("\nAborted, no backups have been deleted.");
+                    vec![]
+                },
+            }
+        }
+        else {
+            let estimate = storage_client.estimate_api_calls(expendable_backups.len());
+            info!(
+                "This run will make ~{} list and {} delete requests.",
+                estimate.list_requests,
+                estimate.delete_requests
+            );
+            if !apply_opt.skip_confirmation {
+                if let Err(error) = check_confirmable(&io::stdin()) {
+                    println!(
+                        "This would delete {} of {} backups.",
+                        expendable_backups.len(),
+                        expendable_backups.len() + stored_backups.len()
+                    );
+                    exit_with_error(&error);
+                }
+            }
+            println!(
+                "This will delete {} of {} backups. Do you want to proceed? (y)",
+                expendable_backups.len(),
+                expendable_backups.len() + stored_backups.len()
+            );
+
+            let timeout = apply_opt.confirm_timeout.map(std::time::Duration::from_secs);
+            if apply_opt.skip_confirmation || confirmed(io::BufReader::new(io::stdin()), timeout) { expendable_backups } else { vec![] }
+        };
+
+        if !backups_to_delete.is_empty() {
+            info!("Removing expendible backups...");
+            deleting = true;
+        }
+
+        backups_to_delete
+    });
+    let (summary, deletion_report) = selected.unwrap_or_else(|error| exit_with(exit_code(&error), &error.to_string()));
+
+    if deleting {
+        info!("{}", deletion_report);
+
+        let number_of_archived_backups = deletion_report.deleted.iter().filter(|backup| backup.is_archived()).count();
+        if number_of_archived_backups > 0 {
+            info!("{} of them were archived (e.g. in S3 Glacier).", number_of_archived_backups);
+        }
+
+        if !deletion_report.protected.is_empty() {
+            info!("{} backups are protected by object lock and have been kept.", deletion_report.protected.len());
+        }
+
+        if !deletion_report.remaining.is_empty() {
+            info!(
+                "The time budget has run out, leaving {} expendable backups for the next run.",
+                deletion_report.remaining.len()
+            );
+        }
+
+        for (backup, reason) in &deletion_report.failed {
+            eprintln!("Failed to delete {}: {}", backup.human_readable_id, reason);
+        }
+        for (backup, reason) in &deletion_report.kms_denied {
+            eprintln!("Failed to delete {}, as the KMS key policy denies it: {}", backup.human_readable_id, reason);
+        }
+
+        // Companions aren't redacted along with their backups, so only their number is shown.
+        if opt.redact && !deletion_report.failed_companions.is_empty() {
+            eprintln!("Failed to delete {} companions.", deletion_report.failed_companions.len());
+        }
+        else {
+            for (key, reason) in &deletion_report.failed_companions {
+                eprintln!("Failed to delete the companion {}: {}", key, reason);
+            }
+        }
+
+        if let Some((bucket, key_prefix)) = &apply_opt.receipt_dest {
+            write_receipt(&settings, &deletion_report, bucket, key_prefix);
+        }
+
+        metrics.record_deletion(&deletion_report);
+        pruned = deletion_report.failed_count() == 0;
+    }
+
+    if let (Some(path), true) = (&opt.incremental, pruned) {
+        save_snapshot(path, &manifest::remaining_backups(listed_backups.clone(), &deletion_report));
+    }
+
+    if let Some((bucket, key)) = &apply_opt.write_manifest {
+        write_inventory(&settings, &manifest::remaining_backups(listed_backups, &deletion_report), bucket, key);
+    }
+
+    finish(opt, &settings, &summary, &metrics);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::offset::TimeZone;
+
+    fn parse_args(args: &[&str]) -> Opt {
+        Opt::from_iter(Some("prune_backups").iter().chain(args.iter()))
+    }
+
+    fn parse_command(args: &[&str]) -> Result<Command, structopt::clap::Error> {
+        let args = Some("prune_backups").iter().chain(args.iter()).map(OsString::from).collect();
+        Command::from_iter_safe(with_default_subcommand(args))
+    }
+
+    #[test]
+    fn test_list_subcommand() {
+        match parse_command(&["list", "-b", "my-database-backups", "--cache_listing", "listing.toml"]).unwrap() {
+            Command::List(opt) => {
+                assert_eq!(opt.config.bucket, Some(String::from("my-database-backups")));
+                assert_eq!(opt.cache_listing, Some(PathBuf::from("listing.toml")));
+            },
+            command => panic!("Parsed {:?}", command),
+        }
+        assert!(parse_command(&["list", "-b", "my-database-backups", "-y"]).is_err());
+        assert!(parse_command(&["list", "-b", "my-database-backups", "--out", "plan.json"]).is_err());
+    }
+
+    #[test]
+    fn test_plan_subcommand() {
+        match parse_command(&["plan", "-b", "my-database-backups", "--keep-daily", "7", "--out", "plan.json"]).unwrap() {
+            Command::Plan(plan_opt) => {
+                assert_eq!(plan_opt.common.config.bucket, Some(String::from("my-database-backups")));
+                assert_eq!(plan_opt.common.config.keep_daily, Some(7));
+                assert_eq!(plan_opt.out, Some(PathBuf::from("plan.json")));
+                assert!(!plan_opt.emit_lifecycle);
+            },
+            command => panic!("Parsed {:?}", command),
+        }
+        assert!(parse_command(&["plan", "-b", "my-database-backups", "--dry_run"]).is_err());
+    }
+
+    #[test]
+    fn test_apply_subcommand() {
+        match parse_command(&["apply", "-b", "my-database-backups", "-y", "--plan", "plan.json"]).unwrap() {
+            Command::Apply(apply_opt) => {
+                assert_eq!(apply_opt.common.config.bucket, Some(String::from("my-database-backups")));
+                assert!(apply_opt.skip_confirmation);
+                assert_eq!(apply_opt.plan, Some(PathBuf::from("plan.json")));
+            },
+            command => panic!("Parsed {:?}", command),
+        }
+        assert!(parse_command(&["apply", "-b", "my-database-backups", "--plan", "plan.json", "--incremental", "snapshot.toml"]).is_err());
+    }
+
+    #[test]
+    fn test_apply_with_targets() {
+        match parse_command(&["apply", "-y", "--targets", "targets.toml", "--concurrency", "4"]).unwrap() {
+            Command::Apply(apply_opt) => {
+                assert_eq!(apply_opt.targets, Some(PathBuf::from("targets.toml")));
+                assert_eq!(apply_opt.concurrency, 4);
+            },
+            command => panic!("Parsed {:?}", command),
+        }
+        assert!(parse_command(&["apply", "--targets", "targets.toml"]).is_err());
+        assert!(parse_command(&["apply", "-y", "--targets", "targets.toml", "--dry_run"]).is_err());
+        assert!(parse_command(&["apply", "-y", "--targets", "targets.toml", "--concurrency", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_max_reduction_pct_requires_incremental() {
+        match parse_command(&["list", "-b", "my-database-backups", "--incremental", "snapshot.toml", "--max_reduction_pct", "30"]).unwrap() {
+            Command::List(opt) => assert_eq!(opt.max_reduction_pct, Some(30)),
+            command => panic!("Parsed {:?}", command),
+        }
+        assert!(parse_command(&["list", "-b", "my-database-backups", "--max_reduction_pct", "30"]).is_err());
+    }
+
+    #[test]
+    fn test_apply_is_the_default_subcommand() {
+        match parse_command(&["-b", "my-database-backups", "--dry_run"]).unwrap() {
+            Command::Apply(apply_opt) => assert!(apply_opt.dry_run),
+            command => panic!("Parsed {:?}", command),
+        }
+        match parse_command(&[]).unwrap() {
+            Command::Apply(apply_opt) => assert_eq!(apply_opt.common.config.bucket, None),
+            command => panic!("Parsed {:?}", command),
+        }
+    }
+
+    /// Returns a backup of no size at `date`, for the tests of all modules to derive
+    /// theirs from.
+    pub(crate) fn build_meta(id: &str, date: DateTime<Utc>) -> BackupFileMeta {
+        BackupFileMeta {
+            id: String::from(id),
+            human_readable_id: String::from(id),
+            date,
+            size: 0,
+            storage_class: None,
+            etag: None,
+            successful: None,
+            retain_until: None,
+            tier: None,
+            version_id: None,
+        }
+    }
+
+    fn expendable_ids(strategy: Box<dyn PruningStrategy>) -> Vec<String> {
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 10).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 20).and_hms(0, 0, 0)),
+        ];
+
+        strategy.expendable_backups(&mut backups).into_iter().map(|backup| backup.id).collect()
+    }
+
+    #[test]
+    fn test_build_pruning_strategy_uses_the_reference_time() {
+        let opt = parse_args(&[
+            "-r", "eu-west-2", "-b", "my-database-backups",
+            "--keep_all_within", "5", "--one_per_month_within", "5",
+            "--reference_time", "2014-07-12T00:00:00Z",
+        ]);
+        let settings = settings(&opt).unwrap();
+
+        assert_eq!(opt.reference_time, Some(Utc.ymd(2014, 7, 12).and_hms(0, 0, 0)));
+        assert_eq!(expendable_ids(build_pruning_strategy(&opt, &settings, opt.reference_time.unwrap()).unwrap()), vec!["A"]);
+        assert_eq!(expendable_ids(build_pruning_strategy(&opt, &settings, Utc.ymd(2014, 7, 22).and_hms(0, 0, 0)).unwrap()), vec!["A", "B"]);
+    }
+
+    #[test]
+    fn test_build_pruning_strategy_with_only_month() {
+        let opt = parse_args(&[
+            "-r", "eu-west-2", "-b", "my-database-backups",
+            "--keep_all_within", "0", "--one_per_month_within", "0",
+            "--only_month", "2014-07",
+        ]);
+        let settings = settings(&opt).unwrap();
+        let pruning_strategy = build_pruning_strategy(&opt, &settings, Utc.ymd(2014, 9, 1).and_hms(0, 0, 0)).unwrap();
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 6, 30).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 31).and_hms(0, 0, 0)),
+            build_meta("D", Utc.ymd(2014, 8, 1).and_hms(0, 0, 0)),
+        ];
+
+        let expendable_ids: Vec<String> = pruning_strategy.expendable_backups(&mut backups).into_iter().map(|backup| backup.id).collect();
+
+        // Everything is old enough to be expendable, but only July is considered.
+        assert_eq!(expendable_ids, vec!["B", "C"]);
+        assert_eq!(backups.len(), 2);
+    }
+
+    #[test]
+    fn test_target() {
+        let opt = parse_args(&[
+            "--target", "s3://my-database-backups/daily/?region=eu-west-2",
+            "--keep_daily", "7",
+        ]);
+        let settings = settings(&opt).unwrap();
+
+        assert_eq!((settings.region.as_str(), settings.bucket.as_str(), settings.prefix.as_str()), ("eu-west-2", "my-database-backups", "daily/"));
+        assert_eq!(super::settings(&parse_args(&["--target", "s3://my-database-backups", "--keep_daily", "7"])).unwrap().region, "auto");
+        assert!(parse_command(&["--target", "s3://my-database-backups", "-b", "other-backups"]).is_err());
+        assert!(parse_command(&["--target", "file:///var/backups"]).is_err());
+        assert!(parse_command(&["--target", "gs://my-database-backups"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_month_and_year() {
+        assert_eq!(parse_month("2022-07"), Ok(Utc.ymd(2022, 7, 1).and_hms(0, 0, 0)));
+        assert!(parse_month("2022-13").is_err());
+        assert!(parse_month("2022").is_err());
+        assert_eq!(parse_year("2022"), Ok(Utc.ymd(2022, 1, 1).and_hms(0, 0, 0)));
+        assert!(parse_year("22x").is_err());
+    }
+
+    #[test]
+    fn test_build_pruning_strategy_with_restic_style_flags() {
+        let opt = parse_args(&[
+            "-r", "eu-west-2", "-b", "my-database-backups",
+            "--keep-daily", "3", "--keep-weekly", "2", "--keep-monthly", "2",
+        ]);
+        let settings = settings(&opt).unwrap();
+        let pruning_strategy = build_pruning_strategy(&opt, &settings, Utc::now()).unwrap();
+
+        // Two backups a day, from the 20th of June up to the 10th of July 2014.
+        let mut backups = vec![];
+        let mut date = Utc.ymd(2014, 6, 20).and_hms(1, 0, 0);
+        while date < Utc.ymd(2014, 7, 11).and_hms(0, 0, 0) {
+            backups.push(build_meta(&date.format("%m-%d %H:%M").to_string(), date));
+            date = date + Duration::hours(12);
+        }
+
+        pruning_strategy.expendable_backups(&mut backups);
+        backups.sort_by_key(|backup| backup.date);
+
+        // What `restic forget --keep-daily 3 --keep-weekly 2 --keep-monthly 2` keeps: The
+        // last backups of the last three days, of the weeks starting on the 7th and the
+        // 30th, and of July and June.
+        let kept_ids: Vec<String> = backups.into_iter().map(|backup| backup.id).collect();
+        assert_eq!(kept_ids, vec!["06-30 13:00", "07-06 13:00", "07-08 13:00", "07-09 13:00", "07-10 13:00"]);
+    }
+
+    #[test]
+    fn test_build_pruning_strategy_with_contradictory_options() {
+        let opt = parse_args(&[
+            "-r", "eu-west-2", "-b", "my-database-backups",
+            "--keep_all_within", "30", "--one_per_month_within", "7",
+        ]);
+        let settings = settings(&opt).unwrap();
+
+        match build_pruning_strategy(&opt, &settings, Utc::now()) {
+            Err(error) => assert_eq!(error.to_string(), "keep_all_within (30 days) must not exceed one_per_month_within (7 days)."),
+            Ok(_) => panic!("The options should be rejected."),
+        }
+    }
+
+    #[test]
+    fn test_candidate_settings() {
+        let path = std::env::temp_dir().join(format!("backups_cleaner-candidate-{}.toml", process::id()));
+        fs::write(&path, "keep_daily = 7\nregion = \"us-east-1\"").unwrap();
+        let opt = parse_args(&[
+            "-r", "eu-west-2", "-b", "my-database-backups",
+            "--keep_all_within", "7", "--one_per_month_within", "30", "--always_keep_latest", "2",
+        ]);
+
+        let settings = candidate_settings(&opt, &path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(settings.region, "eu-west-2");
+        assert_eq!(settings.bucket, "my-database-backups");
+        assert_eq!(settings.retention, Retention::PerPeriod { daily: 7, weekly: 0, monthly: 0, yearly: 0 });
+        assert_eq!(settings.always_keep_latest, None);
+    }
+
+    #[test]
+    fn test_describe_strategy() {
+        let opt = parse_args(&[
+            "-r", "eu-west-2", "-b", "my-database-backups",
+            "--keep_all_within", "7", "--one_per_month_within", "30", "--always_keep_latest", "2",
+        ]);
+
+        assert_eq!(
+            describe_strategy(&settings(&opt).unwrap()),
+            "keep_all_within=7d, one_per_month_within=30d, one_per_month_tolerance=15d, always_keep_latest=2"
+        );
+    }
+
+    #[test]
+    fn test_load_snapshot() {
+        let path = std::env::temp_dir().join(format!("backups_cleaner-snapshot-{}.toml", process::id()));
+        let snapshot = pruning_strategy::Snapshot::of(&[build_meta("backups/2014-07-01.dump", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0))]);
+        fs::write(&path, toml::to_string(&snapshot).unwrap()).unwrap();
+
+        assert_eq!(read_snapshot(&path), Some(snapshot));
+
+        fs::write(&path, "kept = 1").unwrap();
+        assert_eq!(read_snapshot(&path), None);
+
+        let _ = fs::remove_file(&path);
+        assert_eq!(read_snapshot(&path), None);
+    }
+
+    /// Returns a backup of each of the last `n` days before 2014-07-01.
+    fn daily_backups(n: i64) -> Vec<BackupFileMeta> {
+        (1..=n)
+            .rev()
+            .map(|days_ago| {
+                let date = Utc.ymd(2014, 7, 1).and_hms(0, 0, 0) - Duration::days(days_ago);
+                build_meta(&format!("backups/{}.dump", date.format("%Y-%m-%d")), date)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_max_reduction_pct_after_dry_runs() {
+        let path = std::env::temp_dir().join(format!("backups_cleaner-max-reduction-{}.toml", process::id()));
+        let _ = fs::remove_file(&path);
+        let path_argument = path.to_str().unwrap();
+        let opt = parse_args(&[
+            "-r", "eu-west-2", "-b", "my-database-backups", "--keep_daily", "10",
+            "--incremental", path_argument, "--max_reduction_pct", "30",
+        ]);
+        let settings = settings(&opt).unwrap();
+        let reference_time = Utc.ymd(2014, 7, 1).and_hms(0, 0, 0);
+        let client = storage_client::InMemory::new(daily_backups(100));
+
+        // Dry runs and plans only evaluate, which doesn't lower the baseline.
+        for _ in 0..2 {
+            let mut stored_backups = client.stored_backups().unwrap();
+            let pruning_strategy = full_pruning_strategy(&opt, &settings, reference_time);
+            let expendable_backups = evaluate(&opt, pruning_strategy.as_ref(), "", &mut stored_backups, &mut PruneSummary::default());
+
+            assert_eq!(expendable_backups.len(), 30);
+            assert!(!path.exists());
+        }
+
+        let mut stored_backups = client.stored_backups().unwrap();
+        let pruning_strategy = full_pruning_strategy(&opt, &settings, reference_time);
+        let expendable_backups = evaluate(&opt, pruning_strategy.as_ref(), "", &mut stored_backups, &mut PruneSummary::default());
+        let listed_backups: Vec<BackupFileMeta> = stored_backups.iter().chain(expendable_backups.iter()).cloned().collect();
+        let deletion_report = client.delete_backups(expendable_backups);
+        save_snapshot(&path, &manifest::remaining_backups(listed_backups, &deletion_report));
+        assert_eq!(deletion_report.deleted_count(), 30);
+
+        // The next run may only delete 30% of the 70 backups left.
+        let mut stored_backups = client.stored_backups().unwrap();
+        let pruning_strategy = full_pruning_strategy(&opt, &settings, reference_time);
+        let expendable_backups = evaluate(&opt, pruning_strategy.as_ref(), "", &mut stored_backups, &mut PruneSummary::default());
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(expendable_backups.len(), 21);
+    }
+
+    /// Hands a target the backups of a client the test keeps looking at.
+    struct Shared(std::sync::Arc<storage_client::InMemory>);
+
+    impl StorageClient for Shared {
+
+        fn stored_backups(&self) -> Result<Vec<BackupFileMeta>, StorageError> {
+            self.0.stored_backups()
+        }
+
+        fn delete_backups(&self, backup_file_metas: Vec<BackupFileMeta>) -> storage_client::DeletionReport {
+            self.0.delete_backups(backup_file_metas)
+        }
+    }
+
+    /// Prunes each of `all_settings`, each holding `daily_backups(n)`, the way `apply_each`
+    /// would, returning the report along with the ids of the backups left in each of them.
+    fn prune_each(
+        apply_opt: &ApplyOpt,
+        all_settings: &[Settings],
+        manifest_ids: Option<Vec<String>>,
+        n: i64,
+    ) -> (targets::TargetsReport, Vec<Vec<String>>) {
+        let opt = &apply_opt.common;
+        let reference_time = opt.reference_time.unwrap_or_else(|| Utc.ymd(2014, 7, 1).and_hms(0, 0, 0));
+        let clients: Vec<_> = all_settings.iter().map(|_| std::sync::Arc::new(storage_client::InMemory::new(daily_backups(n)))).collect();
+        let targets = all_settings
+            .iter()
+            .zip(&clients)
+            .map(|(settings, client)| -> targets::Target {
+                (
+                    targets::location(settings),
+                    Box::new(Shared(client.clone())),
+                    target_strategy(opt, settings, manifest_ids.clone(), reference_time).unwrap(),
+                )
+            })
+            .collect();
+
+        let report = targets::prune_targets(targets, 2, apply_opt.allow_delete_newest);
+        let remaining = clients
+            .iter()
+            .map(|client| client.stored_backups().unwrap().into_iter().map(|backup| backup.id).collect())
+            .collect();
+
+        (report, remaining)
+    }
+
+    #[test]
+    fn test_targets_keep_the_backups_of_the_manifest() {
+        let apply_opt = match parse_command(&[
+            "apply", "-y", "--targets", "targets.toml", "-r", "eu-west-2", "--keep_daily", "2",
+            "--manifest", "s3://manifests/restore.json",
+        ]).unwrap() {
+            Command::Apply(apply_opt) => apply_opt,
+            command => panic!("Parsed {:?}", command),
+        };
+        assert_eq!(check_several_targets(&apply_opt), Ok(()));
+        let fallback = merged_config(&apply_opt.common).unwrap();
+        let targets: targets::Targets = toml::from_str(r#"
+            [[target]]
+            bucket = "customer-backups"
+
+            [[target]]
+            bucket = "analytics-backups"
+        "#).unwrap();
+        let all_settings = targets.into_settings(&fallback).unwrap();
+
+        let (report, remaining) = prune_each(&apply_opt, &all_settings, Some(vec![String::from("backups/2014-06-26.dump")]), 5);
+
+        assert!(!targets::partially_failed(&report));
+        let expected = vec!["backups/2014-06-26.dump", "backups/2014-06-29.dump", "backups/2014-06-30.dump"];
+        assert_eq!(remaining, vec![expected.clone(), expected]);
+    }
+
+    #[test]
+    fn test_targets_with_incremental() {
+        match parse_command(&["apply", "-y", "--targets", "targets.toml", "--incremental", "snapshot.toml"]).unwrap() {
+            Command::Apply(apply_opt) => assert_eq!(
+                check_several_targets(&apply_opt),
+                Err(String::from("--incremental can't be combined with --targets or several regions.")),
+            ),
+            command => panic!("Parsed {:?}", command),
+        }
+        match parse_command(&[
+            "apply", "-y", "-r", "eu-west-2", "-r", "us-east-1", "-b", "backups-{region}",
+            "--incremental", "snapshot.toml", "--max_reduction_pct", "30",
+        ]).unwrap() {
+            Command::Apply(apply_opt) => assert_eq!(
+                check_several_targets(&apply_opt),
+                Err(String::from("--max_reduction_pct can't be combined with --targets or several regions.")),
+            ),
+            command => panic!("Parsed {:?}", command),
+        }
+    }
+
+    #[test]
+    fn test_regions_keep_the_backups_in_use() {
+        let path = std::env::temp_dir().join(format!("backups_cleaner-in-use-{}.txt", process::id()));
+        fs::write(&path, "# Being restore-tested\nbackups/2014-06-27.dump\n").unwrap();
+        let apply_opt = match parse_command(&[
+            "apply", "-y", "-r", "eu-west-2", "-r", "us-east-1", "-b", "backups-{region}", "--keep_daily", "2",
+            "--in_use_file", path.to_str().unwrap(),
+        ]).unwrap() {
+            Command::Apply(apply_opt) => apply_opt,
+            command => panic!("Parsed {:?}", command),
+        };
+        assert_eq!(check_several_targets(&apply_opt), Ok(()));
+        let all_settings = merged_config(&apply_opt.common).unwrap().into_region_settings().unwrap();
+
+        let (report, remaining) = prune_each(&apply_opt, &all_settings, None, 5);
+        let _ = fs::remove_file(&path);
+
+        assert!(!targets::partially_failed(&report));
+        let expected = vec!["backups/2014-06-27.dump", "backups/2014-06-29.dump", "backups/2014-06-30.dump"];
+        assert_eq!(remaining, vec![expected.clone(), expected]);
+    }
+
+    #[test]
+    fn test_regions_keep_the_newest_backup() {
+        let apply_opt = |extra_args: &[&str]| match parse_command(&[
+            &["apply", "-y", "-r", "eu-west-2", "-r", "us-east-1", "-b", "backups-{region}"][..],
+            &["--keep_all_within", "1", "--one_per_month_within", "30", "--reference_time", "2020-01-01"],
+            extra_args,
+        ].concat()).unwrap() {
+            Command::Apply(apply_opt) => apply_opt,
+            command => panic!("Parsed {:?}", command),
+        };
+        let all_settings = merged_config(&apply_opt(&[]).common).unwrap().into_region_settings().unwrap();
+
+        // All backups are years old, which is most likely a wrong reference time.
+        let (report, remaining) = prune_each(&apply_opt(&[]), &all_settings, None, 3);
+
+        assert!(targets::partially_failed(&report));
+        assert_eq!(report.len(), 2);
+        assert!(report.values().all(|result| result.as_ref().unwrap_err().starts_with("The newest backup backups/2014-06-30.dump")));
+        assert!(remaining.iter().all(|ids| ids.len() == 3));
+
+        let (report, remaining) = prune_each(&apply_opt(&["--allow_delete_newest"]), &all_settings, None, 3);
+
+        assert!(!targets::partially_failed(&report));
+        assert!(remaining.iter().all(Vec::is_empty));
+
+        for (option, extra_args) in &[("--dry_run", &["--dry_run"][..]), ("--plan", &["--plan", "plan.json"][..])] {
+            assert_eq!(
+                check_several_targets(&apply_opt(extra_args)),
+                Err(format!("{} can't be combined with --targets or several regions.", option)),
+            );
+        }
+    }
+
+    #[test]
+    fn test_max_reduction_pct_without_snapshot() {
+        let path = std::env::temp_dir().join(format!("backups_cleaner-no-snapshot-{}.toml", process::id()));
+        let _ = fs::remove_file(&path);
+        let opt = parse_args(&[
+            "-r", "eu-west-2", "-b", "my-database-backups", "--keep_daily", "1",
+            "--incremental", path.to_str().unwrap(), "--max_reduction_pct", "50",
+        ]);
+        let settings = settings(&opt).unwrap();
+        let mut stored_backups = daily_backups(10);
+
+        let pruning_strategy = full_pruning_strategy(&opt, &settings, Utc.ymd(2014, 7, 1).and_hms(0, 0, 0));
+        let expendable_backups = pruning_strategy.expendable_backups(&mut stored_backups);
+
+        // keep_daily would delete 9, but the 10 stored backups are the baseline.
+        assert_eq!(expendable_backups.len(), 5);
+        assert_eq!(stored_backups.len(), 5);
+    }
+
+    #[test]
+    fn test_exit_code() {
+        let codes = vec![
+            exit_code(&StorageError::AccessDenied(String::from("bucket"))),
+            exit_code(&StorageError::Authentication(String::from("No credentials"))),
+            exit_code(&StorageError::Network(String::from("Connection refused"))),
+            exit_code(&StorageError::BucketNotFound(String::from("bucket"))),
+            exit_code(&StorageError::Request(String::from("Internal error"))),
+        ];
+
+        assert_eq!(codes, vec![
+            EXIT_AUTHENTICATION_FAILURE,
+            EXIT_AUTHENTICATION_FAILURE,
+            EXIT_NETWORK_FAILURE,
+            EXIT_BUCKET_NOT_FOUND,
+            1,
+        ]);
+        assert!(!codes.contains(&0));
+        assert!(!codes.contains(&EXIT_PARTIAL_DELETION));
+    }
+
+    #[test]
+    fn test_deletion_exit_code() {
+        let backups = || vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0)),
+        ];
+        let prune_with_undeletable = |ids: &[&str]| {
+            let client = storage_client::InMemory::new(backups()).with_undeletable(ids.iter().map(|id| String::from(*id)).collect());
+            backups_cleaner::prune(&client, &pruning_strategy::KeepLastN::new(1)).unwrap()
+        };
+
+        assert_eq!(deletion_exit_code(&prune_with_undeletable(&[])), None);
+
+        let summary = prune_with_undeletable(&["A"]);
+        assert_eq!((summary.deleted, summary.failed), (1, 1));
+        assert_eq!(deletion_exit_code(&summary), Some(EXIT_PARTIAL_DELETION));
+
+        assert_eq!(deletion_exit_code(&prune_with_undeletable(&["A", "B"])), Some(EXIT_DELETION_FAILED));
+    }
+
+    #[test]
+    fn test_parse_bytes() {
+        assert_eq!(parse_bytes("1024"), Ok(1024));
+        assert_eq!(parse_bytes("500M"), Ok(500 * 1024 * 1024));
+        assert_eq!(parse_bytes("1GiB"), Ok(1024 * 1024 * 1024));
+        assert!(parse_bytes("1.5G").is_err());
+        assert!(parse_bytes("G").is_err());
+        assert!(parse_bytes("99999999999T").is_err());
+    }
+
+    #[test]
+    fn test_parse_tier() {
+        assert_eq!(parse_tier("monthly=forever"), Ok((String::from("monthly"), pruning_strategy::TierRetention::Forever)));
+        assert_eq!(parse_tier("daily=7d"), Ok((String::from("daily"), pruning_strategy::TierRetention::For(Duration::days(7)))));
+        assert!(parse_tier("daily").is_err());
+        assert!(parse_tier("=7d").is_err());
+        assert!(parse_tier("daily=a week").is_err());
+    }
+
+    #[test]
+    fn test_parse_tag() {
+        assert_eq!(parse_tag("lifecycle=expired"), Ok((String::from("lifecycle"), String::from("expired"))));
+        assert_eq!(parse_tag("lifecycle="), Ok((String::from("lifecycle"), String::new())));
+        assert!(parse_tag("lifecycle").is_err());
+        assert!(parse_tag("=expired").is_err());
+    }
+
+    #[test]
+    fn test_parse_s3_url() {
+        assert_eq!(
+            parse_s3_url("s3://my-bucket/manifests/restore.json"),
+            Ok((String::from("my-bucket"), String::from("manifests/restore.json")))
+        );
+        assert!(parse_s3_url("my-bucket/manifests/restore.json").is_err());
+        assert!(parse_s3_url("s3://my-bucket").is_err());
+        assert!(parse_s3_url("s3://my-bucket/").is_err());
+        assert!(parse_s3_url("s3:///restore.json").is_err());
+    }
+
+    /// Yields `y`, but only after a while.
+    struct SlowInput;
+
+    impl Read for SlowInput {
+
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            thread::sleep(std::time::Duration::from_secs(2));
+            buffer[..2].copy_from_slice(b"y\n");
+            Ok(2)
+        }
+    }
+
+    #[test]
+    fn test_confirmed() {
+        assert!(confirmed("y\n".as_bytes(), None));
+        assert!(confirmed("y\n".as_bytes(), Some(std::time::Duration::from_secs(1))));
+        assert!(!confirmed("n\n".as_bytes(), None));
+        assert!(!confirmed("".as_bytes(), None));
+    }
+
+    #[test]
+    fn test_check_confirmable() {
+        let path = std::env::temp_dir().join(format!("backups_cleaner-stdin-{}", process::id()));
+        fs::write(&path, "y\n").unwrap();
+
+        // Input redirected from a file, as by `prune_backups < answers`.
+        let result = check_confirmable(&fs::File::open(&path).unwrap());
+        let _ = fs::remove_file(&path);
+
+        assert!(result.unwrap_err().contains("--skip_confirmation"));
+    }
+
+    #[test]
+    fn test_confirmed_when_the_timeout_passes() {
+        let input = io::BufReader::new(SlowInput);
+
+        assert!(!confirmed(input, Some(std::time::Duration::from_millis(50))));
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90s"), Ok(Duration::seconds(90)));
+        assert_eq!(parse_duration("30m"), Ok(Duration::minutes(30)));
+        assert_eq!(parse_duration("36h"), Ok(Duration::hours(36)));
+        assert_eq!(parse_duration("2d"), Ok(Duration::days(2)));
+        assert_eq!(parse_duration("1w"), Ok(Duration::weeks(1)));
+        assert!(parse_duration("2").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("2 days").is_err());
+        assert!(parse_duration("2é").is_err());
+    }
+
+    #[test]
+    fn test_check_staleness() {
+        let reference_time = Utc.ymd(2014, 7, 10).and_hms(0, 0, 0);
+        let backups = vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 8).and_hms(0, 0, 0)),
+        ];
+
+        assert!(check_staleness(&backups, reference_time, Duration::days(2)).is_ok());
+        assert!(check_staleness(&backups, reference_time, Duration::days(1)).is_err());
+        assert!(check_staleness(&[], reference_time, Duration::days(1)).is_err());
+    }
+
+    #[test]
+    fn test_check_approval_token() {
+        let date = Utc.ymd(2014, 7, 1).and_hms(0, 0, 0);
+        let planned = vec![build_meta("A", date), build_meta("B", date)];
+        let token = plan::approval_token(&planned);
+
+        assert!(check_approval_token(&token, &planned).is_ok());
+        assert!(check_approval_token(&format!(" {} ", token.to_uppercase()), &planned).is_ok());
+
+        // Another backup has become expendable since planning.
+        let expendable_backups = vec![build_meta("A", date), build_meta("B", date), build_meta("C", date)];
+        assert!(check_approval_token(&token, &expendable_backups).is_err());
+    }
+
+    #[test]
+    fn test_check_bytes_freed() {
+        assert_eq!(parse_bytes_range("3G..5G"), Ok((3 << 30, 5 << 30)));
+        assert!(parse_bytes_range("5G..3G").is_err());
+        assert!(parse_bytes_range("4G").is_err());
+
+        let date = Utc.ymd(2014, 7, 1).and_hms(0, 0, 0);
+        let expendable_backups: Vec<BackupFileMeta> = ["A", "B"]
+            .iter()
+            .map(|id| BackupFileMeta { size: 2 << 30, ..build_meta(id, date) })
+            .collect();
+        assert!(check_bytes_freed((3 << 30, 5 << 30), &expendable_backups).is_ok());
+        assert!(check_bytes_freed((4 << 30, 4 << 30), &expendable_backups).is_ok());
+
+        // Another large backup has become expendable since the deletion has been approved.
+        let mut expendable_backups = expendable_backups;
+        expendable_backups.push(BackupFileMeta { size: 2 << 30, ..build_meta("C", date) });
+        assert_eq!(
+            check_bytes_freed((3 << 30, 5 << 30), &expendable_backups),
+            Err(String::from("The expendable backups total 6.0 GiB, which is outside the expected 3.0 GiB..5.0 GiB.")),
+        );
+        assert!(check_bytes_freed((1 << 30, 1 << 30), &[]).is_err());
+    }
+
+    #[test]
+    fn test_explanation() {
+        let pruning_strategy = pruning_strategy::Pin::new(
+            Box::new(pruning_strategy::OlderThanButKeepOnePerMonth::new(
+                Utc.ymd(2014, 6, 15).and_hms(0, 0, 0),
+                Duration::days(1),
+                Duration::days(15),
+                Duration::days(90),
+            )),
+            vec![String::from("K")],
+        );
+        let mut stored_backups = vec![
+            build_meta("N", Utc.ymd(2014, 3, 1).and_hms(0, 0, 0)),
+            build_meta("K", Utc.ymd(2014, 4, 3).and_hms(0, 0, 0)),
+            build_meta("J", Utc.ymd(2014, 5, 17).and_hms(0, 0, 0)),
+            build_meta("I", Utc.ymd(2014, 5, 31).and_hms(0, 0, 0)),
+        ];
+        let expendable_backups = pruning_strategy.expendable_backups(&mut stored_backups);
+        let explain = |id| explanation(id, &pruning_strategy, &stored_backups, &expendable_backups);
+
+        assert_eq!(explain("N"), "N is expendable. It's older than one_per_month_within (90 days), so it's expendable.");
+        assert_eq!(explain("K"), "K is kept. It's pinned, so it's kept.");
+        assert!(explain("J").starts_with("J is expendable. It's older than keep_all_within (1 days) and not the one closest"));
+        assert_eq!(explain("X"), "X is not among the stored backups.");
+    }
+
+    #[test]
+    fn test_check_newest_kept() {
+        let backups = || vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0)),
+        ];
+
+        let mut stored_backups = backups();
+        let expendable_backups = pruning_strategy::KeepLastN::new(1).expendable_backups(&mut stored_backups);
+        assert!(check_newest_kept(&stored_backups, &expendable_backups).is_ok());
+
+        // Inverted by mistake, keeping only the oldest backup.
+        let mut stored_backups = backups();
+        let expendable_backups = pruning_strategy::Not::new(Box::new(pruning_strategy::KeepLastN::new(2)))
+            .expendable_backups(&mut stored_backups);
+        assert_eq!(
+            check_newest_kept(&stored_backups, &expendable_backups),
+            Err(String::from("The newest backup C would be deleted, which is most likely a mistake. Pass --allow_delete_newest, if it isn't.")),
+        );
+        assert!(check_newest_kept(&[], &backups()).is_err());
+        assert!(check_newest_kept(&backups(), &[]).is_ok());
+    }
+
+    #[test]
+    fn test_delete_ids_from_stdin() {
+        match parse_command(&["apply", "-b", "my-database-backups", "-y", "--delete_ids_from_stdin"]).unwrap() {
+            Command::Apply(apply_opt) => assert!(apply_opt.delete_ids_from_stdin),
+            command => panic!("Parsed {:?}", command),
+        }
+        assert!(parse_command(&["apply", "-b", "my-database-backups", "--delete_ids_from_stdin"]).is_err());
+        assert!(parse_command(&["apply", "-b", "my-database-backups", "-y", "--delete_ids_from_stdin", "--plan", "plan.json"]).is_err());
+
+        let date = Utc.ymd(2014, 7, 1).and_hms(0, 0, 0);
+        let client = storage_client::InMemory::new(vec![
+            build_meta("backups/1.dump", date),
+            build_meta("backups/2.dump", date),
+            build_meta("backups/3.dump", date),
+        ]);
+        let ids = parse_in_use_ids("backups/1.dump\nbackups/1.dmup\n\nbackups/3.dump\nbackups/4.dump\n");
+
+        let stored_backups = client.stored_backups().unwrap();
+        assert_eq!(missing_ids(&ids, &stored_backups), vec!["backups/1.dmup", "backups/4.dump"]);
+
+        let summary = backups_cleaner::prune(&client, &listed_ids_strategy(ids)).unwrap();
+        assert_eq!((summary.deleted, summary.failed), (2, 0));
+        let remaining: Vec<String> = client.stored_backups().unwrap().into_iter().map(|backup| backup.id).collect();
+        assert_eq!(remaining, vec!["backups/2.dump"]);
+    }
+
+    #[test]
+    fn test_parse_in_use_ids() {
+        let contents = "# Restore tests of this week\nbackups/1.dump\n\n  backups/2.dump  \n";
+
+        assert_eq!(parse_in_use_ids(contents), vec!["backups/1.dump", "backups/2.dump"]);
+
+        // The in-use backups survive, although nothing would be kept otherwise.
+        let strategy = pruning_strategy::Pin::new(Box::new(pruning_strategy::KeepLastN::new(0)), parse_in_use_ids(contents));
+        let date = Utc.ymd(2014, 7, 1).and_hms(0, 0, 0);
+        let mut backups = vec![build_meta("backups/1.dump", date), build_meta("backups/2.dump", date), build_meta("backups/3.dump", date)];
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(expendable_backups.len(), 1);
+        assert_eq!(expendable_backups[0].id, "backups/3.dump");
+        assert_eq!(backups.len(), 2);
+    }
+
+    #[test]
+    fn test_check_clock() {
+        let reference_time = Utc.ymd(2014, 7, 10).and_hms(0, 0, 0);
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("B", reference_time + Duration::minutes(1)),
+        ];
+
+        assert!(check_clock(&backups, reference_time).is_ok());
+
+        backups.push(build_meta("C", Utc.ymd(2014, 7, 12).and_hms(0, 0, 0)));
+        assert_eq!(
+            check_clock(&backups, reference_time),
+            Err(String::from("1 backups are dated after 2014-07-10T00:00:00+00:00, up to 2014-07-12T00:00:00+00:00 (C). Check the clocks.")),
+        );
+    }
+
+    #[test]
+    fn test_parse_date_range() {
+        let (start, end) = parse_date_range("2022-07-01..2022-07-31").unwrap();
+        assert_eq!(start, Utc.ymd(2022, 7, 1).and_hms(0, 0, 0));
+        assert!(end > Utc.ymd(2022, 7, 31).and_hms(12, 0, 0));
+        assert!(end < Utc.ymd(2022, 8, 1).and_hms(0, 0, 0));
+
+        assert_eq!(
+            parse_date_range("2022-07-01..2022-07-31T00:00:00Z"),
+            Ok((Utc.ymd(2022, 7, 1).and_hms(0, 0, 0), Utc.ymd(2022, 7, 31).and_hms(0, 0, 0))),
+        );
+        assert!(parse_date_range("2022-07-01..2022-07-01").is_ok());
+        assert!(parse_date_range("2022-07-02..2022-07-01").is_err());
+        assert!(parse_date_range("2022-07-01").is_err());
+
+        let opt = parse_args(&[
+            "-r", "eu-west-2", "-b", "my-database-backups",
+            "--keep_all_within", "0", "--one_per_month_within", "0",
+            "--quarantine", "2022-07-01..2022-07-31",
+        ]);
+        let settings = settings(&opt).unwrap();
+        let pruning_strategy = build_pruning_strategy(&opt, &settings, Utc.ymd(2022, 9, 1).and_hms(0, 0, 0)).unwrap();
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2022, 7, 31).and_hms(12, 0, 0)),
+            build_meta("B", Utc.ymd(2022, 8, 1).and_hms(0, 0, 0)),
+        ];
+
+        let expendable_backups = pruning_strategy.expendable_backups(&mut backups);
+
+        assert_eq!(expendable_backups.iter().map(|backup| backup.id.as_str()).collect::<Vec<_>>(), vec!["B"]);
+        assert_eq!(backups.iter().map(|backup| backup.id.as_str()).collect::<Vec<_>>(), vec!["A"]);
+    }
+
+    #[test]
+    fn test_check_listed_backups_redacts_future_backups() {
+        let reference_time = Utc.ymd(2014, 7, 1).and_hms(0, 0, 0);
+        let mut listed_backups = vec![
+            build_meta("customers/acme-corp/2014-06-30.dump", Utc.ymd(2014, 6, 30).and_hms(0, 0, 0)),
+            build_meta("customers/acme-corp/2014-07-02.dump", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+        ];
+
+        let opt = parse_args(&["-b", "my-database-backups", "--redact"]);
+        let warning = check_listed_backups(&opt, &mut listed_backups.clone(), reference_time).unwrap().unwrap();
+        assert!(warning.contains("***"));
+        assert!(!warning.contains("acme-corp"));
+
+        let opt = parse_args(&["-b", "my-database-backups", "--redact", "--strict_clock"]);
+        let (code, error) = check_listed_backups(&opt, &mut listed_backups, reference_time).unwrap_err();
+        assert_eq!(code, 1);
+        assert!(!error.contains("acme-corp"));
+        assert!(listed_backups.iter().all(|backup| !backup.human_readable_id.contains("acme-corp")));
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("2014-07-12T01:02:03+02:00"), Ok(Utc.ymd(2014, 7, 11).and_hms(23, 2, 3)));
+        assert_eq!(parse_date("2014-07-12"), Ok(Utc.ymd(2014, 7, 12).and_hms(0, 0, 0)));
+        assert!(parse_date("last friday").is_err());
+    }
+}
@@ -0,0 +1,124 @@
+//! Plans written by `plan` and executed by `apply`, so the backups deleted are exactly
+//! the ones that have been reviewed.
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use chrono::{DateTime, Utc};
+use backups_cleaner::BackupFileMeta;
+use backups_cleaner::pruning_strategy::CustomStrategy;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Plan {
+    pub created_at: DateTime<Utc>,
+    pub bucket: String,
+    pub prefix: String,
+
+    /// The backups to delete.
+    pub expendable: Vec<BackupFileMeta>,
+}
+
+impl Plan {
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("A plan can always be serialized.")
+    }
+
+    pub fn from_json(json: &str) -> Result<Plan, String> {
+        serde_json::from_str(json).map_err(|error| format!("Invalid plan: {}", error))
+    }
+
+    /// Returns `true`, if `backup` is to be deleted. Backups that have changed since
+    /// planning (i.e. got another date or ETag) aren't.
+    pub fn is_planned(&self, backup: &BackupFileMeta) -> bool {
+        self.expendable.iter().any(|planned| {
+            planned.id == backup.id && planned.date == backup.date && planned.etag == backup.etag
+        })
+    }
+
+    /// Returns a strategy considering exactly the planned backups expendable.
+    pub fn into_strategy(self) -> CustomStrategy {
+        CustomStrategy::new(Box::new(move |backup, _| !self.is_planned(backup)))
+    }
+}
+
+/// Returns a short token identifying exactly the `expendable` backups, regardless of
+/// their order. Changing, adding or removing any of them (by id, date or ETag) changes
+/// the token, so an approver can check `apply` deletes the reviewed backups.
+pub fn approval_token(expendable: &[BackupFileMeta]) -> String {
+    let mut lines: Vec<String> = expendable
+        .iter()
+        .map(|backup| format!("{} {} {}", backup.id, backup.date.to_rfc3339(), backup.etag.as_deref().unwrap_or("")))
+        .collect();
+    lines.sort();
+
+    let mut hasher = Sha256::default();
+    for line in &lines {
+        hasher.input(line.as_bytes());
+        hasher.input(b"\n");
+    }
+
+    hasher.result().iter().take(6).map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backups_cleaner::pruning_strategy::PruningStrategy;
+    use chrono::offset::TimeZone;
+
+    fn build_meta(id: &str, etag: &str) -> BackupFileMeta {
+        BackupFileMeta {
+            etag: Some(String::from(etag)),
+            ..crate::tests::build_meta(id, Utc.ymd(2014, 7, 1).and_hms(0, 0, 0))
+        }
+    }
+
+    fn ids(backups: &[BackupFileMeta]) -> Vec<&str> {
+        backups.iter().map(|backup| backup.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_into_strategy() {
+        let plan = Plan {
+            created_at: Utc::now(),
+            bucket: String::from("my-database-backups"),
+            prefix: String::from("backups/"),
+            expendable: vec![build_meta("A", "\"1\""), build_meta("B", "\"2\""), build_meta("C", "\"3\"")],
+        };
+
+        // B has been overwritten since planning, C is gone.
+        let mut stored_backups = vec![build_meta("A", "\"1\""), build_meta("B", "\"4\""), build_meta("D", "\"5\"")];
+        let planned = plan.into_strategy().expendable_backups(&mut stored_backups);
+
+        assert_eq!(ids(&planned), vec!["A"]);
+        assert_eq!(ids(&stored_backups), vec!["B", "D"]);
+    }
+
+    #[test]
+    fn test_approval_token() {
+        let token = approval_token(&[build_meta("A", "\"1\""), build_meta("B", "\"2\"")]);
+
+        assert_eq!(token.len(), 12);
+        assert_eq!(approval_token(&[build_meta("B", "\"2\""), build_meta("A", "\"1\"")]), token);
+
+        // B has been overwritten, C has become expendable.
+        assert_ne!(approval_token(&[build_meta("A", "\"1\""), build_meta("B", "\"3\"")]), token);
+        assert_ne!(approval_token(&[build_meta("A", "\"1\""), build_meta("B", "\"2\""), build_meta("C", "\"3\"")]), token);
+        assert_ne!(approval_token(&[build_meta("A", "\"1\"")]), token);
+    }
+
+    #[test]
+    fn test_to_json_and_back() {
+        let plan = Plan {
+            created_at: Utc.ymd(2014, 7, 2).and_hms(0, 0, 0),
+            bucket: String::from("my-database-backups"),
+            prefix: String::from("backups/"),
+            expendable: vec![build_meta("A", "\"1\"")],
+        };
+
+        let parsed = Plan::from_json(&plan.to_json()).unwrap();
+
+        assert_eq!(parsed.created_at, plan.created_at);
+        assert_eq!(ids(&parsed.expendable), vec!["A"]);
+        assert!(Plan::from_json("{}").is_err());
+    }
+}
@@ -0,0 +1,55 @@
+//! Turns a prune plan into a shell script, so the deletion can be reviewed and run by
+//! someone holding the credentials to delete backups.
+use backups_cleaner::BackupFileMeta;
+
+/// Returns a shell script deleting each of the `backups` from `bucket` using the AWS CLI.
+pub fn delete_script(bucket: &str, backups: &[BackupFileMeta]) -> String {
+    let mut script = String::from("#!/bin/sh\nset -e\n");
+
+    for backup in backups {
+        script.push_str(&format!(
+            "aws s3api delete-object --bucket {} --key {}\n",
+            shell_quote(bucket),
+            shell_quote(&backup.id),
+        ));
+    }
+
+    script
+}
+
+/// Quotes `value` in single quotes, so the shell doesn't interpret any of its characters.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn build_meta(id: &str) -> BackupFileMeta {
+        crate::tests::build_meta(id, Utc::now())
+    }
+
+    #[test]
+    fn test_delete_script() {
+        let backups = vec![
+            build_meta("backups/2014-07-01.dump"),
+            build_meta("backups/it's $(rm -rf /).dump"),
+        ];
+
+        let script = delete_script("my-database-backups", &backups);
+
+        let commands: Vec<&str> = script.lines().filter(|line| line.starts_with("aws ")).collect();
+        assert_eq!(commands, vec![
+            "aws s3api delete-object --bucket 'my-database-backups' --key 'backups/2014-07-01.dump'",
+            "aws s3api delete-object --bucket 'my-database-backups' --key 'backups/it'\\''s $(rm -rf /).dump'",
+        ]);
+        assert!(script.starts_with("#!/bin/sh\n"));
+    }
+
+    #[test]
+    fn test_delete_script_with_no_backups_given() {
+        assert_eq!(delete_script("my-database-backups", &[]), "#!/bin/sh\nset -e\n");
+    }
+}
@@ -0,0 +1,347 @@
+//! Several buckets pruned in one run, as listed by a TOML file like
+//!
+//! ```toml
+//! [[target]]
+//! region = "eu-west-2"
+//! bucket = "customer-backups"
+//! keep_all_within = 14
+//! one_per_month_within = 365
+//!
+//! [[target]]
+//! region = "us-east-1"
+//! bucket = "analytics-backups"
+//! prefix = "daily/"
+//! keep_daily = 7
+//! ```
+//!
+//! Each target takes the same options as the config file, falling back to the ones
+//! given on the command line or in the config file for anything it leaves out. The
+//! retention policy is only taken as a whole, i.e. by targets that don't set any of its
+//! options.
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use serde::Deserialize;
+use backups_cleaner::{prune_all, BackupFileMeta, PruneSummary};
+use backups_cleaner::storage_client::StorageClient;
+use backups_cleaner::pruning_strategy::PruningStrategy;
+use crate::config::{Config, Settings};
+
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Targets {
+    #[serde(default, rename = "target")]
+    pub targets: Vec<Config>,
+}
+
+/// A bucket to prune, given by its location, along with its client and strategy.
+pub type Target = (String, Box<dyn StorageClient>, Box<dyn PruningStrategy>);
+
+/// Outcome of pruning each target, keyed by its location (`s3://BUCKET/PREFIX`), with
+/// the reason it failed as a whole.
+pub type TargetsReport = BTreeMap<String, Result<PruneSummary, String>>;
+
+/// Keeps all backups of a target, if `inner` would delete the newest one, remembering
+/// why in `refusal`.
+struct KeepNewest {
+    inner: Box<dyn PruningStrategy>,
+    refusal: Arc<Mutex<Option<String>>>,
+}
+
+impl PruningStrategy for KeepNewest {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        let mut expendable_backups = self.inner.expendable_backups(backups);
+
+        if let Err(error) = crate::check_newest_kept(backups, &expendable_backups) {
+            *self.refusal.lock().unwrap() = Some(error);
+            backups.append(&mut expendable_backups);
+            backups.sort_by_key(|backup| backup.date);
+        }
+
+        expendable_backups
+    }
+}
+
+impl Targets {
+
+    /// Reads the targets file at `path`. The error describes the offending field, if
+    /// the file is invalid.
+    pub fn from_file(path: &Path) -> Result<Targets, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|error| format!("Could not read targets file {}: {}", path.display(), error))?;
+
+        toml::from_str(&contents)
+            .map_err(|error| format!("Invalid targets file {}: {}", path.display(), error))
+    }
+
+    /// Returns the settings of each target, taking values it leaves out from
+    /// `fallback`. Fails, if any target is invalid or listed twice, as nothing should
+    /// be pruned before all of them are known to be valid.
+    pub fn into_settings(self, fallback: &Config) -> Result<Vec<Settings>, String> {
+        if self.targets.is_empty() {
+            return Err(String::from("The targets file lists no targets."));
+        }
+
+        let mut locations = HashSet::new();
+
+        self.targets
+            .into_iter()
+            .enumerate()
+            .map(|(index, target)| {
+                let fallback = if sets_retention(&target) {
+                    Config {
+                        keep_all_within: None,
+                        one_per_month_within: None,
+                        one_per_month_tolerance: None,
+                        keep_daily: None,
+                        keep_weekly: None,
+                        keep_monthly: None,
+                        keep_yearly: None,
+                        ..fallback.clone()
+                    }
+                }
+                else {
+                    fallback.clone()
+                };
+                let settings = target.or(fallback)
+                    .into_settings()
+                    .map_err(|error| format!("Invalid target #{}: {}", index + 1, error))?;
+
+                if !locations.insert(location(&settings)) {
+                    return Err(format!("{} is listed more than once.", location(&settings)));
+                }
+
+                Ok(settings)
+            })
+            .collect()
+    }
+}
+
+/// Whether `config` sets any option of the retention policy.
+fn sets_retention(config: &Config) -> bool {
+    config.keep_all_within.is_some()
+        || config.one_per_month_within.is_some()
+        || config.one_per_month_tolerance.is_some()
+        || [config.keep_daily, config.keep_weekly, config.keep_monthly, config.keep_yearly].iter().any(Option::is_some)
+}
+
+/// Returns where the backups of `settings` are located, e.g. `s3://backups/daily/`.
+pub fn location(settings: &Settings) -> String {
+    format!("s3://{}/{}", settings.bucket, settings.prefix)
+}
+
+/// Prunes each of the `targets`, given by their location, with at most `concurrency`
+/// of them at the same time. A failing target doesn't keep the others from being
+/// pruned. Unless `allow_delete_newest`, a target whose newest backup is expendable
+/// fails without deleting anything.
+pub fn prune_targets(targets: Vec<Target>, concurrency: usize, allow_delete_newest: bool) -> TargetsReport {
+    let mut locations = vec![];
+    let mut refusals = vec![];
+    let mut prunings: Vec<(Box<dyn StorageClient>, Box<dyn PruningStrategy>)> = vec![];
+    for (location, storage_client, pruning_strategy) in targets {
+        let refusal = Arc::new(Mutex::new(None));
+        let pruning_strategy: Box<dyn PruningStrategy> = if allow_delete_newest {
+            pruning_strategy
+        } else {
+            Box::new(KeepNewest { inner: pruning_strategy, refusal: refusal.clone() })
+        };
+
+        locations.push(location);
+        refusals.push(refusal);
+        prunings.push((storage_client, pruning_strategy));
+    }
+
+    let summaries = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("The runtime can always be built.")
+        .block_on(prune_all(prunings, concurrency));
+
+    locations
+        .into_iter()
+        .zip(refusals)
+        .zip(summaries)
+        .map(|((location, refusal), summary)| {
+            let result = match refusal.lock().unwrap().take() {
+                Some(error) => Err(error),
+                None => summary.map_err(|error| error.to_string()),
+            };
+
+            (location, result)
+        })
+        .collect()
+}
+
+/// Whether any of the targets failed, as a whole or to delete some backups.
+pub fn partially_failed(report: &TargetsReport) -> bool {
+    report.values().any(|result| result.as_ref().map_or(true, |summary| summary.failed > 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Retention;
+    use backups_cleaner::storage_client::{InMemory, DeletionReport, StorageError};
+    use backups_cleaner::pruning_strategy::KeepLastN;
+    use chrono::{TimeZone, Utc};
+
+    fn build_meta(id: &str) -> BackupFileMeta {
+        crate::tests::build_meta(id, Utc.ymd(2014, 7, 1).and_hms(0, 0, 0))
+    }
+
+    struct FailingClient;
+
+    impl StorageClient for FailingClient {
+
+        fn stored_backups(&self) -> Result<Vec<BackupFileMeta>, StorageError> {
+            Err(StorageError::AccessDenied(String::from("analytics-backups")))
+        }
+
+        fn delete_backups(&self, _backups: Vec<BackupFileMeta>) -> DeletionReport {
+            panic!("Nothing should be deleted.");
+        }
+    }
+
+    #[test]
+    fn test_into_settings() {
+        let targets: Targets = toml::from_str(r#"
+            [[target]]
+            bucket = "customer-backups"
+
+            [[target]]
+            bucket = "analytics-backups"
+            prefix = "daily/"
+            keep_daily = 7
+        "#).unwrap();
+        let fallback = Config {
+            region: vec![String::from("eu-west-2")],
+            keep_all_within: Some(14),
+            one_per_month_within: Some(365),
+            ..Config::default()
+        };
+
+        let settings = targets.into_settings(&fallback).unwrap();
+
+        assert_eq!(settings.iter().map(location).collect::<Vec<_>>(), vec!["s3://customer-backups/", "s3://analytics-backups/daily/"]);
+        assert_eq!(settings[1].region, "eu-west-2");
+        assert_eq!(settings[0].retention, Retention::Windows {
+            keep_all_within: 14,
+            one_per_month_within: 365,
+            one_per_month_tolerance: 15,
+        });
+        assert_eq!(settings[1].retention, Retention::PerPeriod {
+            daily: 7,
+            weekly: 0,
+            monthly: 0,
+            yearly: 0,
+        });
+    }
+
+    #[test]
+    fn test_into_settings_when_a_target_is_invalid() {
+        let targets: Targets = toml::from_str(r#"
+            [[target]]
+            region = "eu-west-2"
+            bucket = "customer-backups"
+            keep_daily = 7
+
+            [[target]]
+            bucket = "customer-backups"
+            keep_daily = 7
+        "#).unwrap();
+
+        assert_eq!(
+            targets.into_settings(&Config::default()),
+            Err(String::from("Invalid target #2: Missing `region`: pass --region or set it in the config file.")),
+        );
+        assert!(Targets::default().into_settings(&Config::default()).is_err());
+    }
+
+    #[test]
+    fn test_into_settings_when_a_target_is_listed_twice() {
+        let targets: Targets = toml::from_str(r#"
+            [[target]]
+            bucket = "customer-backups"
+
+            [[target]]
+            bucket = "customer-backups"
+        "#).unwrap();
+        let fallback = Config {
+            region: vec![String::from("eu-west-2")],
+            keep_daily: Some(7),
+            ..Config::default()
+        };
+
+        assert_eq!(targets.into_settings(&fallback), Err(String::from("s3://customer-backups/ is listed more than once.")));
+    }
+
+    #[test]
+    fn test_prune_targets() {
+        let targets: Vec<Target> = vec![
+            (
+                String::from("s3://customer-backups/"),
+                Box::new(InMemory::new(vec![build_meta("A"), build_meta("B"), build_meta("C")])),
+                Box::new(KeepLastN::new(1)),
+            ),
+            (String::from("s3://analytics-backups/"), Box::new(FailingClient), Box::new(KeepLastN::new(1))),
+            (
+                String::from("s3://logs/"),
+                Box::new(InMemory::new(vec![build_meta("D")])),
+                Box::new(KeepLastN::new(1)),
+            ),
+        ];
+
+        let report = prune_targets(targets, 2, false);
+
+        assert_eq!(report.len(), 3);
+        assert_eq!(report["s3://customer-backups/"].as_ref().unwrap().deleted, 2);
+        assert_eq!(report["s3://analytics-backups/"], Err(StorageError::AccessDenied(String::from("analytics-backups")).to_string()));
+        assert_eq!(report["s3://logs/"].as_ref().unwrap().kept, 1);
+        assert!(partially_failed(&report));
+
+        let report = prune_targets(vec![(String::from("s3://logs/"), Box::new(InMemory::new(vec![])), Box::new(KeepLastN::new(1)))], 1, false);
+        assert!(!partially_failed(&report));
+    }
+
+    #[test]
+    fn test_prune_targets_per_region() {
+
+        // The replica in us-east-1 lags behind, missing the newest backup.
+        let eu_west_2 = InMemory::new(vec![build_meta("A"), build_meta("B"), build_meta("C")]);
+        let us_east_1 = InMemory::new(vec![build_meta("A"), build_meta("B")]);
+        let targets: Vec<Target> = vec![
+            (String::from("eu-west-2"), Box::new(eu_west_2), Box::new(KeepLastN::new(2))),
+            (String::from("us-east-1"), Box::new(us_east_1), Box::new(KeepLastN::new(2))),
+        ];
+
+        let report = prune_targets(targets, 2, false);
+
+        let eu_west_2 = report["eu-west-2"].as_ref().unwrap();
+        assert_eq!((eu_west_2.total_found, eu_west_2.deleted, eu_west_2.kept), (3, 1, 2));
+        let us_east_1 = report["us-east-1"].as_ref().unwrap();
+        assert_eq!((us_east_1.total_found, us_east_1.deleted, us_east_1.kept), (2, 0, 2));
+    }
+
+    #[test]
+    fn test_prune_targets_when_the_newest_backup_is_expendable() {
+        let backups = || vec![build_meta("A"), build_meta("B")];
+        let logs = InMemory::new(backups());
+        let targets: Vec<Target> = vec![
+            (String::from("s3://customer-backups/"), Box::new(InMemory::new(backups())), Box::new(KeepLastN::new(1))),
+            (String::from("s3://logs/"), Box::new(logs), Box::new(KeepLastN::new(0))),
+        ];
+
+        let report = prune_targets(targets, 2, false);
+
+        assert_eq!(report["s3://customer-backups/"].as_ref().unwrap().deleted, 1);
+        assert_eq!(
+            report["s3://logs/"],
+            Err(String::from("The newest backup B would be deleted, which is most likely a mistake. Pass --allow_delete_newest, if it isn't.")),
+        );
+        assert!(partially_failed(&report));
+
+        let report = prune_targets(vec![(String::from("s3://logs/"), Box::new(InMemory::new(backups())), Box::new(KeepLastN::new(0)))], 1, true);
+        assert_eq!(report["s3://logs/"].as_ref().unwrap().deleted, 2);
+    }
+}
@@ -1,9 +1,200 @@
+use time::Duration;
 use chrono::{DateTime, Utc};
 
 /// Internally used abstraction of a single backup file.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BackupFileMeta {
     pub id: String,
     pub human_readable_id: String,
     pub date: DateTime<Utc>,
+
+    /// Size in bytes.
+    pub size: u64,
+
+    /// Storage class as reported by the host (e.g. `GLACIER` on AWS S3), if any.
+    pub storage_class: Option<String>,
+
+    /// Entity tag identifying the content, as reported by the host (e.g. AWS S3) including
+    /// its surrounding quotes, if any.
+    pub etag: Option<String>,
+
+    /// Whether the run creating the backup reported success (e.g. by a sidecar object
+    /// next to the backup), if known.
+    pub successful: Option<bool>,
+
+    /// Time until which the backup must be kept, as decided by its uploader (e.g. by
+    /// metadata on the object), if known.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub retain_until: Option<DateTime<Utc>>,
+
+    /// Retention tier the uploader has assigned the backup to (e.g. `monthly` by an
+    /// object tag), if known.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tier: Option<String>,
+
+    /// Version of the object on hosts keeping several versions per key (e.g. versioned
+    /// AWS S3 buckets), if the backups have been listed by version. Several backups may
+    /// share an `id` then.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub version_id: Option<String>,
+}
+
+/// Returns `true`, if `backups` are in chronological order. Equally dated backups may be
+/// in any order.
+pub fn is_sorted_by_date(backups: &[BackupFileMeta]) -> bool {
+    backups.windows(2).all(|pair| pair[0].date <= pair[1].date)
+}
+
+/// Panics in debug builds, if `backups` are not in chronological order. For functions
+/// relying on sorted input, e.g. to search them by date.
+pub fn assert_sorted_by_date(backups: &[BackupFileMeta]) {
+    debug_assert!(is_sorted_by_date(backups), "Backups must be sorted by date.");
+}
+
+/// Returns the `backups` dated more than `threshold` after `reference`. Any of them
+/// hint at a wrong clock on either side or wrong timestamps, which make deciding by age
+/// unsafe. A small `threshold` tolerates the clocks drifting apart a little.
+pub fn detect_future_backups(backups: &[BackupFileMeta], reference: DateTime<Utc>, threshold: Duration) -> Vec<&BackupFileMeta> {
+    backups.iter().filter(|backup| backup.age(reference) < -threshold).collect()
+}
+
+/// Storage classes of AWS S3, that archive objects and need them to be restored
+/// before they can be read.
+const ARCHIVE_STORAGE_CLASSES: [&str; 2] = ["GLACIER", "DEEP_ARCHIVE"];
+
+/// Number of trailing characters `redact` leaves visible, to tell backups apart.
+const VISIBLE_CHARACTERS_WHEN_REDACTED: usize = 6;
+
+impl BackupFileMeta {
+
+    /// Returns `true`, if the backup is kept in an archive storage class, such as
+    /// S3 Glacier.
+    pub fn is_archived(&self) -> bool {
+        match &self.storage_class {
+            Some(storage_class) => ARCHIVE_STORAGE_CLASSES.contains(&storage_class.as_str()),
+            None => false,
+        }
+    }
+
+    /// Returns how long before `reference` the backup has been created. Negative for
+    /// backups dated after `reference`.
+    pub fn age(&self, reference: DateTime<Utc>) -> Duration {
+        reference.signed_duration_since(self.date)
+    }
+
+    /// Returns `true`, if the backup is strictly older than `duration` at `reference`.
+    pub fn is_older_than(&self, duration: Duration, reference: DateTime<Utc>) -> bool {
+        self.age(reference) > duration
+    }
+
+    /// Masks all but the last few characters of `human_readable_id`, so it can be
+    /// shown in shared logs. The `id` is left as is, so the backup can still be deleted.
+    pub fn redact(&mut self) {
+        let characters: Vec<char> = self.human_readable_id.chars().collect();
+        let visible = characters.len().saturating_sub(VISIBLE_CHARACTERS_WHEN_REDACTED);
+        let tail: String = characters[visible..].iter().collect();
+
+        self.human_readable_id = format!("***{}", tail);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pruning_strategy::tests::build_meta;
+    use time::Duration;
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    #[test]
+    fn test_age() {
+        let backup = build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0));
+
+        assert_eq!(backup.age(Utc.ymd(2014, 7, 8).and_hms(12, 0, 0)), Duration::hours(7 * 24 + 12));
+        assert_eq!(backup.age(Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)), Duration::zero());
+    }
+
+    #[test]
+    fn test_age_of_a_future_dated_backup() {
+        let backup = build_meta("A", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0));
+
+        assert_eq!(backup.age(Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)), Duration::days(-1));
+        assert!(!backup.is_older_than(Duration::zero(), Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)));
+    }
+
+    #[test]
+    fn test_detect_future_backups() {
+        let reference = Utc.ymd(2014, 7, 1).and_hms(0, 0, 0);
+        let backups = vec![
+            build_meta("A", reference - Duration::days(1)),
+            build_meta("B", reference + Duration::minutes(5)),
+            build_meta("C", reference + Duration::minutes(6)),
+        ];
+
+        let future_backups: Vec<&str> = detect_future_backups(&backups, reference, Duration::minutes(5))
+            .into_iter()
+            .map(|backup| backup.id.as_str())
+            .collect();
+
+        assert_eq!(future_backups, vec!["C"]);
+        assert!(detect_future_backups(&backups, reference + Duration::hours(1), Duration::zero()).is_empty());
+    }
+
+    #[test]
+    fn test_is_older_than_at_the_boundary() {
+        let backup = build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0));
+        let reference = Utc.ymd(2014, 7, 8).and_hms(0, 0, 0);
+
+        assert!(!backup.is_older_than(Duration::days(7), reference));
+        assert!(backup.is_older_than(Duration::days(7) - Duration::seconds(1), reference));
+    }
+
+    #[test]
+    fn test_is_sorted_by_date() {
+        let sorted = vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+        ];
+        let unsorted = vec![
+            build_meta("A", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+        ];
+
+        assert!(is_sorted_by_date(&sorted));
+        assert!(is_sorted_by_date(&[]));
+        assert!(!is_sorted_by_date(&unsorted));
+        assert_sorted_by_date(&sorted);
+    }
+
+    #[test]
+    #[should_panic(expected = "Backups must be sorted by date.")]
+    #[cfg(debug_assertions)]
+    fn test_assert_sorted_by_date_when_unsorted() {
+        assert_sorted_by_date(&[
+            build_meta("A", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+        ]);
+    }
+
+    #[test]
+    fn test_redact() {
+        let mut backup = build_meta("db.acme-corp.internal/2014-07-01.dump", Utc::now());
+
+        backup.redact();
+
+        assert_eq!(backup.human_readable_id, "***1.dump");
+        assert!(!backup.human_readable_id.contains("acme-corp"));
+        assert_eq!(backup.id, "db.acme-corp.internal/2014-07-01.dump");
+    }
+
+    #[test]
+    fn test_redact_with_a_short_id() {
+        let mut backup = build_meta("dump", Utc::now());
+
+        backup.redact();
+
+        assert_eq!(backup.human_readable_id, "***dump");
+    }
 }
@@ -0,0 +1,103 @@
+//! Compares what two pruning strategies would delete, to help choosing between
+//! candidate retention policies without deleting anything.
+use super::BackupFileMeta;
+use super::pruning_strategy::PruningStrategy;
+use std::collections::HashSet;
+
+/// Outcome of running two strategies against the same backups. Backups are given in
+/// chronological order.
+#[derive(Debug)]
+pub struct StrategyComparison {
+
+    /// Backups the first strategy keeps, but the second one deletes.
+    pub kept_only_by_first: Vec<BackupFileMeta>,
+
+    /// Backups the second strategy keeps, but the first one deletes.
+    pub kept_only_by_second: Vec<BackupFileMeta>,
+
+    /// Number of backups the first strategy deletes.
+    pub deleted_by_first: usize,
+
+    /// Number of backups the second strategy deletes.
+    pub deleted_by_second: usize,
+}
+
+/// Runs `first` and `second` against copies of `backups`, leaving `backups` untouched.
+pub fn compare(backups: &[BackupFileMeta], first: &dyn PruningStrategy, second: &dyn PruningStrategy) -> StrategyComparison {
+    let expendable_ids = |strategy: &dyn PruningStrategy| -> HashSet<String> {
+        strategy
+            .expendable_backups(&mut backups.to_vec())
+            .into_iter()
+            .map(|backup| backup.id)
+            .collect()
+    };
+    let deleted_by_first = expendable_ids(first);
+    let deleted_by_second = expendable_ids(second);
+
+    let mut sorted_backups = backups.to_vec();
+    sorted_backups.sort_by_key(|backup| backup.date);
+    let kept_only_by = |kept_by: &HashSet<String>, deleted_by: &HashSet<String>| -> Vec<BackupFileMeta> {
+        sorted_backups
+            .iter()
+            .filter(|backup| !kept_by.contains(&backup.id) && deleted_by.contains(&backup.id))
+            .cloned()
+            .collect()
+    };
+
+    StrategyComparison {
+        kept_only_by_first: kept_only_by(&deleted_by_first, &deleted_by_second),
+        kept_only_by_second: kept_only_by(&deleted_by_second, &deleted_by_first),
+        deleted_by_first: deleted_by_first.len(),
+        deleted_by_second: deleted_by_second.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pruning_strategy::{KeepLastN, OlderThan};
+    use crate::pruning_strategy::tests::{build_meta, collect_ids, as_vector};
+    use crate::storage_client::{StorageClient, InMemory};
+    use time::Duration;
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    #[test]
+    fn test_compare() {
+        let client = InMemory::new(vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 6).and_hms(0, 0, 0)),
+            build_meta("D", Utc.ymd(2014, 7, 7).and_hms(0, 0, 0)),
+            build_meta("E", Utc.ymd(2014, 7, 8).and_hms(0, 0, 0)),
+        ]);
+        let backups = client.stored_backups().unwrap();
+        let older_than = OlderThan::new(Duration::days(5), Utc.ymd(2014, 7, 8).and_hms(0, 0, 0));
+        let keep_last_n = KeepLastN::new(2);
+
+        let comparison = compare(&backups, &older_than, &keep_last_n);
+
+        assert_eq!(collect_ids(comparison.kept_only_by_first), as_vector("C"));
+        assert!(comparison.kept_only_by_second.is_empty());
+        assert_eq!(comparison.deleted_by_first, 2);
+        assert_eq!(comparison.deleted_by_second, 3);
+        assert_eq!(client.stored_backups().unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_compare_when_both_keep_the_same_backups() {
+        let backups = vec![
+            build_meta("B", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+        ];
+        let older_than = OlderThan::new(Duration::days(1), Utc.ymd(2014, 7, 2).and_hms(12, 0, 0));
+
+        let comparison = compare(&backups, &older_than, &KeepLastN::new(1));
+
+        assert!(comparison.kept_only_by_first.is_empty());
+        assert!(comparison.kept_only_by_second.is_empty());
+        assert_eq!(comparison.deleted_by_first, 1);
+        assert_eq!(comparison.deleted_by_second, 1);
+        assert_eq!(collect_ids(backups), as_vector("BA"));
+    }
+}
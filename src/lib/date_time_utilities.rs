@@ -0,0 +1,80 @@
+//! Helper functions for date time objects.
+use chrono::{DateTime, Utc, Datelike};
+use chrono::offset::TimeZone;
+use time::Duration;
+
+/// Return a new date that points to the beginning of the day
+/// of `date`.
+pub fn beginning_of_day(date: DateTime<Utc>) -> DateTime<Utc> {
+    date.date().and_hms(0, 0, 0)
+}
+
+/// Return a new date that points to the beginning of the week
+/// (Monday) of `date`.
+pub fn beginning_of_week(date: DateTime<Utc>) -> DateTime<Utc> {
+    beginning_of_day(date) - Duration::days(i64::from(date.weekday().num_days_from_monday()))
+}
+
+/// Return a new date that points to the beginning of the month
+/// of `date`.
+pub fn beginning_of_month(date: DateTime<Utc>) -> DateTime<Utc> {
+    Utc.ymd(date.year(), date.month(), 1).and_hms(0, 0, 0)
+}
+
+/// Return a new date that points to the beginning of the following
+/// month of `date`.
+pub fn beginning_of_next_month(date: DateTime<Utc>) -> DateTime<Utc> {
+    let year;
+    let month;
+
+    if date.month() == 12 {
+        year = date.year() + 1;
+        month = 1;
+    }
+    else {
+        year = date.year();
+        month = date.month() + 1;
+    }
+
+    Utc.ymd(year, month, 1).and_hms(0, 0, 0)
+}
+
+/// Return a new date that points to the beginning of the year
+/// of `date`.
+pub fn beginning_of_year(date: DateTime<Utc>) -> DateTime<Utc> {
+    Utc.ymd(date.year(), 1, 1).and_hms(0, 0, 0)
+}
+
+/// Return a new date that points to the beginning of the following
+/// year of `date`.
+pub fn beginning_of_next_year(date: DateTime<Utc>) -> DateTime<Utc> {
+    Utc.ymd(date.year() + 1, 1, 1).and_hms(0, 0, 0)
+}
+
+/// Return a new date that points to the beginning of the quarter of
+/// `date`, for a fiscal year starting in `fiscal_year_start_month` (1 for
+/// calendar quarters).
+pub fn beginning_of_quarter(date: DateTime<Utc>, fiscal_year_start_month: u32) -> DateTime<Utc> {
+    let months_into_quarter = (date.month() + 12 - fiscal_year_start_month) % 3;
+
+    beginning_of_nth_month(date.year() * 12 + date.month0() as i32 - months_into_quarter as i32)
+}
+
+/// Return a new date that points to the beginning of the following
+/// quarter of `date`, see `beginning_of_quarter`.
+pub fn beginning_of_next_quarter(date: DateTime<Utc>, fiscal_year_start_month: u32) -> DateTime<Utc> {
+    let beginning = beginning_of_quarter(date, fiscal_year_start_month);
+
+    beginning_of_nth_month(beginning.year() * 12 + beginning.month0() as i32 + 3)
+}
+
+/// Return the beginning of the month `n` months after January of year 0.
+fn beginning_of_nth_month(n: i32) -> DateTime<Utc> {
+    Utc.ymd(n.div_euclid(12), n.rem_euclid(12) as u32 + 1, 1).and_hms(0, 0, 0)
+}
+
+/// Returns `true`, if `date_a` is closer to `to_date` than `date_b`, `false`
+/// otherwise.
+pub fn is_closer(to_date: DateTime<Utc>, date_a: DateTime<Utc>, date_b: DateTime<Utc>) -> bool {
+    (date_a - to_date).num_seconds().abs() < (date_b - to_date).num_seconds().abs()
+}
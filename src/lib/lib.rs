@@ -23,12 +23,32 @@
 //!
 //! // Perform pruning. The following code works for whatever client and strategy you
 //! // have chosen.
-//! let mut stored_backups = storage_client.stored_backups();
+//! let mut stored_backups = storage_client.stored_backups().unwrap();
 //! let expendable_backups = pruning_strategy.expendable_backups(&mut stored_backups);
 //! storage_client.delete_backups(expendable_backups);
 //! ```
+//!
+//! # Features
+//!
+//! `serde` (enabled by default) derives `Serialize` and `Deserialize` for backups,
+//! summaries and reports, and provides the modules reading and writing JSON, such as
+//! `manifest` and `receipt`.
+//!
+//! `opendal` provides `storage_client::OpenDal`, a client for any storage the opendal
+//! crate supports, such as GCS, Azure Blob Storage or WebDAV.
 mod backup_file_meta;
+mod date_time_utilities;
+mod prune;
 pub mod storage_client;
 pub mod pruning_strategy;
+pub mod metrics;
+pub mod reporting;
+pub mod lifecycle;
+#[cfg(feature = "serde")]
+pub mod manifest;
+pub mod comparison;
+#[cfg(feature = "serde")]
+pub mod receipt;
 
-pub use backup_file_meta::BackupFileMeta;
+pub use backup_file_meta::{BackupFileMeta, is_sorted_by_date, assert_sorted_by_date, detect_future_backups};
+pub use prune::{prune, prune_selected, prune_all, prune_streaming, PruneSummary, PhaseTimings};
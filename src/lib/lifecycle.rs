@@ -0,0 +1,124 @@
+//! Expresses pruning strategies as AWS S3 lifecycle configurations, so the pruning can
+//! be left to AWS. For large buckets this is considerably cheaper than deleting each
+//! backup with a request.
+use super::pruning_strategy::PruningStrategy;
+
+/// Returns a lifecycle configuration in JSON, as accepted by
+/// `aws s3api put-bucket-lifecycle-configuration`, that expires all objects under `prefix`
+/// the given `strategy` considers expendable. Fails, if the strategy can't be expressed by
+/// an expiration rule, i.e. if it's not purely based on the age of backups.
+///
+/// AWS S3 counts ages in whole days, so the age is rounded up to the next day.
+pub fn lifecycle_configuration(strategy: &dyn PruningStrategy, prefix: &str) -> Result<String, String> {
+    let max_age = strategy.max_age()
+        .ok_or_else(|| String::from("The pruning strategy isn't purely age based, so it can't be expressed as a lifecycle rule."))?;
+
+    let mut days = max_age.num_days();
+    if max_age > time::Duration::days(days) {
+        days += 1;
+    }
+
+    if days < 1 {
+        return Err(String::from("Lifecycle rules can't expire objects younger than a day."));
+    }
+
+    Ok(format!(
+r#"{{
+  "Rules": [
+    {{
+      "ID": "backups-cleaner",
+      "Filter": {{
+        "Prefix": "{}"
+      }},
+      "Status": "Enabled",
+      "Expiration": {{
+        "Days": {}
+      }}
+    }}
+  ]
+}}
+"#,
+        escape_string(prefix),
+        days,
+    ))
+}
+
+fn escape_string(value: &str) -> String {
+    let mut escaped = String::new();
+
+    for character in value.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            character if character.is_control() => escaped.push_str(&format!("\\u{:04x}", character as u32)),
+            character => escaped.push(character),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::pruning_strategy::{KeepLastN, OlderThan, OlderThanButKeepOnePerMonth};
+    use time::Duration;
+    use chrono::Utc;
+
+    #[test]
+    fn test_lifecycle_configuration() {
+        let strategy = OlderThan::new(Duration::days(30), Utc::now());
+
+        assert_eq!(lifecycle_configuration(&strategy, "backups/").unwrap(), r#"{
+  "Rules": [
+    {
+      "ID": "backups-cleaner",
+      "Filter": {
+        "Prefix": "backups/"
+      },
+      "Status": "Enabled",
+      "Expiration": {
+        "Days": 30
+      }
+    }
+  ]
+}
+"#);
+    }
+
+    #[test]
+    fn test_lifecycle_configuration_rounds_up_to_whole_days() {
+        let strategy = OlderThan::new(Duration::days(1) + Duration::hours(1), Utc::now());
+
+        assert!(lifecycle_configuration(&strategy, "").unwrap().contains(r#""Days": 2"#));
+    }
+
+    #[test]
+    fn test_lifecycle_configuration_escapes_the_prefix() {
+        let strategy = OlderThan::new(Duration::days(30), Utc::now());
+
+        assert!(lifecycle_configuration(&strategy, "a\"b\\").unwrap().contains(r#""Prefix": "a\"b\\""#));
+    }
+
+    #[test]
+    fn test_lifecycle_configuration_without_monthly_history() {
+        let strategy = OlderThanButKeepOnePerMonth::new(Utc::now(), Duration::days(14), Duration::days(15), Duration::days(14));
+
+        assert!(lifecycle_configuration(&strategy, "").unwrap().contains(r#""Days": 14"#));
+    }
+
+    #[test]
+    fn test_lifecycle_configuration_when_the_strategy_is_not_age_based() {
+        let with_history = OlderThanButKeepOnePerMonth::new(Utc::now(), Duration::days(14), Duration::days(15), Duration::days(365));
+
+        assert!(lifecycle_configuration(&with_history, "").is_err());
+        assert!(lifecycle_configuration(&KeepLastN::new(3), "").is_err());
+    }
+
+    #[test]
+    fn test_lifecycle_configuration_when_the_age_is_zero() {
+        let strategy = OlderThan::new(Duration::zero(), Utc::now());
+
+        assert!(lifecycle_configuration(&strategy, "").is_err());
+    }
+}
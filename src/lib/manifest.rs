@@ -0,0 +1,141 @@
+//! Reads restore manifests, which list the backups currently needed for recovery. Such
+//! backups must never be pruned, see `pruning_strategy::Pin`. Also writes inventories
+//! of the stored backups in the same format, for downstream systems to rely on.
+use std::collections::HashSet;
+use serde::{Serialize, Deserialize};
+use chrono::{DateTime, Utc};
+use super::BackupFileMeta;
+use super::storage_client::DeletionReport;
+
+#[derive(Deserialize)]
+struct Manifest {
+    backups: Vec<String>,
+}
+
+/// Returns the ids of the backups referenced by `manifest`, a JSON object listing them
+/// under `backups`, e.g. `{"backups": ["backups/2014-07-01.dump"]}`. Other fields are
+/// ignored.
+pub fn referenced_ids(manifest: &str) -> Result<Vec<String>, String> {
+    serde_json::from_str::<Manifest>(manifest)
+        .map(|manifest| manifest.backups)
+        .map_err(|error| format!("Invalid manifest: {}", error))
+}
+
+#[derive(Serialize)]
+struct Inventory<'a> {
+    created: DateTime<Utc>,
+
+    /// Ids only, so the inventory can be read as manifest.
+    backups: Vec<&'a str>,
+    details: Vec<InventoryEntry<'a>>,
+}
+
+#[derive(Serialize)]
+struct InventoryEntry<'a> {
+    id: &'a str,
+    date: DateTime<Utc>,
+    size: u64,
+}
+
+/// Returns a manifest listing `backups` as of `created`, along with their dates and
+/// sizes, e.g. `{"created": "2014-07-03T00:00:00Z", "backups": ["backups/2014-07-01.dump"],
+/// "details": [{"id": "backups/2014-07-01.dump", "date": "2014-07-01T00:00:00Z", "size": 1024}]}`.
+pub fn inventory(backups: &[BackupFileMeta], created: DateTime<Utc>) -> String {
+    let inventory = Inventory {
+        created,
+        backups: backups.iter().map(|backup| backup.id.as_str()).collect(),
+        details: backups
+            .iter()
+            .map(|backup| InventoryEntry {
+                id: &backup.id,
+                date: backup.date,
+                size: backup.size,
+            })
+            .collect(),
+    };
+
+    serde_json::to_string_pretty(&inventory).expect("An inventory can always be serialized.")
+}
+
+/// Returns the `listed` backups, that are still stored after `deletion_report`, i.e.
+/// all but the deleted ones, in chronological order. Backups, that failed to be
+/// deleted, remain.
+pub fn remaining_backups(listed: Vec<BackupFileMeta>, deletion_report: &DeletionReport) -> Vec<BackupFileMeta> {
+    let deleted_ids: HashSet<&str> = deletion_report.deleted.iter().map(|backup| backup.id.as_str()).collect();
+    let mut remaining: Vec<BackupFileMeta> = listed
+        .into_iter()
+        .filter(|backup| !deleted_ids.contains(backup.id.as_str()))
+        .collect();
+    remaining.sort_by_key(|backup| backup.date);
+
+    remaining
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pruning_strategy::{PruningStrategy, KeepLastN, Pin};
+    use crate::pruning_strategy::tests::{build_meta, collect_ids};
+    use crate::storage_client::{StorageClient, InMemory};
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    #[test]
+    fn test_referenced_ids() {
+        let manifest = r#"{"created": "2014-07-03", "backups": ["backups/2014-07-01.dump", "backups/2014-07-02.dump"]}"#;
+
+        assert_eq!(referenced_ids(manifest).unwrap(), vec!["backups/2014-07-01.dump", "backups/2014-07-02.dump"]);
+    }
+
+    #[test]
+    fn test_referenced_ids_when_the_manifest_is_invalid() {
+        assert!(referenced_ids(r#"{"backups": "backups/2014-07-01.dump"}"#).is_err());
+        assert!(referenced_ids(r#"{"created": "2014-07-03"}"#).is_err());
+        assert!(referenced_ids("").is_err());
+    }
+
+    #[test]
+    fn test_referenced_ids_protect_backups() {
+        let client = InMemory::new(vec![
+            build_meta("backups/2014-07-01.dump", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("backups/2014-07-02.dump", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+            build_meta("backups/2014-07-03.dump", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0)),
+        ]);
+        let ids = referenced_ids(r#"{"backups": ["backups/2014-07-02.dump"]}"#).unwrap();
+        let strategy = Pin::new(Box::new(KeepLastN::new(0)), ids);
+
+        let mut backups = client.stored_backups().unwrap();
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), vec!["backups/2014-07-01.dump", "backups/2014-07-03.dump"]);
+        assert_eq!(collect_ids(backups), vec!["backups/2014-07-02.dump"]);
+    }
+
+    #[test]
+    fn test_inventory_after_pruning() {
+        let client = InMemory::new(vec![
+            build_meta("backups/2014-07-01.dump", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("backups/2014-07-02.dump", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+            build_meta("backups/2014-07-03.dump", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0)),
+        ]);
+        let listed = client.stored_backups().unwrap();
+        let mut backups = listed.clone();
+        let mut expendable_backups = KeepLastN::new(1).expendable_backups(&mut backups);
+
+        // The oldest backup can't be deleted, as it's gone already.
+        expendable_backups[0].id = String::from("backups/2014-06-30.dump");
+        let deletion_report = client.delete_backups(expendable_backups);
+        let remaining = remaining_backups(listed, &deletion_report);
+
+        assert_eq!(collect_ids(remaining.clone()), collect_ids(client.stored_backups().unwrap()));
+
+        let inventory = inventory(&remaining, Utc.ymd(2014, 7, 4).and_hms(0, 0, 0));
+        assert_eq!(referenced_ids(&inventory).unwrap(), vec!["backups/2014-07-01.dump", "backups/2014-07-03.dump"]);
+
+        let json: serde_json::Value = serde_json::from_str(&inventory).unwrap();
+        assert_eq!(json["created"], "2014-07-04T00:00:00Z");
+        assert_eq!(json["details"][1]["id"], "backups/2014-07-03.dump");
+        assert_eq!(json["details"][1]["date"], "2014-07-03T00:00:00Z");
+        assert_eq!(json["details"][1]["size"], 0);
+    }
+}
@@ -0,0 +1,162 @@
+//! Summarizes a pruning run as metrics, which can be picked up by monitoring tools
+//! such as Prometheus.
+use super::BackupFileMeta;
+use super::storage_client::DeletionReport;
+
+/// Key figures of a single pruning run.
+#[derive(Debug, PartialEq)]
+pub struct Metrics {
+    pub backups_total: usize,
+    pub backups_expendable: usize,
+    pub backups_deleted: usize,
+    pub backups_bytes_total: u64,
+    pub backups_bytes_freed: u64,
+}
+
+impl Metrics {
+
+    /// Collects the metrics of a run that considered the `kept` and `expendable`
+    /// backups, before any of them have been deleted.
+    pub fn new(kept: &[BackupFileMeta], expendable: &[BackupFileMeta]) -> Metrics {
+        let bytes = |backups: &[BackupFileMeta]| -> u64 {
+            backups.iter().map(|backup| backup.size).sum()
+        };
+
+        Metrics {
+            backups_total: kept.len() + expendable.len(),
+            backups_expendable: expendable.len(),
+            backups_deleted: 0,
+            backups_bytes_total: bytes(kept) + bytes(expendable),
+            backups_bytes_freed: 0,
+        }
+    }
+
+    /// Accounts for the outcome of deleting the expendable backups.
+    pub fn record_deletion(&mut self, deletion_report: &DeletionReport) {
+        self.backups_deleted += deletion_report.deleted.len();
+        self.backups_bytes_freed += deletion_report.bytes_freed();
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format, labeled with
+    /// `bucket` and `prefix`.
+    pub fn to_prometheus(&self, bucket: &str, prefix: &str) -> String {
+        let labels = format!("bucket=\"{}\",prefix=\"{}\"", escape_label_value(bucket), escape_label_value(prefix));
+        let metrics: [(&str, &str, u64); 5] = [
+            ("backups_total", "Number of stored backups found.", self.backups_total as u64),
+            ("backups_expendable", "Number of backups considered expendable.", self.backups_expendable as u64),
+            ("backups_deleted", "Number of backups deleted.", self.backups_deleted as u64),
+            ("backups_bytes_total", "Total size of the stored backups found in bytes.", self.backups_bytes_total),
+            ("backups_bytes_freed", "Total size of the deleted backups in bytes.", self.backups_bytes_freed),
+        ];
+
+        let mut output = String::new();
+        for (name, help, value) in metrics.iter() {
+            output.push_str(&format!("# HELP {} {}\n", name, help));
+            output.push_str(&format!("# TYPE {} gauge\n", name));
+            output.push_str(&format!("{}{{{}}} {}\n", name, labels, value));
+        }
+
+        output
+    }
+}
+
+/// Escapes backslashes, double quotes and line feeds, as required for label values.
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn build_meta(id: &str, size: u64) -> BackupFileMeta {
+        BackupFileMeta {
+            size,
+            ..crate::pruning_strategy::tests::build_meta(id, Utc::now())
+        }
+    }
+
+    /// Checks that `line` is a sample line of the Prometheus text exposition format,
+    /// returning its metric name.
+    fn parse_sample(line: &str) -> String {
+        let name_end = line.find('{').unwrap();
+        let labels_end = line.rfind('}').unwrap();
+        let name = &line[..name_end];
+        let value = line[labels_end + 1..].trim_start_matches(' ');
+
+        assert!(name.chars().all(|character| character.is_ascii_alphanumeric() || character == '_'));
+        assert!(line[labels_end + 1..].starts_with(' '));
+        value.parse::<f64>().unwrap();
+
+        for label in line[name_end + 1..labels_end].split("\",") {
+            let (label_name, label_value) = label.split_at(label.find("=\"").unwrap());
+            assert!(label_name.chars().all(|character| character.is_ascii_alphanumeric() || character == '_'));
+            assert!(label_value.starts_with("=\""));
+        }
+
+        String::from(name)
+    }
+
+    #[test]
+    fn test_new_and_record_deletion() {
+        let kept = vec![build_meta("A", 1), build_meta("B", 2)];
+        let expendable = vec![build_meta("C", 4), build_meta("D", 8)];
+        let report = DeletionReport {
+            deleted: vec![build_meta("C", 4)],
+            failed: vec![(build_meta("D", 8), String::from("Access Denied"))],
+            tagged: vec![],
+            protected: vec![],
+            kms_denied: vec![],
+            remaining: vec![],
+            failed_companions: vec![],
+        };
+
+        let mut metrics = Metrics::new(&kept, &expendable);
+
+        assert_eq!(metrics, Metrics {
+            backups_total: 4,
+            backups_expendable: 2,
+            backups_deleted: 0,
+            backups_bytes_total: 15,
+            backups_bytes_freed: 0,
+        });
+
+        metrics.record_deletion(&report);
+
+        assert_eq!(metrics.backups_deleted, 1);
+        assert_eq!(metrics.backups_bytes_freed, 4);
+    }
+
+    #[test]
+    fn test_to_prometheus() {
+        let metrics = Metrics {
+            backups_total: 4,
+            backups_expendable: 2,
+            backups_deleted: 1,
+            backups_bytes_total: 15,
+            backups_bytes_freed: 4,
+        };
+
+        let output = metrics.to_prometheus("my-database-backups", "backups/\"daily\"");
+
+        let mut names = vec![];
+        for line in output.lines() {
+            if line.starts_with("# HELP ") || line.starts_with("# TYPE ") {
+                continue;
+            }
+            names.push(parse_sample(line));
+        }
+
+        assert!(output.ends_with('\n'));
+        assert_eq!(names, vec![
+            "backups_total",
+            "backups_expendable",
+            "backups_deleted",
+            "backups_bytes_total",
+            "backups_bytes_freed",
+        ]);
+        assert!(output.contains("backups_deleted{bucket=\"my-database-backups\",prefix=\"backups/\\\"daily\\\"\"} 1\n"));
+        assert!(output.contains("# TYPE backups_bytes_freed gauge\n"));
+    }
+}
@@ -0,0 +1,567 @@
+//! High-level functions performing a complete pruning, from listing the stored
+//! backups up to deleting the expendable ones.
+use super::BackupFileMeta;
+use super::storage_client::{StorageClient, StorageError, DeletionCounts, DeletionReport};
+use super::pruning_strategy::PruningStrategy;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Semaphore};
+
+/// Outcome of a single pruning. All output formats (human readable, JSON and metrics)
+/// are rendered from it.
+#[derive(Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PruneSummary {
+
+    /// Number of stored backups found.
+    pub total_found: usize,
+
+    /// Number of backups the pruning strategy has kept.
+    pub kept: usize,
+
+    /// Number of backups that have been deleted.
+    pub deleted: usize,
+
+    /// Number of backups that could not be deleted.
+    pub failed: usize,
+
+    /// Number of expendable backups left for the next run, as the time budget of this
+    /// one has run out.
+    pub remaining: usize,
+
+    /// Total size of the deleted backups in bytes.
+    pub bytes_freed: u64,
+
+    pub timings: PhaseTimings,
+}
+
+/// Wall-clock durations of the phases of a pruning, to tell which of them dominates.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PhaseTimings {
+
+    /// Time spent listing the stored backups.
+    pub listing: Duration,
+
+    /// Time spent deciding which backups are expendable.
+    pub evaluation: Duration,
+
+    /// Time spent deleting the expendable backups.
+    pub deletion: Duration,
+}
+
+/// Deletes all backups stored by `storage_client`, that `pruning_strategy` considers
+/// expendable. Does not ask for confirmation. Fails, if the stored backups can't be
+/// listed. Backups that can't be deleted don't fail pruning, they're counted as `failed`
+/// instead, so the ones that have been deleted aren't lost track of.
+pub fn prune(storage_client: &dyn StorageClient, pruning_strategy: &dyn PruningStrategy) -> Result<PruneSummary, StorageError> {
+    let mut summary = PruneSummary::default();
+    let (_, expendable_backups) = list_and_evaluate(storage_client, pruning_strategy, &mut summary)?;
+
+    let started = Instant::now();
+    if !expendable_backups.is_empty() {
+        summary.record_deletion(storage_client.delete_backups_counting(expendable_backups));
+    }
+    summary.timings.deletion = started.elapsed();
+
+    Ok(summary)
+}
+
+/// Performs `prune`, but lets `select` decide which of the expendable backups to delete
+/// after all, given the kept ones, e.g. by asking for confirmation. Returns the report of
+/// the deletion along with the summary, to tell which backups have been deleted.
+pub fn prune_selected(
+    storage_client: &dyn StorageClient,
+    pruning_strategy: &dyn PruningStrategy,
+    select: impl FnOnce(&[BackupFileMeta], Vec<BackupFileMeta>) -> Vec<BackupFileMeta>,
+) -> Result<(PruneSummary, DeletionReport), StorageError> {
+    let mut summary = PruneSummary::default();
+    let (kept_backups, expendable_backups) = list_and_evaluate(storage_client, pruning_strategy, &mut summary)?;
+    let backups_to_delete = select(&kept_backups, expendable_backups);
+
+    let started = Instant::now();
+    let deletion_report = if backups_to_delete.is_empty() {
+        DeletionReport::default()
+    }
+    else {
+        storage_client.delete_backups(backups_to_delete)
+    };
+    summary.timings.deletion = started.elapsed();
+    summary.record_deletion(DeletionCounts::from(&deletion_report));
+
+    Ok((summary, deletion_report))
+}
+
+/// Lists the backups stored by `storage_client` and splits them into the ones
+/// `pruning_strategy` keeps and the expendable ones, recording the numbers and timings
+/// in `summary`.
+fn list_and_evaluate(
+    storage_client: &dyn StorageClient,
+    pruning_strategy: &dyn PruningStrategy,
+    summary: &mut PruneSummary,
+) -> Result<(Vec<BackupFileMeta>, Vec<BackupFileMeta>), StorageError> {
+    let started = Instant::now();
+    let mut stored_backups = storage_client.stored_backups()?;
+    summary.total_found = stored_backups.len();
+    summary.timings.listing = started.elapsed();
+
+    let started = Instant::now();
+    let expendable_backups = pruning_strategy.expendable_backups(&mut stored_backups);
+    summary.timings.evaluation = started.elapsed();
+    summary.kept = stored_backups.len();
+
+    Ok((stored_backups, expendable_backups))
+}
+
+impl PruneSummary {
+
+    /// Records the outcome of deleting the expendable backups.
+    fn record_deletion(&mut self, deletion_counts: DeletionCounts) {
+        self.deleted = deletion_counts.deleted;
+        self.failed = deletion_counts.failed;
+        self.remaining = deletion_counts.remaining;
+        self.bytes_freed = deletion_counts.bytes_freed;
+    }
+}
+
+/// Performs `prune` for each pair of client and strategy concurrently, with at most
+/// `max_in_flight` prunings running at the same time. Returns the summaries in the
+/// order of `targets`.
+pub async fn prune_all(
+    targets: Vec<(Box<dyn StorageClient>, Box<dyn PruningStrategy>)>,
+    max_in_flight: usize,
+) -> Vec<Result<PruneSummary, StorageError>> {
+
+    // Panic, as no pruning could ever start otherwise.
+    assert!(max_in_flight > 0);
+
+    let semaphore = Arc::new(Semaphore::new(max_in_flight));
+    let tasks: Vec<_> = targets
+        .into_iter()
+        .map(|(storage_client, pruning_strategy)| {
+            let semaphore = semaphore.clone();
+
+            tokio::spawn(async move {
+                let _permit = semaphore.acquire_owned().await.unwrap();
+
+                tokio::task::spawn_blocking(move || prune(storage_client.as_ref(), pruning_strategy.as_ref()))
+                    .await
+                    .unwrap()
+            })
+        })
+        .collect();
+
+    let mut summaries = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        summaries.push(task.await.unwrap());
+    }
+
+    summaries
+}
+
+/// Number of batches of expendable backups `prune_streaming` lets wait for deletion,
+/// before it pauses listing.
+const PENDING_BATCHES: usize = 4;
+
+/// Performs `prune`, but already deletes the expendable backups of each batch of
+/// `batch_size` listed ones, while the next ones are being listed. Saves time and
+/// memory on huge buckets. Only strategies that are streaming safe can decide on a
+/// batch at a time, all others are given all stored backups at once as by `prune`.
+///
+/// If listing fails, the backups of the batches evaluated up to then are deleted
+/// anyway.
+pub async fn prune_streaming(
+    storage_client: Arc<dyn StorageClient + Sync>,
+    pruning_strategy: Arc<dyn PruningStrategy + Sync>,
+    batch_size: usize,
+) -> Result<PruneSummary, StorageError> {
+
+    // Panic, as no batch could ever be evaluated otherwise.
+    assert!(batch_size > 0);
+
+    if !pruning_strategy.is_streaming_safe() {
+        return tokio::task::spawn_blocking(move || prune(storage_client.as_ref(), pruning_strategy.as_ref()))
+            .await
+            .unwrap();
+    }
+
+    let (sender, mut receiver) = mpsc::channel::<Vec<BackupFileMeta>>(PENDING_BATCHES);
+
+    let deleting_client = storage_client.clone();
+    let deletion = tokio::spawn(async move {
+        let mut counts = DeletionCounts::default();
+        let mut duration = Duration::default();
+
+        while let Some(expendable_backups) = receiver.recv().await {
+            let storage_client = deleting_client.clone();
+            let started = Instant::now();
+            let batch_counts = tokio::task::spawn_blocking(move || storage_client.delete_backups_counting(expendable_backups))
+                .await
+                .unwrap();
+            duration += started.elapsed();
+
+            counts.deleted += batch_counts.deleted;
+            counts.failed += batch_counts.failed;
+            counts.protected += batch_counts.protected;
+            counts.remaining += batch_counts.remaining;
+            counts.bytes_freed += batch_counts.bytes_freed;
+        }
+
+        (counts, duration)
+    });
+
+    let listing = tokio::task::spawn_blocking(move || -> Result<(usize, usize, PhaseTimings), StorageError> {
+        let mut timings = PhaseTimings::default();
+        let mut total_found = 0;
+        let mut kept = 0;
+        let mut batch = Vec::with_capacity(batch_size);
+        let mut stored_backups = storage_client.stored_backups_iter();
+
+        let started = Instant::now();
+        loop {
+            let next = stored_backups.next().transpose()?;
+            let done = next.is_none();
+            batch.extend(next);
+
+            if batch.len() == batch_size || (done && !batch.is_empty()) {
+                total_found += batch.len();
+
+                let evaluation_started = Instant::now();
+                let expendable_backups = pruning_strategy.expendable_backups(&mut batch);
+                timings.evaluation += evaluation_started.elapsed();
+                kept += batch.len();
+                batch.clear();
+
+                // The receiver is only gone, if deleting panicked, which is reported below.
+                if !expendable_backups.is_empty() && sender.blocking_send(expendable_backups).is_err() {
+                    break;
+                }
+            }
+
+            if done {
+                break;
+            }
+        }
+        timings.listing = started.elapsed() - timings.evaluation;
+
+        Ok((total_found, kept, timings))
+    });
+
+    let listed = listing.await.unwrap();
+    let (deletion_counts, deletion_duration) = deletion.await.unwrap();
+    let (total_found, kept, mut timings) = listed?;
+    timings.deletion = deletion_duration;
+
+    Ok(PruneSummary {
+        total_found,
+        kept,
+        deleted: deletion_counts.deleted,
+        failed: deletion_counts.failed,
+        remaining: deletion_counts.remaining,
+        bytes_freed: deletion_counts.bytes_freed,
+        timings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BackupFileMeta;
+    use crate::storage_client::InMemory;
+    use crate::pruning_strategy::{KeepLastN, OlderThan};
+    use crate::pruning_strategy::tests::{build_meta, collect_ids, as_vector};
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use time::Duration;
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    fn backups() -> Vec<BackupFileMeta> {
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0)),
+        ];
+        for (size, backup) in backups.iter_mut().enumerate() {
+            backup.size = 1 << size;
+        }
+
+        backups
+    }
+
+    /// Wraps an `InMemory` client, keeping track of how many listings are in
+    /// progress at the same time.
+    struct CountingClient {
+        inner: InMemory,
+        in_flight: Arc<AtomicUsize>,
+        max_in_flight: Arc<AtomicUsize>,
+    }
+
+    impl StorageClient for CountingClient {
+
+        fn stored_backups(&self) -> Result<Vec<BackupFileMeta>, StorageError> {
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            thread::sleep(std::time::Duration::from_millis(20));
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            self.inner.stored_backups()
+        }
+
+        fn delete_backups(&self, backups: Vec<BackupFileMeta>) -> DeletionReport {
+            self.inner.delete_backups(backups)
+        }
+    }
+
+    /// Wraps an `InMemory` client, recording each backup listed and each batch
+    /// deleted. Listing waits for the first batch to be deleted after yielding
+    /// `pause_after` backups.
+    struct RecordingClient {
+        inner: InMemory,
+        pause_after: usize,
+        events: Mutex<Vec<String>>,
+    }
+
+    impl RecordingClient {
+
+        fn new(backups: Vec<BackupFileMeta>, pause_after: usize) -> RecordingClient {
+            RecordingClient {
+                inner: InMemory::new(backups),
+                pause_after,
+                events: Mutex::new(vec![]),
+            }
+        }
+
+        fn record(&self, event: String) {
+            self.events.lock().unwrap().push(event);
+        }
+
+        fn wait_for_deletion(&self) {
+            for _ in 0..100 {
+                if self.events.lock().unwrap().iter().any(|event| event.starts_with("delete")) {
+                    return;
+                }
+                thread::sleep(std::time::Duration::from_millis(10));
+            }
+        }
+    }
+
+    impl StorageClient for RecordingClient {
+
+        fn stored_backups(&self) -> Result<Vec<BackupFileMeta>, StorageError> {
+            self.inner.stored_backups()
+        }
+
+        fn stored_backups_iter(&self) -> Box<dyn Iterator<Item = Result<BackupFileMeta, StorageError>> + '_> {
+            let backups = self.inner.stored_backups().unwrap();
+
+            Box::new(backups.into_iter().enumerate().map(move |(index, backup)| {
+                if index == self.pause_after {
+                    self.wait_for_deletion();
+                }
+                self.record(format!("list {}", backup.id));
+
+                Ok(backup)
+            }))
+        }
+
+        fn delete_backups(&self, backups: Vec<BackupFileMeta>) -> DeletionReport {
+            self.record(format!("delete {}", collect_ids(backups.clone()).join(" ")));
+
+            self.inner.delete_backups(backups)
+        }
+    }
+
+    fn daily_backups(ids: &str) -> Vec<BackupFileMeta> {
+        ids.chars()
+            .enumerate()
+            .map(|(day, id)| build_meta(&id.to_string(), Utc.ymd(2014, 7, 1 + day as u32).and_hms(0, 0, 0)))
+            .collect()
+    }
+
+    #[test]
+    fn test_prune() {
+        let client = InMemory::new(backups());
+
+        let summary = prune(&client, &KeepLastN::new(1)).unwrap();
+
+        assert_eq!(summary, PruneSummary {
+            total_found: 3,
+            kept: 1,
+            deleted: 2,
+            failed: 0,
+            remaining: 0,
+            bytes_freed: 3,
+            timings: summary.timings,
+        });
+        assert_eq!(collect_ids(client.stored_backups().unwrap()), as_vector("C"));
+    }
+
+    #[test]
+    fn test_prune_when_some_deletions_fail() {
+        let client = InMemory::new(backups()).with_undeletable(vec![String::from("A")]);
+
+        let summary = prune(&client, &KeepLastN::new(1)).unwrap();
+
+        assert_eq!((summary.kept, summary.deleted, summary.failed, summary.bytes_freed), (1, 1, 1, 2));
+        assert_eq!(collect_ids(client.stored_backups().unwrap()), as_vector("AC"));
+    }
+
+    #[test]
+    fn test_prune_records_timings() {
+        let client = CountingClient {
+            inner: InMemory::new(backups()),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            max_in_flight: Arc::new(AtomicUsize::new(0)),
+        };
+
+        let summary = prune(&client, &KeepLastN::new(1)).unwrap();
+
+        // The client takes 20 milliseconds to list the backups.
+        assert!(summary.timings.listing >= std::time::Duration::from_millis(20));
+        assert!(summary.timings.evaluation < summary.timings.listing);
+        assert!(summary.timings.deletion < summary.timings.listing);
+    }
+
+    #[test]
+    fn test_prune_when_nothing_is_expendable() {
+        let client = InMemory::new(backups());
+
+        let summary = prune(&client, &KeepLastN::new(5)).unwrap();
+
+        assert_eq!(summary.deleted, 0);
+        assert_eq!(summary.kept, 3);
+        assert_eq!(summary.bytes_freed, 0);
+    }
+
+    #[test]
+    fn test_prune_selected() {
+        let client = InMemory::new(backups()).with_undeletable(vec![String::from("B")]);
+
+        let (summary, deletion_report) = prune_selected(&client, &KeepLastN::new(1), |kept_backups, expendable_backups| {
+            assert_eq!(collect_ids(kept_backups.to_vec()), as_vector("C"));
+            assert_eq!(collect_ids(expendable_backups.clone()), as_vector("AB"));
+
+            expendable_backups
+        }).unwrap();
+
+        assert_eq!(summary, PruneSummary {
+            total_found: 3,
+            kept: 1,
+            deleted: 1,
+            failed: 1,
+            remaining: 0,
+            bytes_freed: 1,
+            timings: summary.timings,
+        });
+        assert_eq!(collect_ids(deletion_report.deleted), as_vector("A"));
+        assert_eq!(collect_ids(client.stored_backups().unwrap()), as_vector("BC"));
+    }
+
+    #[test]
+    fn test_prune_selected_when_nothing_is_selected() {
+        let client = InMemory::new(backups());
+
+        let (summary, deletion_report) = prune_selected(&client, &KeepLastN::new(1), |_, _| vec![]).unwrap();
+
+        assert_eq!((summary.kept, summary.deleted, summary.failed), (1, 0, 0));
+        assert!(deletion_report.deleted.is_empty());
+        assert_eq!(collect_ids(client.stored_backups().unwrap()), as_vector("ABC"));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_prune_summary_to_json() {
+        let client = InMemory::new(backups());
+
+        let summary = prune(&client, &KeepLastN::new(1)).unwrap();
+        let json: serde_json::Value = serde_json::to_value(&summary).unwrap();
+
+        assert_eq!(json["total_found"], 3);
+        assert_eq!(json["kept"], 1);
+        assert_eq!(json["deleted"], 2);
+        assert_eq!(json["failed"], 0);
+        assert_eq!(json["bytes_freed"], 3);
+        assert!(json["timings"]["listing"].is_object());
+    }
+
+    #[test]
+    fn test_prune_when_listing_fails() {
+        struct FailingClient;
+
+        impl StorageClient for FailingClient {
+
+            fn stored_backups(&self) -> Result<Vec<BackupFileMeta>, StorageError> {
+                Err(StorageError::BucketNotFound(String::from("my-database-backups")))
+            }
+
+            fn delete_backups(&self, _backups: Vec<BackupFileMeta>) -> DeletionReport {
+                panic!("Nothing should be deleted.");
+            }
+        }
+
+        assert_eq!(
+            prune(&FailingClient, &KeepLastN::new(1)),
+            Err(StorageError::BucketNotFound(String::from("my-database-backups"))),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prune_streaming() {
+        let client = Arc::new(RecordingClient::new(daily_backups("ABCDE"), 2));
+        let strategy = Arc::new(OlderThan::new(Duration::zero(), Utc.ymd(2014, 7, 5).and_hms(0, 0, 0)));
+
+        let summary = prune_streaming(client.clone(), strategy, 2).await.unwrap();
+
+        assert_eq!((summary.total_found, summary.kept, summary.deleted, summary.failed), (5, 1, 4, 0));
+        assert_eq!(collect_ids(client.inner.stored_backups().unwrap()), as_vector("E"));
+
+        // The first batch is deleted before C is listed.
+        let events = client.events.lock().unwrap();
+        assert_eq!(events[..4], ["list A", "list B", "delete A B", "list C"]);
+        assert!(events.contains(&String::from("delete C D")));
+        assert_eq!(events.len(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_prune_streaming_when_the_strategy_is_not_streaming_safe() {
+        let client = Arc::new(RecordingClient::new(daily_backups("ABCDE"), 2));
+
+        let summary = prune_streaming(client.clone(), Arc::new(KeepLastN::new(1)), 2).await.unwrap();
+
+        assert_eq!((summary.total_found, summary.kept, summary.deleted), (5, 1, 4));
+        assert_eq!(*client.events.lock().unwrap(), vec!["delete A B C D"]);
+    }
+
+    #[tokio::test]
+    async fn test_prune_all() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let targets: Vec<(Box<dyn StorageClient>, Box<dyn PruningStrategy>)> = (0..6)
+            .map(|i| {
+                let client: Box<dyn StorageClient> = Box::new(CountingClient {
+                    inner: InMemory::new(backups()),
+                    in_flight: in_flight.clone(),
+                    max_in_flight: max_in_flight.clone(),
+                });
+                let strategy: Box<dyn PruningStrategy> = Box::new(
+                    OlderThan::new(Duration::zero(), Utc.ymd(2014, 7, 1 + i % 3).and_hms(0, 0, 0))
+                );
+
+                (client, strategy)
+            })
+            .collect();
+
+        let summaries: Vec<PruneSummary> = prune_all(targets, 2)
+            .await
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let deleted: Vec<usize> = summaries.iter().map(|summary| summary.deleted).collect();
+        assert_eq!(deleted, vec![0, 1, 2, 0, 1, 2]);
+        assert!(summaries.iter().all(|summary| summary.total_found == 3));
+        assert!(max_in_flight.load(Ordering::SeqCst) <= 2);
+    }
+}
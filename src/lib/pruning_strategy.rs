@@ -2,26 +2,199 @@
 mod older_than;
 mod keep_one_per_month;
 mod older_than_but_keep_history;
+mod quarantine;
+mod keep_last_n;
+mod always_keep_latest;
+mod skip_storage_class;
+mod keep_every_nth;
+mod deduplicate_identical;
+mod keep_business_days_only;
+mod drop_unsuccessful;
+mod routing_strategy;
+mod collapse_bursts;
+mod keep_at_least_n_per_period;
+mod composite_strategy;
+mod not;
+mod pin;
+mod keep_evenly_spaced;
+mod strategy_config_error;
+mod only_period;
+mod keep_on_specific_days;
+mod keep_newest_per_period;
+mod keep_one_per_weekday;
+mod custom_strategy;
+mod incremental;
+mod keep_latest_per_source;
+mod keep_one_per_quarter;
+mod respect_retain_until;
+mod keep_exponential;
+mod respect_tier_tag;
+mod max_delete_bytes;
+mod max_reduction;
+mod retain_incremental_chains;
+mod keep_last_n_versions_per_key;
+mod satisfy_rpo;
 
 use super::BackupFileMeta;
+use time::Duration;
+use chrono::{DateTime, Utc};
 pub use older_than::OlderThan;
-pub use keep_one_per_month::KeepOnePerMonth;
+pub use keep_one_per_month::{KeepOnePerMonth, TolerancePolicy};
 pub use older_than_but_keep_history::OlderThanButKeepOnePerMonth;
+pub use quarantine::Quarantine;
+pub use keep_last_n::KeepLastN;
+pub use always_keep_latest::AlwaysKeepLatest;
+pub use skip_storage_class::SkipStorageClass;
+pub use keep_every_nth::KeepEveryNth;
+pub use deduplicate_identical::DeduplicateIdentical;
+pub use keep_business_days_only::KeepBusinessDaysOnly;
+pub use drop_unsuccessful::DropUnsuccessful;
+pub use routing_strategy::{RoutingStrategy, Matcher};
+pub use collapse_bursts::CollapseBursts;
+pub use keep_at_least_n_per_period::KeepAtLeastNPerPeriod;
+pub use composite_strategy::{CompositeStrategy, Combinator};
+pub use not::Not;
+pub use pin::Pin;
+pub use keep_evenly_spaced::KeepEvenlySpaced;
+pub use strategy_config_error::StrategyConfigError;
+pub use only_period::OnlyPeriod;
+pub use keep_on_specific_days::KeepOnSpecificDays;
+pub use keep_newest_per_period::KeepNewestPerPeriod;
+pub use keep_one_per_weekday::KeepOnePerWeekday;
+pub use custom_strategy::{CustomStrategy, KeepPredicate};
+pub use incremental::{Incremental, Snapshot};
+pub use keep_latest_per_source::KeepLatestPerSource;
+pub use keep_one_per_quarter::KeepOnePerQuarter;
+pub use respect_retain_until::RespectRetainUntil;
+pub use keep_exponential::KeepExponential;
+pub use respect_tier_tag::{RespectTierTag, TierRetention};
+pub use max_delete_bytes::MaxDeleteBytes;
+pub use max_reduction::MaxReduction;
+pub use retain_incremental_chains::RetainIncrementalChains;
+pub use keep_last_n_versions_per_key::KeepLastNVersionsPerKey;
+pub use satisfy_rpo::SatisfyRpo;
 
 /// Each pruning strategy should implement this trait, so it can be used to perform
-/// the pruning.
-pub trait PruningStrategy {
+/// the pruning. Strategies need to be `Send`, so several prunings can be performed
+/// concurrently.
+pub trait PruningStrategy: Send {
 
     /// Removes all expendable backups from the given `backups`
     fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta>;
+
+    /// Returns the age beyond which all backups are expendable, if that is all the
+    /// strategy considers. Such strategies can be expressed as e.g. an S3 lifecycle rule.
+    fn max_age(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Returns when the next of the `backups` the strategy keeps will become expendable,
+    /// if no new backups arrive. Returns `None`, if none will or the strategy can't tell.
+    fn next_expiry(&self, _backups: &[BackupFileMeta]) -> Option<DateTime<Utc>> {
+        None
+    }
+
+    /// Returns `true`, if the strategy decides on each backup on its own, regardless of
+    /// the others. Such strategies may be given the stored backups in batches as they
+    /// are listed, see `prune_streaming`.
+    fn is_streaming_safe(&self) -> bool {
+        false
+    }
+
+    /// Explains in a sentence why the strategy keeps or deletes `backup`, one of the
+    /// stored `backups`, e.g. which age band it falls into. Returns `None`, if the
+    /// strategy can't tell.
+    fn explain(&self, _backup: &BackupFileMeta, _backups: &[BackupFileMeta]) -> Option<String> {
+        None
+    }
+}
+
+impl<S: PruningStrategy + ?Sized> PruningStrategy for Box<S> {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        (**self).expendable_backups(backups)
+    }
+
+    fn max_age(&self) -> Option<Duration> {
+        (**self).max_age()
+    }
+
+    fn next_expiry(&self, backups: &[BackupFileMeta]) -> Option<DateTime<Utc>> {
+        (**self).next_expiry(backups)
+    }
+
+    fn is_streaming_safe(&self) -> bool {
+        (**self).is_streaming_safe()
+    }
+
+    fn explain(&self, backup: &BackupFileMeta, backups: &[BackupFileMeta]) -> Option<String> {
+        (**self).explain(backup, backups)
+    }
 }
 
+/// Combinators for building policies out of several strategies, e.g.
+///
+/// ```
+/// use backups_cleaner::pruning_strategy::{OlderThan, KeepLastN, PruningStrategyExt};
+/// use chrono::Utc;
+/// use time::Duration;
+///
+/// // Deletes backups older than 30 days, unless they're among the 5 newest ones or
+/// // the one everything has been migrated from.
+/// let strategy = OlderThan::new(Duration::days(30), Utc::now())
+///     .and(KeepLastN::new(5))
+///     .pin(vec![String::from("backups/pre-migration.dump")]);
+/// ```
+///
+/// The combinators take ownership, so they don't work on strategies behind references:
+///
+/// ```compile_fail
+/// use backups_cleaner::pruning_strategy::{PruningStrategy, PruningStrategyExt};
+///
+/// fn negate(strategy: &dyn PruningStrategy) {
+///     let _ = strategy.not();
+/// }
+/// ```
+pub trait PruningStrategyExt: PruningStrategy {
+
+    /// Considers backups expendable, which both strategies consider expendable.
+    fn and<S: PruningStrategy + 'static>(self, other: S) -> Box<dyn PruningStrategy>
+        where Self: Sized + 'static
+    {
+        Box::new(CompositeStrategy::new(Combinator::And, vec![Box::new(self), Box::new(other)]))
+    }
+
+    /// Considers backups expendable, which either strategy considers expendable.
+    fn or<S: PruningStrategy + 'static>(self, other: S) -> Box<dyn PruningStrategy>
+        where Self: Sized + 'static
+    {
+        Box::new(CompositeStrategy::new(Combinator::Or, vec![Box::new(self), Box::new(other)]))
+    }
+
+    /// Considers exactly the backups expendable, which the strategy keeps.
+    fn not(self) -> Box<dyn PruningStrategy>
+        where Self: Sized + 'static
+    {
+        Box::new(Not::new(Box::new(self)))
+    }
+
+    /// Keeps the backups with the given `ids`, whatever the strategy decides.
+    fn pin(self, ids: Vec<String>) -> Box<dyn PruningStrategy>
+        where Self: Sized + 'static
+    {
+        Box::new(Pin::new(Box::new(self), ids))
+    }
+}
+
+impl<S: PruningStrategy + ?Sized> PruningStrategyExt for S {}
+
 /// A collection of helper methods that come in handy when writing tests
 /// for pruning strategies.
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use chrono::{DateTime, Utc};
+    use chrono::offset::TimeZone;
 
     pub fn collect_ids(backup_file_metas: Vec<BackupFileMeta>) -> Vec<String> {
         backup_file_metas
@@ -35,10 +208,59 @@ mod tests {
             id: String::from(id),
             human_readable_id: String::from(id),
             date,
+            size: 0,
+            storage_class: None,
+            etag: None,
+            successful: None,
+            retain_until: None,
+            tier: None,
+            version_id: None,
         }
     }
 
     pub fn as_vector(ids: &str) -> Vec<String> {
-        ids.chars().map(|character| String::from(character.to_string())).collect()
+        ids.chars().map(|character| character.to_string()).collect()
+    }
+
+    #[test]
+    fn test_combinators() {
+        let reference_time = Utc.ymd(2014, 7, 10).and_hms(0, 0, 0);
+        let strategy = OlderThan::new(Duration::days(5), reference_time)
+            .and(KeepLastN::new(3))
+            .or(DropUnsuccessful::new(Box::new(KeepLastN::new(100))))
+            .pin(vec![String::from("B")]);
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0)),
+            build_meta("D", Utc.ymd(2014, 7, 4).and_hms(0, 0, 0)),
+            BackupFileMeta {
+                successful: Some(false),
+                ..build_meta("E", Utc.ymd(2014, 7, 9).and_hms(0, 0, 0))
+            },
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // A and B are old and not among the newest three, E is unsuccessful, but B is
+        // pinned.
+        let mut expendable_ids = collect_ids(expendable_backups);
+        expendable_ids.sort();
+        assert_eq!(expendable_ids, as_vector("AE"));
+        assert_eq!(backups.len(), 3);
+    }
+
+    #[test]
+    fn test_not() {
+        let strategy = KeepLastN::new(1).not().not();
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("A"));
+        assert_eq!(collect_ids(backups), as_vector("B"));
     }
 }
@@ -0,0 +1,115 @@
+use super::{PruningStrategy, BackupFileMeta};
+use super::composite_strategy::expendable_ids;
+use std::collections::HashSet;
+
+/// Wraps another strategy and always keeps the `n` most recent backups, even if the
+/// `inner` strategy considers them expendable.
+pub struct AlwaysKeepLatest {
+    inner: Box<dyn PruningStrategy>,
+    n: usize,
+}
+
+impl AlwaysKeepLatest {
+
+    pub fn new(inner: Box<dyn PruningStrategy>, n: usize) -> AlwaysKeepLatest {
+        AlwaysKeepLatest {
+            inner,
+            n,
+        }
+    }
+
+    fn latest_ids(&self, mut all_backups: Vec<&BackupFileMeta>) -> HashSet<String> {
+        all_backups.sort_by_key(|backup| backup.date);
+        all_backups
+            .iter()
+            .rev()
+            .take(self.n)
+            .map(|backup| backup.id.clone())
+            .collect()
+    }
+}
+
+impl PruningStrategy for AlwaysKeepLatest {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        let inner_expendable_backups = self.inner.expendable_backups(backups);
+        let latest_ids = self.latest_ids(backups.iter().chain(inner_expendable_backups.iter()).collect());
+
+        let mut expendable_backups = vec![];
+        for backup in inner_expendable_backups {
+            if latest_ids.contains(&backup.id) {
+                backups.push(backup);
+            }
+            else {
+                expendable_backups.push(backup);
+            }
+        }
+
+        expendable_backups
+    }
+
+    fn explain(&self, backup: &BackupFileMeta, backups: &[BackupFileMeta]) -> Option<String> {
+        // The latest backups are only kept because of this strategy, if the inner one
+        // considers them expendable.
+        if self.latest_ids(backups.iter().collect()).contains(&backup.id) && expendable_ids(self.inner.as_ref(), backups).contains(&backup.id) {
+            Some(format!("It's one of the {} latest backups (always_keep_latest), so it's kept.", self.n))
+        }
+        else {
+            self.inner.explain(backup, backups)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{OlderThan, KeepLastN};
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use time::Duration;
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    #[test]
+    fn test_expendable_backups() {
+        let strategy = AlwaysKeepLatest::new(
+            Box::new(OlderThan::new(Duration::zero(), Utc.ymd(2015, 1, 1).and_hms(0, 0, 0))),
+            5,
+        );
+        let mut backups: Vec<BackupFileMeta> = "ABCDEFGH"
+            .chars()
+            .enumerate()
+            .map(|(i, id)| build_meta(&id.to_string(), Utc.ymd(2014, 7, 1 + i as u32).and_hms(0, 0, 0)))
+            .collect();
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("ABC"));
+        assert_eq!(collect_ids(backups), as_vector("DEFGH"));
+    }
+
+    #[test]
+    fn test_expendable_backups_when_the_inner_strategy_already_keeps_the_latest() {
+        let strategy = AlwaysKeepLatest::new(Box::new(KeepLastN::new(1)), 2);
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("A"));
+        assert_eq!(collect_ids(backups), as_vector("CB"));
+    }
+
+    #[test]
+    fn test_expendable_backups_with_no_backups_given() {
+        let strategy = AlwaysKeepLatest::new(Box::new(KeepLastN::new(0)), 5);
+        let mut backups = vec![];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert!(expendable_backups.is_empty());
+        assert!(backups.is_empty());
+    }
+}
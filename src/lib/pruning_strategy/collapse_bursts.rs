@@ -0,0 +1,111 @@
+use super::{PruningStrategy, BackupFileMeta};
+use time::Duration;
+
+/// Collapses bursts of backups made in quick succession (e.g. by retries) into their
+/// first backup. Orders backups chronologically and considers each backup expendable,
+/// that has been made within `window` after the last kept one.
+pub struct CollapseBursts {
+    window: Duration,
+}
+
+impl CollapseBursts {
+
+    pub fn new(window: Duration) -> CollapseBursts {
+        CollapseBursts {
+            window,
+        }
+    }
+}
+
+impl PruningStrategy for CollapseBursts {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        backups.sort_by_key(|backup| backup.date);
+
+        let mut backups_to_keep: Vec<BackupFileMeta> = vec![];
+        let mut expendable_backups = vec![];
+
+        for backup in backups.drain(..) {
+            let within_burst = match backups_to_keep.last() {
+                Some(last_kept_backup) => backup.date.signed_duration_since(last_kept_backup.date) <= self.window,
+                None => false,
+            };
+
+            if within_burst {
+                expendable_backups.push(backup);
+            }
+            else {
+                backups_to_keep.push(backup);
+            }
+        }
+
+        *backups = backups_to_keep;
+
+        expendable_backups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    fn build_meta_at(id: &str, hour: u32, second: u32) -> BackupFileMeta {
+        build_meta(id, Utc.ymd(2014, 7, 1).and_hms(hour, 0, second))
+    }
+
+    #[test]
+    fn test_expendable_backups() {
+        let strategy = CollapseBursts::new(Duration::seconds(10));
+        let mut backups = vec![
+            // A burst of one.
+            build_meta_at("A", 1, 0),
+
+            // A burst of three.
+            build_meta_at("D", 2, 10),
+            build_meta_at("B", 2, 0),
+            build_meta_at("C", 2, 5),
+
+            // A burst of five.
+            build_meta_at("E", 3, 0),
+            build_meta_at("F", 3, 1),
+            build_meta_at("G", 3, 2),
+            build_meta_at("H", 3, 3),
+            build_meta_at("I", 3, 4),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("CDFGHI"));
+        assert_eq!(collect_ids(backups), as_vector("ABE"));
+    }
+
+    #[test]
+    fn test_expendable_backups_measures_from_the_last_kept_backup() {
+        let strategy = CollapseBursts::new(Duration::seconds(10));
+        let mut backups = vec![
+            build_meta_at("A", 1, 0),
+            build_meta_at("B", 1, 8),
+            build_meta_at("C", 1, 16), // Within 10 seconds of B, but not of A.
+            build_meta_at("D", 1, 24),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("BD"));
+        assert_eq!(collect_ids(backups), as_vector("AC"));
+    }
+
+    #[test]
+    fn test_expendable_backups_with_no_backups_given() {
+        let strategy = CollapseBursts::new(Duration::seconds(10));
+        let mut backups = vec![];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert!(expendable_backups.is_empty());
+        assert!(backups.is_empty());
+    }
+}
@@ -0,0 +1,172 @@
+use super::{PruningStrategy, BackupFileMeta, StrategyConfigError};
+use std::collections::HashSet;
+
+/// How a `CompositeStrategy` combines the decisions of its strategies.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Combinator {
+
+    /// Backups are expendable, if all strategies consider them expendable.
+    And,
+
+    /// Backups are expendable, if any strategy considers them expendable.
+    Or,
+}
+
+/// Combines the decisions of several strategies, each of which considers all of the
+/// backups independently of the others.
+pub struct CompositeStrategy {
+    combinator: Combinator,
+    strategies: Vec<Box<dyn PruningStrategy>>,
+}
+
+impl CompositeStrategy {
+
+    /// Panics, if no `strategies` are given, see `try_new`.
+    pub fn new(combinator: Combinator, strategies: Vec<Box<dyn PruningStrategy>>) -> CompositeStrategy {
+        CompositeStrategy::try_new(combinator, strategies).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Fails, if no `strategies` are given, as it's unclear what combining nothing
+    /// should mean.
+    pub fn try_new(combinator: Combinator, strategies: Vec<Box<dyn PruningStrategy>>) -> Result<CompositeStrategy, StrategyConfigError> {
+        if strategies.is_empty() {
+            return Err(StrategyConfigError::NoStrategies);
+        }
+
+        Ok(CompositeStrategy {
+            combinator,
+            strategies,
+        })
+    }
+}
+
+/// Returns the ids of the `backups` the `strategy` considers expendable, leaving the
+/// `backups` themselves untouched.
+pub(super) fn expendable_ids(strategy: &dyn PruningStrategy, backups: &[BackupFileMeta]) -> HashSet<String> {
+    strategy.expendable_backups(&mut backups.to_vec())
+        .into_iter()
+        .map(|backup| backup.id)
+        .collect()
+}
+
+impl PruningStrategy for CompositeStrategy {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        let decisions: Vec<HashSet<String>> = self.strategies
+            .iter()
+            .map(|strategy| expendable_ids(strategy.as_ref(), backups))
+            .collect();
+
+        let (expendable_backups, backups_to_keep) = backups.drain(..).partition(|backup: &BackupFileMeta| {
+            match self.combinator {
+                Combinator::And => decisions.iter().all(|ids| ids.contains(&backup.id)),
+                Combinator::Or => decisions.iter().any(|ids| ids.contains(&backup.id)),
+            }
+        });
+
+        *backups = backups_to_keep;
+
+        expendable_backups
+    }
+
+    /// Explains by the first strategy that decides like the combination, e.g. by the
+    /// first one keeping the backup, if they're combined by `And`.
+    fn explain(&self, backup: &BackupFileMeta, backups: &[BackupFileMeta]) -> Option<String> {
+        let considers_expendable = |strategy: &dyn PruningStrategy| expendable_ids(strategy, backups).contains(&backup.id);
+        let deciding_strategy = match self.combinator {
+            Combinator::And => self.strategies.iter().find(|strategy| !considers_expendable(strategy.as_ref())),
+            Combinator::Or => self.strategies.iter().find(|strategy| considers_expendable(strategy.as_ref())),
+        };
+
+        deciding_strategy.or_else(|| self.strategies.first())?.explain(backup, backups)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{KeepLastN, OlderThan};
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use time::Duration;
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    fn backups() -> Vec<BackupFileMeta> {
+        vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0)),
+            build_meta("D", Utc.ymd(2014, 7, 4).and_hms(0, 0, 0)),
+        ]
+    }
+
+    fn strategies() -> Vec<Box<dyn PruningStrategy>> {
+        vec![
+            // Considers A and B expendable.
+            Box::new(OlderThan::new(Duration::days(1), Utc.ymd(2014, 7, 4).and_hms(0, 0, 0))),
+
+            // Considers A, B and C expendable.
+            Box::new(KeepLastN::new(1)),
+        ]
+    }
+
+    #[test]
+    fn test_expendable_backups_with_and() {
+        let strategy = CompositeStrategy::new(Combinator::And, strategies());
+        let mut backups = backups();
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("AB"));
+        assert_eq!(collect_ids(backups), as_vector("CD"));
+    }
+
+    #[test]
+    fn test_expendable_backups_with_or() {
+        let strategy = CompositeStrategy::new(Combinator::Or, strategies());
+        let mut backups = backups();
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("ABC"));
+        assert_eq!(collect_ids(backups), as_vector("D"));
+    }
+
+    #[test]
+    fn test_explain() {
+        use super::super::KeepNewestPerPeriod;
+        use crate::reporting::Period;
+
+        let strategy = CompositeStrategy::new(Combinator::And, vec![
+            Box::new(KeepNewestPerPeriod::new(Period::Day, 1)),
+            Box::new(KeepNewestPerPeriod::new(Period::Month, 2)),
+        ]);
+        let mut backups = backups();
+        backups.insert(0, build_meta("Z", Utc.ymd(2014, 6, 30).and_hms(0, 0, 0)));
+
+        // Z is only kept as the newest of June, D as the newest of its day.
+        assert_eq!(
+            strategy.explain(&backups[0], &backups),
+            Some(String::from("It's the newest backup of its month, one of the 2 latest months with backups, so it's kept.")),
+        );
+        assert_eq!(
+            strategy.explain(&backups[4], &backups),
+            Some(String::from("It's the newest backup of its day, one of the 1 latest days with backups, so it's kept.")),
+        );
+        assert_eq!(
+            strategy.explain(&backups[1], &backups),
+            Some(String::from("It's not the newest backup of one of the 1 latest days with backups, so it's expendable.")),
+        );
+    }
+
+    #[test]
+    fn test_try_new_when_no_strategies_are_given() {
+        assert_eq!(CompositeStrategy::try_new(Combinator::Or, vec![]).err(), Some(StrategyConfigError::NoStrategies));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_new_when_no_strategies_are_given() {
+        CompositeStrategy::new(Combinator::And, vec![]);
+    }
+}
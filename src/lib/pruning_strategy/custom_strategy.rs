@@ -0,0 +1,80 @@
+use super::{PruningStrategy, BackupFileMeta};
+
+/// Decides whether to keep a backup, given the backup and all of the backups.
+pub type KeepPredicate = Box<dyn Fn(&BackupFileMeta, &[BackupFileMeta]) -> bool + Send>;
+
+/// Keeps the backups `keep` returns `true` for, and considers all others expendable.
+/// `keep` is given each backup along with all of the backups, sorted by date. Being a
+/// closure, it may capture any context it needs, such as a reference time.
+pub struct CustomStrategy {
+    keep: KeepPredicate,
+}
+
+impl CustomStrategy {
+
+    pub fn new(keep: KeepPredicate) -> CustomStrategy {
+        CustomStrategy {
+            keep,
+        }
+    }
+}
+
+impl PruningStrategy for CustomStrategy {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        backups.sort_by_key(|backup| backup.date);
+
+        let keep: Vec<bool> = backups.iter().map(|backup| (self.keep)(backup, backups)).collect();
+        let mut keep = keep.into_iter();
+        let (backups_to_keep, expendable_backups) = backups.drain(..).partition(|_| keep.next().unwrap());
+        *backups = backups_to_keep;
+
+        expendable_backups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use time::Duration;
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    #[test]
+    fn test_expendable_backups() {
+        let strategy = CustomStrategy::new(Box::new(|backup, backups| {
+            backups.iter().position(|other| other.id == backup.id).unwrap() % 2 == 0
+        }));
+        let mut backups = vec![
+            build_meta("C", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0)),
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("D", Utc.ymd(2014, 7, 4).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+            build_meta("E", Utc.ymd(2014, 7, 5).and_hms(0, 0, 0)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(backups), as_vector("ACE"));
+        assert_eq!(collect_ids(expendable_backups), as_vector("BD"));
+    }
+
+    #[test]
+    fn test_expendable_backups_with_captured_context() {
+        let reference_time = Utc.ymd(2014, 7, 5).and_hms(0, 0, 0);
+        let strategy = CustomStrategy::new(Box::new(move |backup, _| {
+            backup.id.contains("prod") || !backup.is_older_than(Duration::days(2), reference_time)
+        }));
+        let mut backups = vec![
+            build_meta("prod-1", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("test-1", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("test-2", Utc.ymd(2014, 7, 4).and_hms(0, 0, 0)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(backups), vec!["prod-1", "test-2"]);
+        assert_eq!(collect_ids(expendable_backups), vec!["test-1"]);
+    }
+}
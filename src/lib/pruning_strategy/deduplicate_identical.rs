@@ -0,0 +1,96 @@
+use super::{PruningStrategy, BackupFileMeta};
+use std::collections::HashSet;
+
+/// Considers backups expendable, whose `etag` equals the one of an older backup, i.e.
+/// only the first backup of each content is kept. Backups without an ETag are kept.
+///
+/// _NOTE_ AWS S3 ETags of objects uploaded in multiple parts depend on the part size,
+/// so identical content may have differing ETags, if it was uploaded differently.
+pub struct DeduplicateIdentical;
+
+impl DeduplicateIdentical {
+
+    pub fn new() -> DeduplicateIdentical {
+        DeduplicateIdentical
+    }
+}
+
+impl Default for DeduplicateIdentical {
+
+    fn default() -> DeduplicateIdentical {
+        DeduplicateIdentical::new()
+    }
+}
+
+impl PruningStrategy for DeduplicateIdentical {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        backups.sort_by_key(|backup| backup.date);
+
+        let mut known_etags = HashSet::new();
+        let mut backups_to_keep = vec![];
+        let mut expendable_backups = vec![];
+
+        for backup in backups.drain(..) {
+            let duplicate = match &backup.etag {
+                Some(etag) => !known_etags.insert(etag.clone()),
+                None => false,
+            };
+
+            if duplicate {
+                expendable_backups.push(backup);
+            }
+            else {
+                backups_to_keep.push(backup);
+            }
+        }
+
+        *backups = backups_to_keep;
+
+        expendable_backups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    fn build_meta_with_etag(id: &str, day: u32, etag: Option<&str>) -> BackupFileMeta {
+        BackupFileMeta {
+            etag: etag.map(String::from),
+            ..build_meta(id, Utc.ymd(2014, 7, day).and_hms(0, 0, 0))
+        }
+    }
+
+    #[test]
+    fn test_expendable_backups() {
+        let strategy = DeduplicateIdentical::new();
+        let mut backups = vec![
+            build_meta_with_etag("C", 3, Some("a")),
+            build_meta_with_etag("A", 1, Some("a")), // The first backup of content "a".
+            build_meta_with_etag("B", 2, Some("b")),
+            build_meta_with_etag("D", 4, Some("a")),
+            build_meta_with_etag("E", 5, None),
+            build_meta_with_etag("F", 6, None),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("CD"));
+        assert_eq!(collect_ids(backups), as_vector("ABEF"));
+    }
+
+    #[test]
+    fn test_expendable_backups_with_no_backups_given() {
+        let strategy = DeduplicateIdentical::new();
+        let mut backups = vec![];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert!(expendable_backups.is_empty());
+        assert!(backups.is_empty());
+    }
+}
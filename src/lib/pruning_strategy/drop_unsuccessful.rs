@@ -0,0 +1,92 @@
+use super::{PruningStrategy, BackupFileMeta};
+
+/// Wraps another strategy and considers all backups expendable, whose run is known to
+/// have failed (see `BackupFileMeta::successful`), regardless of their age. Decides on
+/// the remaining ones using the inner strategy.
+pub struct DropUnsuccessful {
+    inner: Box<dyn PruningStrategy>,
+}
+
+impl DropUnsuccessful {
+
+    pub fn new(inner: Box<dyn PruningStrategy>) -> DropUnsuccessful {
+        DropUnsuccessful {
+            inner,
+        }
+    }
+}
+
+impl PruningStrategy for DropUnsuccessful {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        let (mut unsuccessful_backups, mut considered_backups): (Vec<BackupFileMeta>, Vec<BackupFileMeta>) = backups
+            .drain(..)
+            .partition(|backup| backup.successful == Some(false));
+
+        let mut expendable_backups = self.inner.expendable_backups(&mut considered_backups);
+        expendable_backups.append(&mut unsuccessful_backups);
+
+        *backups = considered_backups;
+
+        expendable_backups
+    }
+
+    fn explain(&self, backup: &BackupFileMeta, backups: &[BackupFileMeta]) -> Option<String> {
+        if backup.successful == Some(false) {
+            Some(String::from("Its run failed, so it's expendable."))
+        }
+        else {
+            self.inner.explain(backup, backups)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::KeepLastN;
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    fn build_meta_with_success(id: &str, day: u32, successful: Option<bool>) -> BackupFileMeta {
+        BackupFileMeta {
+            successful,
+            ..build_meta(id, Utc.ymd(2014, 7, day).and_hms(0, 0, 0))
+        }
+    }
+
+    #[test]
+    fn test_expendable_backups() {
+        let strategy = DropUnsuccessful::new(Box::new(KeepLastN::new(2)));
+        let mut backups = vec![
+            build_meta_with_success("A", 1, Some(true)),
+            build_meta_with_success("B", 2, Some(true)),
+            build_meta_with_success("C", 3, Some(false)),
+            build_meta_with_success("D", 4, None),
+            build_meta_with_success("E", 5, Some(false)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // The newest backups C and E are expendable anyways, as their runs failed.
+        let mut expendable_ids = collect_ids(expendable_backups);
+        expendable_ids.sort();
+        assert_eq!(expendable_ids, as_vector("ACE"));
+
+        let mut ids = collect_ids(backups);
+        ids.sort();
+        assert_eq!(ids, as_vector("BD"));
+    }
+
+    #[test]
+    fn test_expendable_backups_with_no_backups_given() {
+        let strategy = DropUnsuccessful::new(Box::new(KeepLastN::new(2)));
+        let mut backups = vec![];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert!(expendable_backups.is_empty());
+        assert!(backups.is_empty());
+    }
+}
@@ -0,0 +1,160 @@
+use super::{PruningStrategy, BackupFileMeta};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// The backups a previous run kept, along with their dates, to be persisted between
+/// runs for `Incremental`.
+#[derive(Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Snapshot {
+    pub kept: HashMap<String, DateTime<Utc>>,
+}
+
+impl Snapshot {
+
+    /// Takes a snapshot of the `backups` a run kept.
+    pub fn of(backups: &[BackupFileMeta]) -> Snapshot {
+        Snapshot {
+            kept: backups.iter().map(|backup| (backup.id.clone(), backup.date)).collect(),
+        }
+    }
+}
+
+/// Wraps a strategy purely based on age (i.e. one with a `max_age`, such as `OlderThan`)
+/// and only lets it evaluate the backups that changed since the `snapshot` of a previous
+/// run was taken: New backups, and backups that have become older than `max_age` from
+/// `reference_time` since. All other backups of the snapshot are kept right away.
+///
+/// Strategies considering more than age (e.g. `KeepOnePerMonth`) need to see all
+/// backups, so they always evaluate all of them.
+pub struct Incremental {
+    inner: Box<dyn PruningStrategy>,
+    snapshot: Snapshot,
+    reference_time: DateTime<Utc>,
+}
+
+impl Incremental {
+
+    pub fn new(inner: Box<dyn PruningStrategy>, snapshot: Snapshot, reference_time: DateTime<Utc>) -> Incremental {
+        Incremental {
+            inner,
+            snapshot,
+            reference_time,
+        }
+    }
+}
+
+impl PruningStrategy for Incremental {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        let max_age = match self.inner.max_age() {
+            Some(max_age) => max_age,
+            None => return self.inner.expendable_backups(backups),
+        };
+
+        let (mut known_to_be_kept, mut to_evaluate): (Vec<BackupFileMeta>, Vec<BackupFileMeta>) = backups
+            .drain(..)
+            .partition(|backup| {
+                self.snapshot.kept.get(&backup.id) == Some(&backup.date) && !backup.is_older_than(max_age, self.reference_time)
+            });
+
+        let expendable_backups = self.inner.expendable_backups(&mut to_evaluate);
+        backups.append(&mut known_to_be_kept);
+        backups.append(&mut to_evaluate);
+        backups.sort_by_key(|backup| backup.date);
+
+        expendable_backups
+    }
+
+    fn max_age(&self) -> Option<time::Duration> {
+        self.inner.max_age()
+    }
+
+    fn next_expiry(&self, backups: &[BackupFileMeta]) -> Option<DateTime<Utc>> {
+        self.inner.next_expiry(backups)
+    }
+
+    fn explain(&self, backup: &BackupFileMeta, backups: &[BackupFileMeta]) -> Option<String> {
+        self.inner.explain(backup, backups)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{OlderThan, KeepLastN};
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use time::Duration;
+    use chrono::offset::TimeZone;
+
+    fn backups_until(day: u32) -> Vec<BackupFileMeta> {
+        (1..=day)
+            .map(|day| build_meta(&format!("{:02}", day), Utc.ymd(2014, 7, day).and_hms(0, 0, 0)))
+            .collect()
+    }
+
+    #[test]
+    fn test_expendable_backups_equal_a_full_evaluation() {
+        let first_run = Utc.ymd(2014, 7, 10).and_hms(12, 0, 0);
+        let mut backups = backups_until(10);
+        OlderThan::new(Duration::days(5), first_run).expendable_backups(&mut backups);
+        let snapshot = Snapshot::of(&backups);
+
+        // Some days later, new backups have arrived and further ones have become too old.
+        let second_run = Utc.ymd(2014, 7, 14).and_hms(12, 0, 0);
+        let mut incrementally_evaluated = backups.clone();
+        incrementally_evaluated.extend(backups_until(14).into_iter().skip(10));
+        let mut fully_evaluated = incrementally_evaluated.clone();
+
+        let incremental = Incremental::new(Box::new(OlderThan::new(Duration::days(5), second_run)), snapshot, second_run);
+        let incrementally_expendable = incremental.expendable_backups(&mut incrementally_evaluated);
+        let fully_expendable = OlderThan::new(Duration::days(5), second_run).expendable_backups(&mut fully_evaluated);
+
+        assert_eq!(collect_ids(incrementally_expendable), vec!["06", "07", "08", "09"]);
+        assert_eq!(collect_ids(fully_expendable), vec!["06", "07", "08", "09"]);
+        assert_eq!(collect_ids(incrementally_evaluated), collect_ids(fully_evaluated));
+    }
+
+    #[test]
+    fn test_expendable_backups_only_evaluates_changed_backups() {
+        let reference_time = Utc.ymd(2014, 7, 3).and_hms(0, 0, 0);
+        let snapshot = Snapshot::of(&[build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0))]);
+
+        // Deletes whatever it is given, but claims to be based on age.
+        struct DeleteAll;
+
+        impl PruningStrategy for DeleteAll {
+
+            fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+                std::mem::take(backups)
+            }
+
+            fn max_age(&self) -> Option<Duration> {
+                Some(Duration::days(7))
+            }
+        }
+
+        let strategy = Incremental::new(Box::new(DeleteAll), snapshot, reference_time);
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(backups), as_vector("A"));
+        assert_eq!(collect_ids(expendable_backups), as_vector("B"));
+    }
+
+    #[test]
+    fn test_expendable_backups_when_the_strategy_is_not_based_on_age() {
+        let snapshot = Snapshot::of(&backups_until(3));
+        let strategy = Incremental::new(Box::new(KeepLastN::new(1)), snapshot, Utc.ymd(2014, 7, 3).and_hms(0, 0, 0));
+        let mut backups = backups_until(3);
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(backups), vec!["03"]);
+        assert_eq!(collect_ids(expendable_backups), vec!["01", "02"]);
+    }
+}
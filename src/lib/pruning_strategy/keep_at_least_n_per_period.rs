@@ -0,0 +1,138 @@
+use super::{PruningStrategy, BackupFileMeta};
+use crate::reporting::Period;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// Wraps another strategy and makes sure, that at least `n` backups of each `period`
+/// are kept, if that many exist. Periods the inner strategy kept fewer backups of get
+/// the newest of their expendable ones back.
+pub struct KeepAtLeastNPerPeriod {
+    inner: Box<dyn PruningStrategy>,
+    period: Period,
+    n: usize,
+}
+
+impl KeepAtLeastNPerPeriod {
+
+    pub fn new(inner: Box<dyn PruningStrategy>, period: Period, n: usize) -> KeepAtLeastNPerPeriod {
+        KeepAtLeastNPerPeriod {
+            inner,
+            period,
+            n,
+        }
+    }
+}
+
+impl PruningStrategy for KeepAtLeastNPerPeriod {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        let mut candidates = self.inner.expendable_backups(backups);
+
+        let mut kept_per_period = HashMap::new();
+        for backup in backups.iter() {
+            *kept_per_period.entry(self.period.beginning(backup.date)).or_insert(0) += 1;
+        }
+
+        candidates.sort_by_key(|backup| Reverse(backup.date));
+        let mut expendable_backups = vec![];
+
+        for backup in candidates {
+            let kept = kept_per_period.entry(self.period.beginning(backup.date)).or_insert(0);
+
+            if *kept < self.n {
+                *kept += 1;
+                backups.push(backup);
+            }
+            else {
+                expendable_backups.push(backup);
+            }
+        }
+
+        expendable_backups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{KeepLastN, OlderThan};
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use time::Duration;
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    fn sorted(mut ids: Vec<String>) -> Vec<String> {
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn test_expendable_backups() {
+
+        // Keeps only the newest backup, i.e. one of August.
+        let inner = Box::new(KeepLastN::new(1));
+        let strategy = KeepAtLeastNPerPeriod::new(inner, Period::Month, 2);
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 10).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 20).and_hms(0, 0, 0)),
+            build_meta("D", Utc.ymd(2014, 8, 1).and_hms(0, 0, 0)),
+            build_meta("E", Utc.ymd(2014, 8, 10).and_hms(0, 0, 0)),
+            build_meta("F", Utc.ymd(2014, 9, 1).and_hms(0, 0, 0)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // The two newest backups of July get restored, as well as D for August.
+        assert_eq!(collect_ids(expendable_backups), as_vector("A"));
+        assert_eq!(sorted(collect_ids(backups)), as_vector("BCDEF"));
+    }
+
+    #[test]
+    fn test_expendable_backups_restores_a_second_backup_of_a_thinned_month() {
+        let reference_time = Utc.ymd(2014, 8, 1).and_hms(0, 0, 0);
+        let inner = Box::new(OlderThan::new(Duration::days(5), reference_time));
+        let strategy = KeepAtLeastNPerPeriod::new(inner, Period::Month, 2);
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0)),
+            build_meta("D", Utc.ymd(2014, 7, 30).and_hms(0, 0, 0)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // The inner strategy leaves only D of July, so the newest expendable July backup
+        // is kept as well.
+        assert_eq!(sorted(collect_ids(expendable_backups)), as_vector("AB"));
+        assert_eq!(sorted(collect_ids(backups)), as_vector("CD"));
+    }
+
+    #[test]
+    fn test_expendable_backups_when_the_inner_strategy_keeps_enough() {
+        let inner = Box::new(KeepLastN::new(3));
+        let strategy = KeepAtLeastNPerPeriod::new(inner, Period::Day, 1);
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 1).and_hms(1, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 1).and_hms(2, 0, 0)),
+            build_meta("D", Utc.ymd(2014, 7, 1).and_hms(3, 0, 0)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("A"));
+        assert_eq!(backups.len(), 3);
+    }
+
+    #[test]
+    fn test_expendable_backups_with_no_backups_given() {
+        let strategy = KeepAtLeastNPerPeriod::new(Box::new(KeepLastN::new(1)), Period::Week, 2);
+        let mut backups = vec![];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert!(expendable_backups.is_empty());
+        assert!(backups.is_empty());
+    }
+}
@@ -0,0 +1,151 @@
+use super::{PruningStrategy, BackupFileMeta};
+use time::Duration;
+use chrono::{DateTime, Datelike, FixedOffset, Utc, Weekday};
+
+/// Wraps another strategy and additionally considers backups expendable, that the
+/// inner strategy keeps, but that have been made on a weekend day and are older than
+/// `keep_all_within` from `reference_time`. Recent weekend backups are left to the
+/// inner strategy.
+///
+/// Weekend days default to Saturday and Sunday, and are determined in UTC unless
+/// another `timezone` is given.
+pub struct KeepBusinessDaysOnly {
+    inner: Box<dyn PruningStrategy>,
+    reference_time: DateTime<Utc>,
+    keep_all_within: Duration,
+    weekend_days: Vec<Weekday>,
+    timezone: FixedOffset,
+}
+
+impl KeepBusinessDaysOnly {
+
+    pub fn new(inner: Box<dyn PruningStrategy>, reference_time: DateTime<Utc>, keep_all_within: Duration) -> KeepBusinessDaysOnly {
+        KeepBusinessDaysOnly {
+            inner,
+            reference_time,
+            keep_all_within,
+            weekend_days: vec![Weekday::Sat, Weekday::Sun],
+            timezone: FixedOffset::east(0),
+        }
+    }
+
+    /// Treats the given `weekend_days` as weekend, instead of Saturday and Sunday.
+    pub fn with_weekend_days(mut self, weekend_days: Vec<Weekday>) -> KeepBusinessDaysOnly {
+        self.weekend_days = weekend_days;
+        self
+    }
+
+    /// Determines the day of week of a backup in the given `timezone`, instead of UTC.
+    pub fn with_timezone(mut self, timezone: FixedOffset) -> KeepBusinessDaysOnly {
+        self.timezone = timezone;
+        self
+    }
+
+    fn old_weekend_backup(&self, backup: &BackupFileMeta) -> bool {
+        let weekday = backup.date.with_timezone(&self.timezone).weekday();
+
+        backup.is_older_than(self.keep_all_within, self.reference_time)
+            && self.weekend_days.contains(&weekday)
+    }
+}
+
+impl PruningStrategy for KeepBusinessDaysOnly {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        let mut expendable_backups = self.inner.expendable_backups(backups);
+
+        let (mut weekend_backups, backups_to_keep): (Vec<BackupFileMeta>, Vec<BackupFileMeta>) = backups
+            .drain(..)
+            .partition(|backup| self.old_weekend_backup(backup));
+
+        *backups = backups_to_keep;
+        expendable_backups.append(&mut weekend_backups);
+
+        expendable_backups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{KeepLastN, OlderThan};
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use chrono::offset::TimeZone;
+
+    /// Builds one backup per day at 22:00 UTC, starting with "A" on Monday, 2014-07-07.
+    fn backups(ids: &str) -> Vec<BackupFileMeta> {
+        ids.chars()
+            .enumerate()
+            .map(|(i, id)| build_meta(&id.to_string(), Utc.ymd(2014, 7, 7 + i as u32).and_hms(22, 0, 0)))
+            .collect()
+    }
+
+    fn reference_time() -> DateTime<Utc> {
+        Utc.ymd(2014, 7, 20).and_hms(0, 0, 0)
+    }
+
+    #[test]
+    fn test_expendable_backups() {
+        let inner = Box::new(KeepLastN::new(100));
+        let strategy = KeepBusinessDaysOnly::new(inner, reference_time(), Duration::days(3));
+        let mut backups = backups("ABCDEFGHIJKLMN");
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // M and N are weekend backups, too, but recent enough.
+        assert_eq!(collect_ids(expendable_backups), as_vector("FG"));
+        assert_eq!(collect_ids(backups), as_vector("ABCDEHIJKLMN"));
+    }
+
+    #[test]
+    fn test_expendable_backups_keeps_the_ones_expendable_to_the_inner_strategy() {
+        let inner = Box::new(OlderThan::new(Duration::days(10), reference_time()));
+        let strategy = KeepBusinessDaysOnly::new(inner, reference_time(), Duration::days(3));
+        let mut backups = backups("ABCDEFGHIJKLMN");
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("ABCFG"));
+        assert_eq!(collect_ids(backups), as_vector("DEHIJKLMN"));
+    }
+
+    #[test]
+    fn test_expendable_backups_with_custom_weekend_days() {
+        let inner = Box::new(KeepLastN::new(100));
+        let strategy = KeepBusinessDaysOnly::new(inner, reference_time(), Duration::days(3))
+            .with_weekend_days(vec![Weekday::Fri, Weekday::Sat]);
+        let mut backups = backups("ABCDEFG");
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("EF"));
+        assert_eq!(collect_ids(backups), as_vector("ABCDG"));
+    }
+
+    #[test]
+    fn test_expendable_backups_with_timezone() {
+        let inner = Box::new(KeepLastN::new(100));
+
+        // At UTC+3, each backup has been made the day after its UTC date.
+        let strategy = KeepBusinessDaysOnly::new(inner, reference_time(), Duration::days(3))
+            .with_timezone(FixedOffset::east(3 * 3600));
+        let mut backups = backups("ABCDEFG");
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("EF"));
+        assert_eq!(collect_ids(backups), as_vector("ABCDG"));
+    }
+
+    #[test]
+    fn test_expendable_backups_with_no_backups_given() {
+        let inner = Box::new(KeepLastN::new(100));
+        let strategy = KeepBusinessDaysOnly::new(inner, reference_time(), Duration::days(3));
+        let mut backups = vec![];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert!(expendable_backups.is_empty());
+        assert!(backups.is_empty());
+    }
+}
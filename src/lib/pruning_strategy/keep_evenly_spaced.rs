@@ -0,0 +1,204 @@
+use super::{PruningStrategy, BackupFileMeta, StrategyConfigError};
+use std::collections::BTreeSet;
+use chrono::{DateTime, Utc};
+use time::Duration;
+
+/// Keeps about `target_count` backups, spread as evenly across time as possible. Places
+/// `target_count` anchors evenly between the oldest and the newest backup and keeps the
+/// backup nearest to each of them. Unlike `KeepEveryNth`, this spaces backups by time
+/// rather than by index, so dense stretches of backups don't get more survivors. Fewer
+/// backups are kept, if several anchors share their nearest backup. The most recent
+/// backup is always kept.
+pub struct KeepEvenlySpaced {
+    target_count: usize,
+}
+
+impl KeepEvenlySpaced {
+
+    /// Panics, if `target_count` is 0, see `try_new`.
+    pub fn new(target_count: usize) -> KeepEvenlySpaced {
+        KeepEvenlySpaced::try_new(target_count).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Fails, if `target_count` is 0, as the most recent backup is always kept.
+    pub fn try_new(target_count: usize) -> Result<KeepEvenlySpaced, StrategyConfigError> {
+        if target_count == 0 {
+            return Err(StrategyConfigError::Zero("target_count"));
+        }
+
+        Ok(KeepEvenlySpaced {
+            target_count,
+        })
+    }
+
+    /// Returns the anchors between `oldest` and `newest`, both of which are anchors
+    /// themselves, unless there is only a single one. That one is `newest`.
+    fn anchors(&self, oldest: DateTime<Utc>, newest: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+        if self.target_count == 1 {
+            return vec![newest];
+        }
+
+        let span = newest.signed_duration_since(oldest).num_milliseconds();
+        let gaps = self.target_count as i64 - 1;
+
+        (0..self.target_count as i64)
+            .map(|i| oldest + Duration::milliseconds(span / gaps * i + span % gaps * i / gaps))
+            .collect()
+    }
+}
+
+/// Returns the index of the backup dated nearest to `anchor` among the chronologically
+/// sorted `backups`. Prefers the newer one of two equally near backups.
+fn nearest_backup(backups: &[BackupFileMeta], anchor: DateTime<Utc>) -> usize {
+    crate::assert_sorted_by_date(backups);
+
+    let index = backups.partition_point(|backup| backup.date < anchor);
+
+    if index == backups.len() {
+        return index - 1;
+    }
+    if index == 0 {
+        return 0;
+    }
+
+    let distance_to_older = anchor.signed_duration_since(backups[index - 1].date);
+    let distance_to_newer = backups[index].date.signed_duration_since(anchor);
+
+    if distance_to_older < distance_to_newer { index - 1 } else { index }
+}
+
+impl PruningStrategy for KeepEvenlySpaced {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        backups.sort_by_key(|backup| backup.date);
+
+        let (oldest, newest) = match (backups.first(), backups.last()) {
+            (Some(oldest), Some(newest)) => (oldest.date, newest.date),
+            _ => return vec![],
+        };
+
+        let indices_to_keep: BTreeSet<usize> = self.anchors(oldest, newest)
+            .into_iter()
+            .map(|anchor| nearest_backup(backups, anchor))
+            .collect();
+
+        let mut backups_to_keep = vec![];
+        let mut expendable_backups = vec![];
+
+        for (i, backup) in backups.drain(..).enumerate() {
+            if indices_to_keep.contains(&i) {
+                backups_to_keep.push(backup);
+            }
+            else {
+                expendable_backups.push(backup);
+            }
+        }
+
+        *backups = backups_to_keep;
+
+        expendable_backups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use chrono::offset::TimeZone;
+
+    fn dates(backups: &[BackupFileMeta]) -> Vec<DateTime<Utc>> {
+        backups.iter().map(|backup| backup.date).collect()
+    }
+
+    #[test]
+    fn test_expendable_backups() {
+
+        // Hourly backups on the first day, daily ones afterwards.
+        let mut backups: Vec<BackupFileMeta> = (0..24)
+            .map(|hour| build_meta(&format!("1-{}", hour), Utc.ymd(2014, 7, 1).and_hms(hour, 0, 0)))
+            .chain((2..11).map(|day| build_meta(&day.to_string(), Utc.ymd(2014, 7, day).and_hms(0, 0, 0))))
+            .collect();
+        backups.reverse();
+        let strategy = KeepEvenlySpaced::new(4);
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // Counting by index would keep mostly backups of the first day.
+        assert_eq!(dates(&backups), vec![
+            Utc.ymd(2014, 7, 1).and_hms(0, 0, 0),
+            Utc.ymd(2014, 7, 4).and_hms(0, 0, 0),
+            Utc.ymd(2014, 7, 7).and_hms(0, 0, 0),
+            Utc.ymd(2014, 7, 10).and_hms(0, 0, 0),
+        ]);
+        assert_eq!(expendable_backups.len(), 29);
+    }
+
+    #[test]
+    fn test_expendable_backups_keeps_the_backups_nearest_to_the_anchors() {
+        let strategy = KeepEvenlySpaced::new(3);
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 4).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 6).and_hms(0, 0, 0)), // Nearest to the 6th, 12:00.
+            build_meta("D", Utc.ymd(2014, 7, 9).and_hms(0, 0, 0)),
+            build_meta("E", Utc.ymd(2014, 7, 12).and_hms(0, 0, 0)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("BD"));
+        assert_eq!(collect_ids(backups), as_vector("ACE"));
+    }
+
+    #[test]
+    fn test_expendable_backups_with_a_single_anchor() {
+        let strategy = KeepEvenlySpaced::new(1);
+        let mut backups = vec![
+            build_meta("B", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("A"));
+        assert_eq!(collect_ids(backups), as_vector("B"));
+    }
+
+    #[test]
+    fn test_expendable_backups_with_fewer_backups_than_anchors() {
+        let strategy = KeepEvenlySpaced::new(10);
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 20).and_hms(0, 0, 0)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert!(expendable_backups.is_empty());
+        assert_eq!(collect_ids(backups), as_vector("ABC"));
+    }
+
+    #[test]
+    fn test_expendable_backups_with_no_backups_given() {
+        let strategy = KeepEvenlySpaced::new(5);
+        let mut backups = vec![];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert!(expendable_backups.is_empty());
+        assert!(backups.is_empty());
+    }
+
+    #[test]
+    fn test_try_new_when_target_count_is_zero() {
+        assert_eq!(KeepEvenlySpaced::try_new(0).err(), Some(StrategyConfigError::Zero("target_count")));
+        assert!(KeepEvenlySpaced::try_new(1).is_ok());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_new_when_target_count_is_zero() {
+        KeepEvenlySpaced::new(0);
+    }
+}
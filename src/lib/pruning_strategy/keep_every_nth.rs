@@ -0,0 +1,137 @@
+use super::{PruningStrategy, BackupFileMeta, StrategyConfigError};
+
+/// Orders backups chronologically and keeps every `n`th of them, starting with the
+/// oldest one. The most recent backup is always kept.
+pub struct KeepEveryNth {
+    n: usize,
+}
+
+impl KeepEveryNth {
+
+    /// Panics, if `n` is 0, see `try_new`.
+    pub fn new(n: usize) -> KeepEveryNth {
+        KeepEveryNth::try_new(n).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Fails, if `n` is 0, as there is no 0th backup to keep.
+    pub fn try_new(n: usize) -> Result<KeepEveryNth, StrategyConfigError> {
+        if n == 0 {
+            return Err(StrategyConfigError::Zero("n"));
+        }
+
+        Ok(KeepEveryNth {
+            n,
+        })
+    }
+}
+
+impl PruningStrategy for KeepEveryNth {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        backups.sort_by_key(|backup| backup.date);
+
+        let index_of_newest_backup = backups.len().saturating_sub(1);
+        let mut backups_to_keep = vec![];
+        let mut expendable_backups = vec![];
+
+        for (i, backup) in backups.drain(..).enumerate() {
+            if i % self.n == 0 || i == index_of_newest_backup {
+                backups_to_keep.push(backup);
+            }
+            else {
+                expendable_backups.push(backup);
+            }
+        }
+
+        *backups = backups_to_keep;
+
+        expendable_backups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    /// Builds one backup per day from `ids` in chronological order, and returns them
+    /// newest first.
+    fn backups(ids: &str) -> Vec<BackupFileMeta> {
+        let mut backups: Vec<BackupFileMeta> = ids.chars()
+            .enumerate()
+            .map(|(i, id)| build_meta(&id.to_string(), Utc.ymd(2014, 7, 1 + i as u32).and_hms(0, 0, 0)))
+            .collect();
+        backups.reverse();
+
+        backups
+    }
+
+    #[test]
+    fn test_expendable_backups_when_n_is_1() {
+        let strategy = KeepEveryNth::new(1);
+        let mut backups = backups("ABCD");
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert!(expendable_backups.is_empty());
+        assert_eq!(collect_ids(backups), as_vector("ABCD"));
+    }
+
+    #[test]
+    fn test_expendable_backups_when_n_is_2() {
+        let strategy = KeepEveryNth::new(2);
+        let mut backups = backups("ABCDE");
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("BD"));
+        assert_eq!(collect_ids(backups), as_vector("ACE"));
+    }
+
+    #[test]
+    fn test_expendable_backups_when_the_number_of_backups_is_not_divisible_by_n() {
+        let strategy = KeepEveryNth::new(3);
+        let mut backups = backups("ABCDEFGH");
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("BCEF"));
+        assert_eq!(collect_ids(backups), as_vector("ADGH"));
+    }
+
+    #[test]
+    fn test_expendable_backups_always_keeps_the_newest_backup() {
+        let strategy = KeepEveryNth::new(10);
+        let mut backups = backups("ABC");
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("B"));
+        assert_eq!(collect_ids(backups), as_vector("AC"));
+    }
+
+    #[test]
+    fn test_expendable_backups_with_no_backups_given() {
+        let strategy = KeepEveryNth::new(2);
+        let mut backups = vec![];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert!(expendable_backups.is_empty());
+        assert!(backups.is_empty());
+    }
+
+    #[test]
+    fn test_try_new_when_n_is_0() {
+        assert_eq!(KeepEveryNth::try_new(0).err(), Some(StrategyConfigError::Zero("n")));
+        assert!(KeepEveryNth::try_new(1).is_ok());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_new_when_n_is_0() {
+        KeepEveryNth::new(0);
+    }
+}
@@ -0,0 +1,149 @@
+use super::{PruningStrategy, BackupFileMeta, StrategyConfigError};
+use std::cmp::Reverse;
+use chrono::{DateTime, Utc};
+
+/// Keeps the newest backup of each band of ages, with the bands growing exponentially:
+/// Backups less than a day old, at least 1 but less than `base` days old, at least
+/// `base` but less than `base`² days old and so on. Thins out old backups aggressively,
+/// leaving a long but compact history.
+pub struct KeepExponential {
+    reference_time: DateTime<Utc>,
+    base: u32,
+}
+
+impl KeepExponential {
+
+    /// Panics, if `base` is less than 2, see `try_new`.
+    pub fn new(reference_time: DateTime<Utc>, base: u32) -> KeepExponential {
+        KeepExponential::try_new(reference_time, base).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Fails, if `base` is less than 2, as the bands wouldn't grow.
+    pub fn try_new(reference_time: DateTime<Utc>, base: u32) -> Result<KeepExponential, StrategyConfigError> {
+        if base < 2 {
+            return Err(StrategyConfigError::BaseTooSmall(base));
+        }
+
+        Ok(KeepExponential {
+            reference_time,
+            base,
+        })
+    }
+
+    /// Returns the index of the band `backup` belongs to. Backups dated after
+    /// `reference_time` belong to the first band.
+    fn band(&self, backup: &BackupFileMeta) -> u32 {
+        let age = backup.age(self.reference_time).num_seconds();
+        let mut upper_bound: i64 = 24 * 60 * 60;
+        let mut band = 0;
+
+        while age >= upper_bound {
+            band += 1;
+            upper_bound = match upper_bound.checked_mul(i64::from(self.base)) {
+                Some(upper_bound) => upper_bound,
+                None => break,
+            };
+        }
+
+        band
+    }
+}
+
+impl PruningStrategy for KeepExponential {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        backups.sort_by_key(|backup| Reverse(backup.date));
+
+        let mut backups_to_keep = vec![];
+        let mut expendable_backups = vec![];
+        let mut last_band = None;
+
+        for backup in backups.drain(..) {
+            let band = self.band(&backup);
+
+            if last_band != Some(band) {
+                last_band = Some(band);
+                backups_to_keep.push(backup);
+            }
+            else {
+                expendable_backups.push(backup);
+            }
+        }
+
+        backups_to_keep.reverse();
+        expendable_backups.reverse();
+        *backups = backups_to_keep;
+
+        expendable_backups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use time::Duration;
+    use chrono::offset::TimeZone;
+
+    fn reference_time() -> DateTime<Utc> {
+        Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)
+    }
+
+    /// Returns a backup every 6 hours, for the 100 days up to the reference time.
+    fn dense_backups() -> Vec<BackupFileMeta> {
+        (0..400)
+            .map(|i| build_meta(&i.to_string(), reference_time() - Duration::hours(6 * i)))
+            .collect()
+    }
+
+    fn ages_in_days(backups: &[BackupFileMeta]) -> Vec<i64> {
+        backups.iter().map(|backup| backup.age(reference_time()).num_days()).collect()
+    }
+
+    #[test]
+    fn test_expendable_backups() {
+        let strategy = KeepExponential::new(reference_time(), 2);
+        let mut backups = dense_backups();
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // The newest backup of each band, the gaps between them doubling with age.
+        assert_eq!(ages_in_days(&backups), vec![64, 32, 16, 8, 4, 2, 1, 0]);
+        assert_eq!(expendable_backups.len(), 400 - 8);
+    }
+
+    #[test]
+    fn test_expendable_backups_with_base_3() {
+        let strategy = KeepExponential::new(reference_time(), 3);
+        let mut backups = dense_backups();
+
+        strategy.expendable_backups(&mut backups);
+
+        assert_eq!(ages_in_days(&backups), vec![81, 27, 9, 3, 1, 0]);
+    }
+
+    #[test]
+    fn test_expendable_backups_keeps_the_newest_backup_of_each_band() {
+        let strategy = KeepExponential::new(reference_time(), 2);
+        let mut backups = vec![
+            build_meta("A", reference_time() - Duration::days(7)),
+            build_meta("B", reference_time() - Duration::days(5)),
+            build_meta("C", reference_time() - Duration::days(3)),
+            build_meta("D", reference_time() - Duration::hours(30)),
+            build_meta("E", reference_time() + Duration::hours(1)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // A and B fall into the band of 4 to 8 days, and D is alone in the one of 1 to 2
+        // days.
+        assert_eq!(collect_ids(expendable_backups), as_vector("A"));
+        assert_eq!(collect_ids(backups), as_vector("BCDE"));
+    }
+
+    #[test]
+    fn test_try_new_when_the_base_is_too_small() {
+        assert_eq!(KeepExponential::try_new(reference_time(), 1).err(), Some(StrategyConfigError::BaseTooSmall(1)));
+        assert!(KeepExponential::try_new(reference_time(), 2).is_ok());
+    }
+}
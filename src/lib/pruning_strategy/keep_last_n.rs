@@ -0,0 +1,76 @@
+use super::{PruningStrategy, BackupFileMeta};
+use std::mem;
+
+/// Keeps the `n` most recent backups and considers all others expendable.
+pub struct KeepLastN {
+    n: usize,
+}
+
+impl KeepLastN {
+
+    pub fn new(n: usize) -> KeepLastN {
+        KeepLastN {
+            n,
+        }
+    }
+}
+
+impl PruningStrategy for KeepLastN {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        backups.sort_by_key(|backup| backup.date);
+
+        let number_of_expendable_backups = backups.len().saturating_sub(self.n);
+        let backups_to_keep = backups.split_off(number_of_expendable_backups);
+
+        mem::replace(backups, backups_to_keep)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    #[test]
+    fn test_expendable_backups() {
+        let strategy = KeepLastN::new(2);
+        let mut backups = vec![
+            build_meta("C", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0)),
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("D", Utc.ymd(2014, 7, 4).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("AB"));
+        assert_eq!(collect_ids(backups), as_vector("CD"));
+    }
+
+    #[test]
+    fn test_expendable_backups_when_less_than_n_backups_are_given() {
+        let strategy = KeepLastN::new(3);
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert!(expendable_backups.is_empty());
+        assert_eq!(collect_ids(backups), as_vector("A"));
+    }
+
+    #[test]
+    fn test_expendable_backups_with_no_backups_given() {
+        let strategy = KeepLastN::new(3);
+        let mut backups = vec![];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert!(expendable_backups.is_empty());
+        assert!(backups.is_empty());
+    }
+}
@@ -0,0 +1,76 @@
+use super::{PruningStrategy, BackupFileMeta};
+use std::collections::HashMap;
+
+/// Keeps the `k` most recent versions of each key and considers all older versions
+/// expendable, e.g. to prune the noncurrent versions of a versioned bucket, whose
+/// backups are overwritten in place. Needs the backups to be listed by version (see
+/// `AwsS3::with_versions`), so that several of them share an `id`.
+pub struct KeepLastNVersionsPerKey {
+    k: usize,
+}
+
+impl KeepLastNVersionsPerKey {
+
+    pub fn new(k: usize) -> KeepLastNVersionsPerKey {
+        KeepLastNVersionsPerKey {
+            k,
+        }
+    }
+}
+
+impl PruningStrategy for KeepLastNVersionsPerKey {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        backups.sort_by_key(|backup| backup.date);
+
+        // Counts the versions of each key seen so far, from the newest one on.
+        let mut newer_versions: HashMap<String, usize> = HashMap::new();
+        let mut expendable_backups = vec![];
+        let mut backups_to_keep = vec![];
+
+        for backup in backups.drain(..).rev() {
+            let count = newer_versions.entry(backup.id.clone()).or_insert(0);
+
+            if *count < self.k {
+                backups_to_keep.push(backup);
+            }
+            else {
+                expendable_backups.push(backup);
+            }
+            *count += 1;
+        }
+
+        backups_to_keep.reverse();
+        expendable_backups.reverse();
+        *backups = backups_to_keep;
+
+        expendable_backups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    #[test]
+    fn test_expendable_backups() {
+        let strategy = KeepLastNVersionsPerKey::new(2);
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("A", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+            build_meta("A", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 4).and_hms(0, 0, 0)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // Only A has more than two versions.
+        assert_eq!(collect_ids(expendable_backups), as_vector("A"));
+        assert_eq!(collect_ids(backups), as_vector("BAABC"));
+    }
+}
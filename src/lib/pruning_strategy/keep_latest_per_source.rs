@@ -0,0 +1,129 @@
+use super::{PruningStrategy, BackupFileMeta};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+
+/// Wraps another strategy and always keeps the newest backup of each source, even if
+/// the `inner` strategy considers it expendable. That way, sources that are backed up
+/// rarely don't lose their only recent backup in a bucket shared with other sources.
+///
+/// The source of a backup is the first capture group of `source_pattern` within its
+/// `id` (e.g. `db1` for `^(\w+)-` and `db1-2014-07-01.dump`), or the whole match, if
+/// the pattern has no groups. Backups not matching the pattern have no source and are
+/// left to the `inner` strategy.
+pub struct KeepLatestPerSource {
+    inner: Box<dyn PruningStrategy>,
+    source_pattern: Regex,
+}
+
+impl KeepLatestPerSource {
+
+    pub fn new(inner: Box<dyn PruningStrategy>, source_pattern: Regex) -> KeepLatestPerSource {
+        KeepLatestPerSource {
+            inner,
+            source_pattern,
+        }
+    }
+
+    fn source<'a>(&self, id: &'a str) -> Option<&'a str> {
+        let captures = self.source_pattern.captures(id)?;
+
+        captures.get(1).or_else(|| captures.get(0)).map(|source| source.as_str())
+    }
+}
+
+impl PruningStrategy for KeepLatestPerSource {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        let inner_expendable_backups = self.inner.expendable_backups(backups);
+
+        let mut latest_per_source: HashMap<&str, &BackupFileMeta> = HashMap::new();
+        for backup in backups.iter().chain(inner_expendable_backups.iter()) {
+            if let Some(source) = self.source(&backup.id) {
+                match latest_per_source.get(source) {
+                    Some(latest) if latest.date >= backup.date => (),
+                    _ => { latest_per_source.insert(source, backup); },
+                }
+            }
+        }
+        let latest_ids: HashSet<String> = latest_per_source.values().map(|backup| backup.id.clone()).collect();
+
+        let mut expendable_backups = vec![];
+        for backup in inner_expendable_backups {
+            if latest_ids.contains(&backup.id) {
+                backups.push(backup);
+            }
+            else {
+                expendable_backups.push(backup);
+            }
+        }
+
+        expendable_backups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::OlderThan;
+    use super::super::tests::{build_meta, collect_ids};
+    use time::Duration;
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    #[test]
+    fn test_expendable_backups() {
+        let strategy = KeepLatestPerSource::new(
+            Box::new(OlderThan::new(Duration::days(3), Utc.ymd(2014, 7, 10).and_hms(0, 0, 0))),
+            Regex::new(r"^(\w+)-").unwrap(),
+        );
+
+        // db1 is backed up daily, db2 weekly.
+        let mut backups = vec![
+            build_meta("db1-07-07", Utc.ymd(2014, 7, 7).and_hms(0, 0, 0)),
+            build_meta("db1-07-08", Utc.ymd(2014, 7, 8).and_hms(0, 0, 0)),
+            build_meta("db1-07-09", Utc.ymd(2014, 7, 9).and_hms(0, 0, 0)),
+            build_meta("db2-06-26", Utc.ymd(2014, 6, 26).and_hms(0, 0, 0)),
+            build_meta("db2-07-03", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0)),
+        ];
+
+        let mut expendable_backups = strategy.expendable_backups(&mut backups);
+        backups.sort_by_key(|backup| backup.id.clone());
+        expendable_backups.sort_by_key(|backup| backup.id.clone());
+
+        assert_eq!(collect_ids(backups), vec!["db1-07-07", "db1-07-08", "db1-07-09", "db2-07-03"]);
+        assert_eq!(collect_ids(expendable_backups), vec!["db2-06-26"]);
+    }
+
+    #[test]
+    fn test_expendable_backups_when_all_sources_are_outdated() {
+        let strategy = KeepLatestPerSource::new(
+            Box::new(OlderThan::new(Duration::days(3), Utc.ymd(2014, 7, 30).and_hms(0, 0, 0))),
+            Regex::new(r"^(\w+)-").unwrap(),
+        );
+        let mut backups = vec![
+            build_meta("db1-07-08", Utc.ymd(2014, 7, 8).and_hms(0, 0, 0)),
+            build_meta("db1-07-09", Utc.ymd(2014, 7, 9).and_hms(0, 0, 0)),
+            build_meta("db2-07-03", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0)),
+            build_meta("unrelated", Utc.ymd(2014, 7, 20).and_hms(0, 0, 0)),
+        ];
+
+        let mut expendable_backups = strategy.expendable_backups(&mut backups);
+        backups.sort_by_key(|backup| backup.id.clone());
+        expendable_backups.sort_by_key(|backup| backup.id.clone());
+
+        // Backups without a source are left to the inner strategy.
+        assert_eq!(collect_ids(backups), vec!["db1-07-09", "db2-07-03"]);
+        assert_eq!(collect_ids(expendable_backups), vec!["db1-07-08", "unrelated"]);
+    }
+
+    #[test]
+    fn test_source_without_capture_group() {
+        let strategy = KeepLatestPerSource::new(
+            Box::new(OlderThan::new(Duration::days(3), Utc::now())),
+            Regex::new(r"^db\d").unwrap(),
+        );
+
+        assert_eq!(strategy.source("db1-07-08"), Some("db1"));
+        assert_eq!(strategy.source("unrelated"), None);
+    }
+}
@@ -0,0 +1,115 @@
+use super::{PruningStrategy, BackupFileMeta};
+use crate::reporting::Period;
+use std::cmp::Reverse;
+
+/// Keeps the newest backup of each of the `n` most recent periods (e.g. days) that
+/// have backups, like restic's and borg's `--keep-daily` and the like. Periods without
+/// backups don't count towards `n`.
+pub struct KeepNewestPerPeriod {
+    period: Period,
+    n: usize,
+}
+
+impl KeepNewestPerPeriod {
+
+    pub fn new(period: Period, n: usize) -> KeepNewestPerPeriod {
+        KeepNewestPerPeriod {
+            period,
+            n,
+        }
+    }
+}
+
+impl PruningStrategy for KeepNewestPerPeriod {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        backups.sort_by_key(|backup| Reverse(backup.date));
+
+        let mut backups_to_keep = vec![];
+        let mut expendable_backups = vec![];
+        let mut last_period = None;
+
+        for backup in backups.drain(..) {
+            let period = self.period.beginning(backup.date);
+
+            if last_period != Some(period) && backups_to_keep.len() < self.n {
+                last_period = Some(period);
+                backups_to_keep.push(backup);
+            }
+            else {
+                expendable_backups.push(backup);
+            }
+        }
+
+        backups_to_keep.reverse();
+        expendable_backups.reverse();
+        *backups = backups_to_keep;
+
+        expendable_backups
+    }
+
+    fn explain(&self, backup: &BackupFileMeta, backups: &[BackupFileMeta]) -> Option<String> {
+        let period = format!("{:?}", self.period).to_lowercase();
+        let mut kept_backups = backups.to_vec();
+        self.expendable_backups(&mut kept_backups);
+
+        if kept_backups.iter().any(|kept| kept.id == backup.id) {
+            Some(format!("It's the newest backup of its {}, one of the {} latest {}s with backups, so it's kept.", period, self.n, period))
+        }
+        else {
+            Some(format!("It's not the newest backup of one of the {} latest {}s with backups, so it's expendable.", self.n, period))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    #[test]
+    fn test_expendable_backups() {
+        let strategy = KeepNewestPerPeriod::new(Period::Day, 2);
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(1, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 1).and_hms(13, 0, 0)),
+            build_meta("D", Utc.ymd(2014, 7, 5).and_hms(13, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 5).and_hms(1, 0, 0)),
+            build_meta("E", Utc.ymd(2014, 7, 7).and_hms(1, 0, 0)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // Days without backups in between don't count.
+        assert_eq!(collect_ids(backups), as_vector("DE"));
+        assert_eq!(collect_ids(expendable_backups), as_vector("ABC"));
+    }
+
+    #[test]
+    fn test_expendable_backups_with_fewer_periods_than_n() {
+        let strategy = KeepNewestPerPeriod::new(Period::Month, 12);
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 6, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 6, 30).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(backups), as_vector("BC"));
+        assert_eq!(collect_ids(expendable_backups), as_vector("A"));
+    }
+
+    #[test]
+    fn test_expendable_backups_when_n_is_0() {
+        let strategy = KeepNewestPerPeriod::new(Period::Year, 0);
+        let mut backups = vec![build_meta("A", Utc.ymd(2014, 6, 1).and_hms(0, 0, 0))];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("A"));
+        assert!(backups.is_empty());
+    }
+}
@@ -0,0 +1,215 @@
+use super::{PruningStrategy, BackupFileMeta, StrategyConfigError};
+use crate::date_time_utilities;
+use std::collections::BTreeSet;
+use time::Duration;
+use chrono::{DateTime, Utc, Datelike};
+use chrono::offset::TimeZone;
+
+/// Keeps one backup for each of the given `days` of each month, e.g. the 1st and the
+/// 15th. It will be the one closest to the respective day, and only backups less than
+/// `tolerance` away from it are considered. Days beyond the end of a month refer to its
+/// last day, e.g. the 31st is the 28th in February of most years.
+///
+/// This generalizes `KeepOnePerMonth` to several days per month.
+pub struct KeepOnSpecificDays {
+    days: Vec<u8>,
+    tolerance: Duration,
+}
+
+impl KeepOnSpecificDays {
+
+    /// Panics, if a day is not within the 1st to the 31st, see `try_new`.
+    pub fn new(days: Vec<u8>, tolerance: Duration) -> KeepOnSpecificDays {
+        KeepOnSpecificDays::try_new(days, tolerance).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Fails, if a day is not within the 1st to the 31st.
+    pub fn try_new(days: Vec<u8>, tolerance: Duration) -> Result<KeepOnSpecificDays, StrategyConfigError> {
+        if let Some(day) = days.iter().find(|day| !(1..=31).contains(*day)) {
+            return Err(StrategyConfigError::InvalidDayOfMonth(*day));
+        }
+
+        Ok(KeepOnSpecificDays {
+            days,
+            tolerance,
+        })
+    }
+
+    /// Returns the dates of the days to keep backups for in the month starting at
+    /// `beginning_of_month`, in chronological order.
+    fn anchors(&self, beginning_of_month: DateTime<Utc>) -> BTreeSet<DateTime<Utc>> {
+        let days_in_month = date_time_utilities::beginning_of_next_month(beginning_of_month)
+            .signed_duration_since(beginning_of_month)
+            .num_days() as u32;
+
+        self.days
+            .iter()
+            .map(|day| Utc.ymd(beginning_of_month.year(), beginning_of_month.month(), u32::from(*day).min(days_in_month)).and_hms(0, 0, 0))
+            .collect()
+    }
+}
+
+/// Returns the index of the backup closest to `date` within `tolerance`, which hasn't
+/// been `claimed` for another day yet, among the chronologically sorted `backups`.
+pub(super) fn nearest_unclaimed_backup(
+    backups: &[BackupFileMeta],
+    date: DateTime<Utc>,
+    tolerance: Duration,
+    claimed: &BTreeSet<usize>,
+) -> Option<usize> {
+    crate::assert_sorted_by_date(backups);
+
+    let first = backups.partition_point(|backup| backup.date < date - tolerance);
+    let end = backups.partition_point(|backup| backup.date <= date + tolerance);
+    let mut index_of_nearest_backup: Option<usize> = None;
+
+    for i in (first..end).filter(|i| !claimed.contains(i)) {
+        match index_of_nearest_backup {
+            Some(nearest) if !date_time_utilities::is_closer(date, backups[i].date, backups[nearest].date) => (),
+            _ => index_of_nearest_backup = Some(i),
+        }
+    }
+
+    index_of_nearest_backup
+}
+
+impl PruningStrategy for KeepOnSpecificDays {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        backups.sort_by_key(|backup| backup.date);
+
+        let (oldest_date, youngest_date) = match (backups.first(), backups.last()) {
+            (Some(oldest), Some(youngest)) => (oldest.date, youngest.date),
+            _ => return vec![],
+        };
+
+        let mut claimed = BTreeSet::new();
+        let mut month = date_time_utilities::beginning_of_month(oldest_date - self.tolerance);
+
+        while month <= youngest_date + self.tolerance {
+            for anchor in self.anchors(month) {
+                if let Some(index) = nearest_unclaimed_backup(backups, anchor, self.tolerance, &claimed) {
+                    claimed.insert(index);
+                }
+            }
+
+            month = date_time_utilities::beginning_of_next_month(month);
+        }
+
+        let mut backups_to_keep = vec![];
+        let mut expendable_backups = vec![];
+
+        for (i, backup) in backups.drain(..).enumerate() {
+            if claimed.contains(&i) {
+                backups_to_keep.push(backup);
+            }
+            else {
+                expendable_backups.push(backup);
+            }
+        }
+
+        *backups = backups_to_keep;
+
+        expendable_backups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::{build_meta, collect_ids};
+
+    /// Builds one backup per day from `start` to `end` (inclusive), with ids of the form
+    /// `MM-DD`.
+    fn daily_backups(start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<BackupFileMeta> {
+        let mut backups = vec![];
+        let mut date = start;
+
+        while date <= end {
+            backups.push(build_meta(&format!("{:02}-{:02}", date.month(), date.day()), date));
+            date = date + Duration::days(1);
+        }
+
+        backups
+    }
+
+    #[test]
+    fn test_expendable_backups() {
+        let strategy = KeepOnSpecificDays::new(vec![1, 15], Duration::days(3));
+        let mut backups = daily_backups(Utc.ymd(2014, 5, 1).and_hms(6, 0, 0), Utc.ymd(2014, 7, 20).and_hms(6, 0, 0));
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(backups), vec!["05-01", "05-15", "06-01", "06-15", "07-01", "07-15"]);
+        assert_eq!(expendable_backups.len(), 81 - 6);
+    }
+
+    #[test]
+    fn test_expendable_backups_keeps_the_nearest_backups() {
+        let strategy = KeepOnSpecificDays::new(vec![1, 15], Duration::days(5));
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 6, 28).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 6, 30).and_hms(12, 0, 0)), // Nearest to the 1st.
+            build_meta("C", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0)),
+            build_meta("D", Utc.ymd(2014, 7, 12).and_hms(0, 0, 0)),
+            build_meta("E", Utc.ymd(2014, 7, 17).and_hms(0, 0, 0)), // Nearest to the 15th.
+            build_meta("F", Utc.ymd(2014, 7, 24).and_hms(0, 0, 0)), // Too far from both days.
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(backups), vec!["B", "E"]);
+        assert_eq!(collect_ids(expendable_backups), vec!["A", "C", "D", "F"]);
+    }
+
+    #[test]
+    fn test_expendable_backups_clamps_days_to_the_end_of_the_month() {
+        let strategy = KeepOnSpecificDays::new(vec![30, 31], Duration::hours(12));
+        let mut backups = daily_backups(Utc.ymd(2014, 2, 1).and_hms(0, 0, 0), Utc.ymd(2014, 4, 30).and_hms(0, 0, 0));
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // Both days are the 28th in February, and one backup is enough for it.
+        assert_eq!(collect_ids(backups), vec!["02-28", "03-30", "03-31", "04-30"]);
+        assert_eq!(expendable_backups.len(), 89 - 4);
+    }
+
+    #[test]
+    fn test_expendable_backups_when_the_nearest_backup_has_already_been_claimed() {
+        let strategy = KeepOnSpecificDays::new(vec![1, 2], Duration::days(3));
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(12, 0, 0)), // Nearest to both days.
+            build_meta("B", Utc.ymd(2014, 7, 5).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 20).and_hms(0, 0, 0)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // The 2nd gets the nearest backup, that hasn't already been kept for the 1st.
+        assert_eq!(collect_ids(backups), vec!["A", "B"]);
+        assert_eq!(collect_ids(expendable_backups), vec!["C"]);
+    }
+
+    #[test]
+    fn test_expendable_backups_with_no_backups_given() {
+        let strategy = KeepOnSpecificDays::new(vec![1, 15], Duration::days(3));
+        let mut backups = vec![];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert!(expendable_backups.is_empty());
+        assert!(backups.is_empty());
+    }
+
+    #[test]
+    fn test_try_new_with_an_invalid_day() {
+        assert_eq!(KeepOnSpecificDays::try_new(vec![1, 32], Duration::days(3)).err(), Some(StrategyConfigError::InvalidDayOfMonth(32)));
+        assert_eq!(KeepOnSpecificDays::try_new(vec![0], Duration::days(3)).err(), Some(StrategyConfigError::InvalidDayOfMonth(0)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_new_with_an_invalid_day() {
+        KeepOnSpecificDays::new(vec![32], Duration::days(3));
+    }
+}
@@ -1,24 +1,109 @@
-mod date_time_utilities;
-
 use super::{PruningStrategy, BackupFileMeta};
+use crate::date_time_utilities;
+use std::collections::HashSet;
 use time::Duration;
 use chrono::{DateTime, Utc};
 
 /// Keeps one backup for each month. It will be the one that's closest to the
 /// 1st day of the respective month. Will only consider backups that are less
-/// than `tolerance` away from the 1st of the month.
+/// than the tolerance away from the 1st of the month, see `TolerancePolicy`.
+///
+/// Optionally, backups of at least `min_size` bytes are preferred, as small ones are
+/// likely truncated. See `with_min_size`.
+///
+/// Backups may be given in any order, they're sorted by date before being searched, and
+/// left sorted.
 pub struct KeepOnePerMonth {
-    tolerance: Duration,
+    tolerance: TolerancePolicy,
+    min_size: Option<u64>,
+}
+
+/// How far away from the 1st of a month backups may be, to be kept for it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TolerancePolicy {
+
+    /// The same tolerance for all months.
+    Fixed(Duration),
+
+    /// `recent` for months beginning at most `older_than` before `reference_time`, `old`
+    /// for all older ones. Allows old, sparse history to still keep a backup per month.
+    AgeScaled {
+        recent: Duration,
+        old: Duration,
+        older_than: Duration,
+        reference_time: DateTime<Utc>,
+    },
+}
+
+impl TolerancePolicy {
+
+    /// Returns the tolerance for the month beginning at `month`.
+    fn tolerance(&self, month: DateTime<Utc>) -> Duration {
+        match *self {
+            TolerancePolicy::Fixed(tolerance) => tolerance,
+            TolerancePolicy::AgeScaled { recent, old, older_than, reference_time } => {
+                if reference_time - month > older_than { old } else { recent }
+            },
+        }
+    }
+}
+
+impl From<Duration> for TolerancePolicy {
+
+    fn from(tolerance: Duration) -> TolerancePolicy {
+        TolerancePolicy::Fixed(tolerance)
+    }
 }
 
 impl KeepOnePerMonth {
 
-    pub fn new(tolerance: Duration) -> KeepOnePerMonth {
+    /// Takes either a `TolerancePolicy` or a `Duration` as fixed `tolerance`.
+    pub fn new<T: Into<TolerancePolicy>>(tolerance: T) -> KeepOnePerMonth {
 
         KeepOnePerMonth {
-            tolerance,
+            tolerance: tolerance.into(),
+            min_size: None,
         }
     }
+
+    /// Keeps the closest backup of at least `min_size` bytes for each month, instead of
+    /// the closest one. Only if there is no such backup within `tolerance`, the closest
+    /// smaller one is kept.
+    pub fn with_min_size(mut self, min_size: u64) -> KeepOnePerMonth {
+        self.min_size = Some(min_size);
+        self
+    }
+
+    /// Returns the index of the backup closest to `date` within `tolerance`, that is at
+    /// least `min_size` bytes large, ignoring all indices before `skip_indices_before`.
+    /// The `backups` need to be sorted by date.
+    fn nearest_large_enough_backup(
+        backups: &[BackupFileMeta],
+        date: DateTime<Utc>,
+        tolerance: Duration,
+        skip_indices_before: usize,
+        min_size: u64,
+    ) -> Option<usize> {
+        crate::assert_sorted_by_date(backups);
+
+        let mut index_of_nearest_backup: Option<usize> = None;
+
+        for (i, backup) in backups.iter().enumerate().skip(skip_indices_before) {
+            if backup.date > date + tolerance {
+                break;
+            }
+            if backup.date < date - tolerance || backup.size < min_size {
+                continue;
+            }
+
+            match index_of_nearest_backup {
+                Some(nearest) if !date_time_utilities::is_closer(date, backup.date, backups[nearest].date) => (),
+                _ => index_of_nearest_backup = Some(i),
+            }
+        }
+
+        index_of_nearest_backup
+    }
 }
 
 impl PruningStrategy for KeepOnePerMonth {
@@ -28,18 +113,18 @@ impl PruningStrategy for KeepOnePerMonth {
             return Vec::with_capacity(0);
         }
 
-        backups.sort_by(|a, b| a.date.cmp(&b.date));
+        backups.sort_by_key(|backup| backup.date);
 
         let oldest_date = backups.first().unwrap().date;
         let youngest_date = backups.last().unwrap().date;
-        let backup_for_month = |date: DateTime<Utc>, skip_indices_before: usize| -> Option<usize> {
+        let backup_for_month = |date: DateTime<Utc>, tolerance: Duration, skip_indices_before: usize| -> Option<usize> {
             let mut index_of_nearest_backup = None;
 
-            for i in skip_indices_before..backups.len() {
-                if backups[i].date < date - self.tolerance {
+            for (i, backup) in backups.iter().enumerate().skip(skip_indices_before) {
+                if backup.date < date - tolerance {
                     continue;
                 }
-                if backups[i].date > date + self.tolerance {
+                if backup.date > date + tolerance {
                     return None;
                 }
 
@@ -47,13 +132,10 @@ impl PruningStrategy for KeepOnePerMonth {
                 break;
             }
 
-            if index_of_nearest_backup.is_none() {
-                return None;
-            }
-            let mut index_of_nearest_backup = index_of_nearest_backup.unwrap();
+            let mut index_of_nearest_backup = index_of_nearest_backup?;
 
             for i in (index_of_nearest_backup + 1)..backups.len() {
-                if backups[i].date > date + self.tolerance {
+                if backups[i].date > date + tolerance {
                     return Some(index_of_nearest_backup);
                 }
 
@@ -70,26 +152,38 @@ impl PruningStrategy for KeepOnePerMonth {
         let last_date = date_time_utilities::beginning_of_next_month(youngest_date);
         let mut date = date_time_utilities::beginning_of_month(oldest_date);
         let mut start_index = 0;
-        let mut backups_to_keep_indices: Vec<usize> = vec![];
+        let mut backups_to_keep_indices: HashSet<usize> = HashSet::new();
 
         while date <= last_date {
-            let backup_index = backup_for_month(date, start_index);
-
-            if backup_index.is_some() {
-                backups_to_keep_indices.push(backup_index.unwrap());
-                start_index = backup_index.unwrap() + 1;
+            let tolerance = self.tolerance.tolerance(date);
+            let backup_index = match self.min_size {
+                Some(min_size) => KeepOnePerMonth::nearest_large_enough_backup(backups, date, tolerance, start_index, min_size)
+                    .or_else(|| backup_for_month(date, tolerance, start_index)),
+                None => backup_for_month(date, tolerance, start_index),
+            };
+
+            if let Some(backup_index) = backup_index {
+                backups_to_keep_indices.insert(backup_index);
+                start_index = backup_index + 1;
             }
 
             date = date_time_utilities::beginning_of_next_month(date);
         }
 
+        let mut backups_to_keep = vec![];
         let mut expendable_backups = vec![];
-        for i in (0..backups.len()).rev() {
-            if !backups_to_keep_indices.contains(&i) {
-                expendable_backups.insert(0, backups.remove(i));
+
+        for (i, backup) in backups.drain(..).enumerate() {
+            if backups_to_keep_indices.contains(&i) {
+                backups_to_keep.push(backup);
+            }
+            else {
+                expendable_backups.push(backup);
             }
         }
 
+        *backups = backups_to_keep;
+
         expendable_backups
     }
 }
@@ -186,4 +280,128 @@ mod tests {
         assert_eq!(collect_ids(expendable_backups), as_vector("BC"));
         assert_eq!(collect_ids(backups), as_vector("A"));
     }
+
+    fn build_meta_with_size(id: &str, date: DateTime<Utc>, size: u64) -> BackupFileMeta {
+        BackupFileMeta {
+            size,
+            ..build_meta(id, date)
+        }
+    }
+
+    #[test]
+    fn test_expendable_backups_with_min_size() {
+        let strategy = KeepOnePerMonth::new(Duration::days(5)).with_min_size(1000);
+        let mut backups = vec![
+            build_meta_with_size("1", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0), 10),
+            build_meta_with_size("A", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0), 5000),
+            build_meta_with_size("2", Utc.ymd(2014, 7, 4).and_hms(0, 0, 0), 5000),
+            build_meta_with_size("B", Utc.ymd(2014, 8, 1).and_hms(0, 0, 0), 5000),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // The tiny backup 1 is closest to July 1st, but A is large enough.
+        assert_eq!(collect_ids(expendable_backups), as_vector("12"));
+        assert_eq!(collect_ids(backups), as_vector("AB"));
+    }
+
+    #[test]
+    fn test_expendable_backups_with_min_size_when_no_backup_is_large_enough() {
+        let strategy = KeepOnePerMonth::new(Duration::days(5)).with_min_size(1000);
+        let mut backups = vec![
+            build_meta_with_size("1", Utc.ymd(2014, 6, 28).and_hms(0, 0, 0), 10),
+            build_meta_with_size("A", Utc.ymd(2014, 7, 1).and_hms(12, 0, 0), 20),
+            build_meta_with_size("2", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0), 30),
+            build_meta_with_size("3", Utc.ymd(2014, 7, 20).and_hms(0, 0, 0), 5000), // Out of tolerance.
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("123"));
+        assert_eq!(collect_ids(backups), as_vector("A"));
+    }
+
+    #[test]
+    fn test_expendable_backups_without_min_size_keeps_tiny_backups() {
+        let strategy = KeepOnePerMonth::new(Duration::days(5));
+        let mut backups = vec![
+            build_meta_with_size("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0), 10),
+            build_meta_with_size("1", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0), 5000),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("1"));
+        assert_eq!(collect_ids(backups), as_vector("A"));
+    }
+
+    #[test]
+    fn test_expendable_backups_with_many_backups() {
+
+        // Used to take minutes, as each expendable backup was moved separately.
+        let newest = Utc.ymd(2014, 7, 1).and_hms(0, 0, 0);
+        let mut backups: Vec<BackupFileMeta> = (0..100_000)
+            .rev()
+            .map(|hours_ago| build_meta(&hours_ago.to_string(), newest - Duration::hours(hours_ago)))
+            .collect();
+        let strategy = KeepOnePerMonth::new(Duration::days(15));
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // One backup for each month from February 2003 up to July 2014, taken on the 1st
+        // except for the oldest one, taken on the 2nd of February.
+        assert_eq!(backups.len(), 138);
+        assert!(backups[1..].iter().all(|backup| backup.date.format("%d %H").to_string() == "01 00"));
+        assert_eq!(expendable_backups.len(), 100_000 - 138);
+        assert!(crate::is_sorted_by_date(&expendable_backups));
+    }
+
+    fn age_scaled_tolerance() -> TolerancePolicy {
+        TolerancePolicy::AgeScaled {
+            recent: Duration::days(10),
+            old: Duration::days(45),
+            older_than: Duration::days(365),
+            reference_time: Utc.ymd(2016, 7, 1).and_hms(0, 0, 0),
+        }
+    }
+
+    fn sparse_backups() -> Vec<BackupFileMeta> {
+        vec![
+            build_meta("A", Utc.ymd(2014, 3, 15).and_hms(0, 0, 0)), // Two weeks away from March 1st.
+            build_meta("B", Utc.ymd(2016, 6, 15).and_hms(0, 0, 0)), // Two weeks away from June 1st.
+        ]
+    }
+
+    #[test]
+    fn test_expendable_backups_with_fixed_tolerance_loses_old_sparse_months() {
+        let strategy = KeepOnePerMonth::new(TolerancePolicy::Fixed(Duration::days(10)));
+        let mut backups = sparse_backups();
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("AB"));
+        assert!(backups.is_empty());
+    }
+
+    #[test]
+    fn test_expendable_backups_with_age_scaled_tolerance() {
+        let strategy = KeepOnePerMonth::new(age_scaled_tolerance());
+        let mut backups = sparse_backups();
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // Only the old month is granted the wider tolerance.
+        assert_eq!(collect_ids(expendable_backups), as_vector("B"));
+        assert_eq!(collect_ids(backups), as_vector("A"));
+    }
+
+    #[test]
+    fn test_tolerance() {
+        let policy = age_scaled_tolerance();
+
+        assert_eq!(policy.tolerance(Utc.ymd(2016, 6, 1).and_hms(0, 0, 0)), Duration::days(10));
+        assert_eq!(policy.tolerance(Utc.ymd(2015, 8, 1).and_hms(0, 0, 0)), Duration::days(10));
+        assert_eq!(policy.tolerance(Utc.ymd(2015, 6, 1).and_hms(0, 0, 0)), Duration::days(45));
+        assert_eq!(TolerancePolicy::from(Duration::days(3)).tolerance(Utc::now()), Duration::days(3));
+    }
 }
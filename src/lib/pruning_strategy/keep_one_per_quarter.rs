@@ -0,0 +1,138 @@
+use super::{PruningStrategy, BackupFileMeta, StrategyConfigError};
+use super::keep_on_specific_days::nearest_unclaimed_backup;
+use crate::date_time_utilities;
+use std::collections::BTreeSet;
+use time::Duration;
+
+/// Keeps one backup for each quarter. It will be the one closest to the 1st day of the
+/// quarter, and only backups less than `tolerance` away from it are considered.
+///
+/// Quarters are calendar quarters (starting in January, April, July and October) for a
+/// `fiscal_year_start` of 1, and shifted accordingly for fiscal years starting in
+/// another month, e.g. February, May, August and November for 2.
+pub struct KeepOnePerQuarter {
+    tolerance: Duration,
+    fiscal_year_start: u8,
+}
+
+impl KeepOnePerQuarter {
+
+    /// Panics, if `fiscal_year_start` is not a month, see `try_new`.
+    pub fn new(tolerance: Duration, fiscal_year_start: u8) -> KeepOnePerQuarter {
+        KeepOnePerQuarter::try_new(tolerance, fiscal_year_start).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Fails, if `fiscal_year_start` is not within 1 to 12.
+    pub fn try_new(tolerance: Duration, fiscal_year_start: u8) -> Result<KeepOnePerQuarter, StrategyConfigError> {
+        if !(1..=12).contains(&fiscal_year_start) {
+            return Err(StrategyConfigError::InvalidMonth(fiscal_year_start));
+        }
+
+        Ok(KeepOnePerQuarter {
+            tolerance,
+            fiscal_year_start,
+        })
+    }
+}
+
+impl PruningStrategy for KeepOnePerQuarter {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        backups.sort_by_key(|backup| backup.date);
+
+        let (oldest_date, youngest_date) = match (backups.first(), backups.last()) {
+            (Some(oldest), Some(youngest)) => (oldest.date, youngest.date),
+            _ => return vec![],
+        };
+
+        let fiscal_year_start = u32::from(self.fiscal_year_start);
+        let mut claimed = BTreeSet::new();
+        let mut quarter = date_time_utilities::beginning_of_quarter(oldest_date - self.tolerance, fiscal_year_start);
+
+        while quarter <= youngest_date + self.tolerance {
+            if let Some(index) = nearest_unclaimed_backup(backups, quarter, self.tolerance, &claimed) {
+                claimed.insert(index);
+            }
+
+            quarter = date_time_utilities::beginning_of_next_quarter(quarter, fiscal_year_start);
+        }
+
+        let mut backups_to_keep = vec![];
+        let mut expendable_backups = vec![];
+
+        for (i, backup) in backups.drain(..).enumerate() {
+            if claimed.contains(&i) {
+                backups_to_keep.push(backup);
+            }
+            else {
+                expendable_backups.push(backup);
+            }
+        }
+
+        *backups = backups_to_keep;
+
+        expendable_backups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::{build_meta, collect_ids};
+    use chrono::{DateTime, Utc};
+    use chrono::offset::TimeZone;
+
+    /// Builds one backup every 10 days from the 25th of December 2013 on over the year
+    /// 2014, with ids of the form `MM-DD`.
+    fn backups_over_a_year() -> Vec<BackupFileMeta> {
+        let mut backups = vec![];
+        let mut date: DateTime<Utc> = Utc.ymd(2013, 12, 25).and_hms(0, 0, 0);
+
+        while date < Utc.ymd(2015, 1, 1).and_hms(0, 0, 0) {
+            backups.push(build_meta(&date.format("%m-%d").to_string(), date));
+            date = date + Duration::days(10);
+        }
+
+        backups
+    }
+
+    #[test]
+    fn test_expendable_backups_with_calendar_quarters() {
+        let strategy = KeepOnePerQuarter::new(Duration::days(5), 1);
+        let mut backups = backups_over_a_year();
+
+        strategy.expendable_backups(&mut backups);
+
+        // The nearest backups to January 1st, April 1st, July 1st, October 1st and January
+        // 1st 2015.
+        assert_eq!(collect_ids(backups), vec!["01-04", "04-04", "07-03", "10-01", "12-30"]);
+    }
+
+    #[test]
+    fn test_expendable_backups_with_a_fiscal_year_starting_in_april() {
+        let strategy = KeepOnePerQuarter::new(Duration::days(5), 4);
+        let mut backups = backups_over_a_year();
+
+        strategy.expendable_backups(&mut backups);
+
+        // Quarters starting in April are calendar quarters, only counted differently.
+        assert_eq!(collect_ids(backups), vec!["01-04", "04-04", "07-03", "10-01", "12-30"]);
+    }
+
+    #[test]
+    fn test_expendable_backups_with_a_fiscal_year_starting_in_february() {
+        let strategy = KeepOnePerQuarter::new(Duration::days(5), 2);
+        let mut backups = backups_over_a_year();
+
+        strategy.expendable_backups(&mut backups);
+
+        // The nearest backups to February 1st, May 1st, August 1st and November 1st.
+        assert_eq!(collect_ids(backups), vec!["02-03", "05-04", "08-02", "10-31"]);
+    }
+
+    #[test]
+    fn test_try_new_when_the_fiscal_year_start_is_not_a_month() {
+        assert_eq!(KeepOnePerQuarter::try_new(Duration::days(5), 13).err(), Some(StrategyConfigError::InvalidMonth(13)));
+        assert_eq!(KeepOnePerQuarter::try_new(Duration::days(5), 0).err(), Some(StrategyConfigError::InvalidMonth(0)));
+    }
+}
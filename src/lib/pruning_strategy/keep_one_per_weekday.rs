@@ -0,0 +1,123 @@
+use super::{PruningStrategy, BackupFileMeta};
+use time::Duration;
+use chrono::{DateTime, Datelike, FixedOffset, Utc, Weekday};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+
+/// Keeps the most recent backup of each day of the week among the backups within
+/// `window` from `reference_time`, so every weekday is represented (e.g. to debug
+/// issues only occurring on certain days). All other backups are expendable, so
+/// combine it with another strategy using `and`, to keep more.
+///
+/// Weekdays are determined in UTC unless another `timezone` is given.
+pub struct KeepOnePerWeekday {
+    reference_time: DateTime<Utc>,
+    window: Duration,
+    timezone: FixedOffset,
+}
+
+impl KeepOnePerWeekday {
+
+    pub fn new(reference_time: DateTime<Utc>, window: Duration) -> KeepOnePerWeekday {
+        KeepOnePerWeekday {
+            reference_time,
+            window,
+            timezone: FixedOffset::east(0),
+        }
+    }
+
+    /// Determines the day of week of a backup in the given `timezone`, instead of UTC.
+    pub fn with_timezone(mut self, timezone: FixedOffset) -> KeepOnePerWeekday {
+        self.timezone = timezone;
+        self
+    }
+}
+
+impl PruningStrategy for KeepOnePerWeekday {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        backups.sort_by_key(|backup| Reverse(backup.date));
+
+        let mut backups_to_keep = vec![];
+        let mut expendable_backups = vec![];
+        let mut represented_weekdays: HashSet<Weekday> = HashSet::new();
+
+        for backup in backups.drain(..) {
+            let weekday = backup.date.with_timezone(&self.timezone).weekday();
+
+            if !backup.is_older_than(self.window, self.reference_time) && represented_weekdays.insert(weekday) {
+                backups_to_keep.push(backup);
+            }
+            else {
+                expendable_backups.push(backup);
+            }
+        }
+
+        backups_to_keep.reverse();
+        expendable_backups.reverse();
+        *backups = backups_to_keep;
+
+        expendable_backups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use chrono::offset::TimeZone;
+
+    /// Two backups a day from Monday the 30th of June up to Sunday the 13th of July
+    /// 2014, named `<day of month>-<hour>`.
+    fn two_weeks_of_backups() -> Vec<BackupFileMeta> {
+        let mut backups = vec![];
+        let mut date = Utc.ymd(2014, 6, 30).and_hms(1, 0, 0);
+
+        while date < Utc.ymd(2014, 7, 14).and_hms(0, 0, 0) {
+            backups.push(build_meta(&date.format("%d-%H").to_string(), date));
+            date = date + Duration::hours(12);
+        }
+
+        backups
+    }
+
+    #[test]
+    fn test_expendable_backups() {
+        let strategy = KeepOnePerWeekday::new(Utc.ymd(2014, 7, 14).and_hms(0, 0, 0), Duration::weeks(2));
+        let mut backups = two_weeks_of_backups();
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(backups), vec!["07-13", "08-13", "09-13", "10-13", "11-13", "12-13", "13-13"]);
+        assert_eq!(expendable_backups.len(), 21);
+    }
+
+    #[test]
+    fn test_expendable_backups_outside_the_window() {
+        let strategy = KeepOnePerWeekday::new(Utc.ymd(2014, 7, 4).and_hms(0, 0, 0), Duration::days(2));
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(backups), as_vector("BC"));
+        assert_eq!(collect_ids(expendable_backups), as_vector("A"));
+    }
+
+    #[test]
+    fn test_expendable_backups_with_timezone() {
+        let strategy = KeepOnePerWeekday::new(Utc.ymd(2014, 7, 14).and_hms(0, 0, 0), Duration::weeks(2))
+            .with_timezone(FixedOffset::east(12 * 3600));
+        let mut backups = two_weeks_of_backups();
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // From 12:00 UTC on, it's the next day already, so the newest backups of a day
+        // are the ones at 01:00 UTC, except for the last one, made on Monday the 14th.
+        assert_eq!(collect_ids(backups), vec!["08-01", "09-01", "10-01", "11-01", "12-01", "13-01", "13-13"]);
+        assert_eq!(expendable_backups.len(), 21);
+    }
+}
@@ -0,0 +1,105 @@
+use super::{PruningStrategy, BackupFileMeta};
+use super::composite_strategy::expendable_ids;
+
+/// Wraps another strategy and caps the total size of the expendable backups at
+/// `max_bytes`, spreading large prunings over several runs. What `inner` gives up goes
+/// in order of age, stopping before the backup that would exceed the cap, so the newer
+/// ones wait for a later run.
+pub struct MaxDeleteBytes {
+    inner: Box<dyn PruningStrategy>,
+    max_bytes: u64,
+}
+
+impl MaxDeleteBytes {
+
+    pub fn new(inner: Box<dyn PruningStrategy>, max_bytes: u64) -> MaxDeleteBytes {
+        MaxDeleteBytes {
+            inner,
+            max_bytes,
+        }
+    }
+}
+
+impl PruningStrategy for MaxDeleteBytes {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        let mut inner_expendable_backups = self.inner.expendable_backups(backups);
+        inner_expendable_backups.sort_by_key(|backup| backup.date);
+
+        let mut expendable_backups = vec![];
+        let mut total_bytes: u64 = 0;
+        let mut capped = false;
+
+        for backup in inner_expendable_backups {
+            capped = capped || total_bytes.saturating_add(backup.size) > self.max_bytes;
+
+            if capped {
+                backups.push(backup);
+            }
+            else {
+                total_bytes += backup.size;
+                expendable_backups.push(backup);
+            }
+        }
+
+        expendable_backups
+    }
+
+    fn explain(&self, backup: &BackupFileMeta, backups: &[BackupFileMeta]) -> Option<String> {
+        if expendable_ids(self.inner.as_ref(), backups).contains(&backup.id) && !expendable_ids(self, backups).contains(&backup.id) {
+            Some(format!("It's expendable, but deleting it would exceed max_delete_bytes ({} bytes), so it's kept for now.", self.max_bytes))
+        }
+        else {
+            self.inner.explain(backup, backups)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::KeepLastN;
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    fn build_meta_with_size(id: &str, day: u32, size: u64) -> BackupFileMeta {
+        BackupFileMeta {
+            size,
+            ..build_meta(id, Utc.ymd(2014, 7, day).and_hms(0, 0, 0))
+        }
+    }
+
+    #[test]
+    fn test_expendable_backups() {
+        let strategy = MaxDeleteBytes::new(Box::new(KeepLastN::new(1)), GIB);
+        let mut backups = vec![
+            build_meta_with_size("C", 3, 300 * 1024 * 1024),
+            build_meta_with_size("A", 1, 400 * 1024 * 1024),
+            build_meta_with_size("B", 2, 324 * 1024 * 1024),
+            build_meta_with_size("D", 4, 1),
+            build_meta_with_size("E", 5, 10 * GIB),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // A, B and C take exactly 1 GiB, so D has to wait for the next run, although it's
+        // tiny.
+        assert_eq!(collect_ids(expendable_backups), as_vector("ABC"));
+
+        let mut kept_backups = collect_ids(backups);
+        kept_backups.sort();
+        assert_eq!(kept_backups, as_vector("DE"));
+    }
+
+    #[test]
+    fn test_expendable_backups_when_the_oldest_backup_exceeds_the_cap() {
+        let strategy = MaxDeleteBytes::new(Box::new(KeepLastN::new(0)), GIB);
+        let mut backups = vec![build_meta_with_size("A", 1, 2 * GIB), build_meta_with_size("B", 2, 1)];
+
+        assert!(strategy.expendable_backups(&mut backups).is_empty());
+        assert_eq!(backups.len(), 2);
+    }
+}
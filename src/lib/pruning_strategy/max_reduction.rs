@@ -0,0 +1,163 @@
+use super::{PruningStrategy, BackupFileMeta, StrategyConfigError};
+use super::composite_strategy::expendable_ids;
+
+/// Wraps another strategy and keeps it from shrinking the set of kept backups by more
+/// than `max_reduction_pct` percent of the `previously_kept` ones in a single run, e.g.
+/// after a misconfigured policy. Only as many of the backups `inner` gives up as the
+/// limit allows are expendable, the oldest ones, while the newer ones survive until the
+/// next run.
+pub struct MaxReduction {
+    inner: Box<dyn PruningStrategy>,
+
+    /// `None`, if there's no previous run, so the stored backups count as kept.
+    previously_kept: Option<usize>,
+    max_reduction_pct: u8,
+}
+
+impl MaxReduction {
+
+    /// Panics, if `max_reduction_pct` exceeds 100, see `try_new`.
+    pub fn new(inner: Box<dyn PruningStrategy>, previously_kept: usize, max_reduction_pct: u8) -> MaxReduction {
+        MaxReduction::try_new(inner, previously_kept, max_reduction_pct).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Fails, if `max_reduction_pct` exceeds 100.
+    pub fn try_new(
+        inner: Box<dyn PruningStrategy>,
+        previously_kept: usize,
+        max_reduction_pct: u8,
+    ) -> Result<MaxReduction, StrategyConfigError> {
+        if max_reduction_pct > 100 {
+            return Err(StrategyConfigError::InvalidPercentage(max_reduction_pct));
+        }
+
+        Ok(MaxReduction {
+            inner,
+            previously_kept: Some(previously_kept),
+            max_reduction_pct,
+        })
+    }
+
+    /// Panics, if `max_reduction_pct` exceeds 100, see `try_relative_to_stored`.
+    pub fn relative_to_stored(inner: Box<dyn PruningStrategy>, max_reduction_pct: u8) -> MaxReduction {
+        MaxReduction::try_relative_to_stored(inner, max_reduction_pct).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Like `try_new`, but for a first run, which no backups have been kept by before:
+    /// All backups evaluated count as previously kept, so the first run of a new policy
+    /// is limited too.
+    pub fn try_relative_to_stored(inner: Box<dyn PruningStrategy>, max_reduction_pct: u8) -> Result<MaxReduction, StrategyConfigError> {
+        Ok(MaxReduction {
+            previously_kept: None,
+            ..MaxReduction::try_new(inner, 0, max_reduction_pct)?
+        })
+    }
+
+    /// The fewest backups to keep, i.e. the previously kept ones (or the `stored` ones,
+    /// if there's no previous run) minus the maximum reduction, rounded down.
+    fn min_kept(&self, stored: usize) -> usize {
+        let previously_kept = self.previously_kept.unwrap_or(stored);
+
+        previously_kept - previously_kept * self.max_reduction_pct as usize / 100
+    }
+}
+
+impl PruningStrategy for MaxReduction {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        let min_kept = self.min_kept(backups.len());
+        let mut expendable_backups = self.inner.expendable_backups(backups);
+        expendable_backups.sort_by_key(|backup| backup.date);
+
+        let deferred = min_kept.saturating_sub(backups.len()).min(expendable_backups.len());
+        let deferred_start = expendable_backups.len() - deferred;
+        backups.extend(expendable_backups.drain(deferred_start..));
+
+        expendable_backups
+    }
+
+    fn explain(&self, backup: &BackupFileMeta, backups: &[BackupFileMeta]) -> Option<String> {
+        if expendable_ids(self.inner.as_ref(), backups).contains(&backup.id) && !expendable_ids(self, backups).contains(&backup.id) {
+            Some(format!(
+                "It's expendable, but deleting it would shrink the kept backups by more than max_reduction_pct ({}%), so it's kept for now.",
+                self.max_reduction_pct,
+            ))
+        }
+        else {
+            self.inner.explain(backup, backups)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::KeepLastN;
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use time::Duration;
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    fn daily_backups(n: usize) -> Vec<BackupFileMeta> {
+        (0..n)
+            .map(|day| build_meta(&format!("{:03}", day), Utc.ymd(2014, 1, 1).and_hms(0, 0, 0) + Duration::days(day as i64)))
+            .collect()
+    }
+
+    #[test]
+    fn test_expendable_backups() {
+        let strategy = MaxReduction::new(Box::new(KeepLastN::new(10)), 100, 30);
+        let mut backups = daily_backups(100);
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // Only the 30 oldest of the 90 backups KeepLastN would drop.
+        assert_eq!(collect_ids(expendable_backups), collect_ids(daily_backups(30)));
+        assert_eq!(backups.len(), 70);
+    }
+
+    #[test]
+    fn test_expendable_backups_within_the_limit() {
+        let strategy = MaxReduction::new(Box::new(KeepLastN::new(2)), 3, 50);
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0)),
+        ];
+
+        assert_eq!(collect_ids(strategy.expendable_backups(&mut backups)), as_vector("A"));
+        assert_eq!(collect_ids(backups), as_vector("BC"));
+    }
+
+    #[test]
+    fn test_expendable_backups_without_previously_kept_backups() {
+        let strategy = MaxReduction::new(Box::new(KeepLastN::new(0)), 0, 0);
+        let mut backups = daily_backups(3);
+
+        assert_eq!(strategy.expendable_backups(&mut backups).len(), 3);
+        assert!(backups.is_empty());
+    }
+
+    #[test]
+    fn test_expendable_backups_relative_to_stored() {
+        let strategy = MaxReduction::relative_to_stored(Box::new(KeepLastN::new(10)), 30);
+        let mut backups = daily_backups(100);
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), collect_ids(daily_backups(30)));
+        assert_eq!(backups.len(), 70);
+    }
+
+    #[test]
+    fn test_try_new() {
+        assert_eq!(
+            MaxReduction::try_new(Box::new(KeepLastN::new(1)), 10, 101).err(),
+            Some(StrategyConfigError::InvalidPercentage(101)),
+        );
+        assert_eq!(
+            MaxReduction::try_relative_to_stored(Box::new(KeepLastN::new(1)), 101).err(),
+            Some(StrategyConfigError::InvalidPercentage(101)),
+        );
+    }
+}
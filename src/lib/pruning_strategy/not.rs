@@ -0,0 +1,56 @@
+use super::{PruningStrategy, BackupFileMeta};
+use super::composite_strategy::expendable_ids;
+
+/// Inverts another strategy: exactly the backups the `inner` strategy keeps are
+/// expendable.
+pub struct Not {
+    inner: Box<dyn PruningStrategy>,
+}
+
+impl Not {
+
+    pub fn new(inner: Box<dyn PruningStrategy>) -> Not {
+        Not {
+            inner,
+        }
+    }
+}
+
+impl PruningStrategy for Not {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        let ids_to_keep = expendable_ids(self.inner.as_ref(), backups);
+
+        let (backups_to_keep, expendable_backups) = backups
+            .drain(..)
+            .partition(|backup: &BackupFileMeta| ids_to_keep.contains(&backup.id));
+
+        *backups = backups_to_keep;
+
+        expendable_backups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::KeepLastN;
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    #[test]
+    fn test_expendable_backups() {
+        let strategy = Not::new(Box::new(KeepLastN::new(1)));
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("C"));
+        assert_eq!(collect_ids(backups), as_vector("AB"));
+    }
+}
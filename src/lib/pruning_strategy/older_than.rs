@@ -18,26 +18,34 @@ impl OlderThan {
     }
 
     fn too_old(&self, backup: &BackupFileMeta) -> bool {
-        self.reference_time.signed_duration_since(backup.date) > self.duration
+        backup.is_older_than(self.duration, self.reference_time)
     }
 }
 
 impl PruningStrategy for OlderThan {
 
     fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
-        let mut expendable_backups: Vec<BackupFileMeta> = vec![];
-        let mut index = backups.len();
+        let (expendable_backups, backups_to_keep) = backups.drain(..).partition(|backup| self.too_old(backup));
+        *backups = backups_to_keep;
 
-        for _ in 0..backups.len() {
-            index -= 1;
+        expendable_backups
+    }
 
-            if self.too_old(&backups[index]) {
-                let backup = backups.remove(index);
-                expendable_backups.insert(0, backup);
-            }
-        }
+    fn max_age(&self) -> Option<Duration> {
+        Some(self.duration)
+    }
 
-        expendable_backups
+    fn is_streaming_safe(&self) -> bool {
+        true
+    }
+
+    fn next_expiry(&self, backups: &[BackupFileMeta]) -> Option<DateTime<Utc>> {
+        backups
+            .iter()
+            .filter(|backup| !self.too_old(backup))
+            .map(|backup| backup.date)
+            .min()
+            .map(|oldest_kept_date| oldest_kept_date + self.duration)
     }
 }
 
@@ -91,4 +99,50 @@ mod tests {
         assert!(expendable_backups.is_empty());
         assert!(backups.is_empty());
     }
+
+    #[test]
+    fn test_expendable_backups_with_many_backups() {
+        let reference_time = Utc.ymd(2014, 11, 14).and_hms(0, 0, 0);
+        let strategy = OlderThan::new(Duration::hours(1000), reference_time);
+
+        // Used to take minutes, as each expendable backup was moved separately.
+        let mut backups: Vec<BackupFileMeta> = (0..200_000)
+            .map(|hours_ago| build_meta(&hours_ago.to_string(), reference_time - Duration::hours(hours_ago)))
+            .collect();
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(backups.len(), 1001);
+        assert_eq!(expendable_backups.len(), 200_000 - 1001);
+        assert_eq!(expendable_backups[0].id, "1001");
+        assert_eq!(expendable_backups.last().unwrap().id, "199999");
+    }
+
+    #[test]
+    fn test_next_expiry() {
+        let strategy = OlderThan::new(Duration::days(7), Utc.ymd(2014, 11, 14).and_hms(0, 0, 0));
+        let backups = vec![
+            build_meta("A", Utc.ymd(2014, 11, 1).and_hms(0, 0, 0)), // Already expendable.
+            build_meta("B", Utc.ymd(2014, 11, 9).and_hms(12, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 11, 13).and_hms(0, 0, 0)),
+        ];
+
+        assert_eq!(strategy.next_expiry(&backups), Some(Utc.ymd(2014, 11, 16).and_hms(12, 0, 0)));
+    }
+
+    #[test]
+    fn test_next_expiry_when_no_backup_is_kept() {
+        let strategy = OlderThan::new(Duration::days(7), Utc.ymd(2014, 11, 14).and_hms(0, 0, 0));
+        let backups = vec![build_meta("A", Utc.ymd(2014, 11, 1).and_hms(0, 0, 0))];
+
+        assert_eq!(strategy.next_expiry(&backups), None);
+        assert_eq!(strategy.next_expiry(&[]), None);
+    }
+
+    #[test]
+    fn test_next_expiry_of_other_strategies() {
+        let backups = vec![build_meta("A", Utc.ymd(2014, 11, 1).and_hms(0, 0, 0))];
+
+        assert_eq!(super::super::KeepLastN::new(1).next_expiry(&backups), None);
+    }
 }
@@ -1,4 +1,5 @@
-use super::{PruningStrategy, BackupFileMeta, KeepOnePerMonth, OlderThan};
+use super::{PruningStrategy, BackupFileMeta, KeepOnePerMonth, OlderThan, StrategyConfigError};
+use crate::date_time_utilities;
 use time::Duration;
 use chrono::{DateTime, Utc};
 
@@ -22,22 +23,38 @@ pub struct OlderThanButKeepOnePerMonth {
 
 impl OlderThanButKeepOnePerMonth {
 
+    /// Panics, if `keep_all_within` exceeds `one_per_month_within`, see `try_new`.
     pub fn new(
         reference_time: DateTime<Utc>,
         keep_all_within: Duration,
         one_per_month_tolerance: Duration,
         one_per_month_within: Duration,
     ) -> OlderThanButKeepOnePerMonth {
+        OlderThanButKeepOnePerMonth::try_new(reference_time, keep_all_within, one_per_month_tolerance, one_per_month_within)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
 
-        // Panic, if the options are contradictory.
-        assert!(keep_all_within <= one_per_month_within);
+    /// Fails, if the options are contradictory, i.e. if `keep_all_within` exceeds
+    /// `one_per_month_within`.
+    pub fn try_new(
+        reference_time: DateTime<Utc>,
+        keep_all_within: Duration,
+        one_per_month_tolerance: Duration,
+        one_per_month_within: Duration,
+    ) -> Result<OlderThanButKeepOnePerMonth, StrategyConfigError> {
+        if keep_all_within > one_per_month_within {
+            return Err(StrategyConfigError::KeepAllWithinExceedsOnePerMonthWithin {
+                keep_all_within,
+                one_per_month_within,
+            });
+        }
 
-        OlderThanButKeepOnePerMonth {
+        Ok(OlderThanButKeepOnePerMonth {
             reference_time,
             keep_all_within,
             one_per_month_tolerance,
             one_per_month_within,
-        }
+        })
     }
 }
 
@@ -58,6 +75,63 @@ impl PruningStrategy for OlderThanButKeepOnePerMonth {
 
         expendable_backups
     }
+
+    fn max_age(&self) -> Option<Duration> {
+
+        // Without a period to keep monthly backups in, this is the same as `OlderThan`.
+        if self.keep_all_within == self.one_per_month_within {
+            Some(self.keep_all_within)
+        }
+        else {
+            None
+        }
+    }
+
+    fn explain(&self, backup: &BackupFileMeta, backups: &[BackupFileMeta]) -> Option<String> {
+        if !backup.is_older_than(self.keep_all_within, self.reference_time) {
+            return Some(format!(
+                "It's within keep_all_within ({} days), so it's kept.",
+                self.keep_all_within.num_days(),
+            ));
+        }
+        if backup.is_older_than(self.one_per_month_within, self.reference_time) {
+            return Some(format!(
+                "It's older than one_per_month_within ({} days), so it's expendable.",
+                self.one_per_month_within.num_days(),
+            ));
+        }
+
+        // Repeat the evaluation of the backups between both windows.
+        let mut older_backups: Vec<BackupFileMeta> = backups
+            .iter()
+            .filter(|other| {
+                other.is_older_than(self.keep_all_within, self.reference_time)
+                    && !other.is_older_than(self.one_per_month_within, self.reference_time)
+            })
+            .cloned()
+            .collect();
+        KeepOnePerMonth::new(self.one_per_month_tolerance).expendable_backups(&mut older_backups);
+
+        if older_backups.iter().any(|kept| kept.id == backup.id) {
+            let month = date_time_utilities::beginning_of_month(backup.date);
+            let next_month = date_time_utilities::beginning_of_next_month(backup.date);
+            let nearest_month = if backup.date - month <= next_month - backup.date { month } else { next_month };
+
+            Some(format!(
+                "It's older than keep_all_within ({} days), but the one closest to {} within one_per_month_tolerance ({} days), so it's kept.",
+                self.keep_all_within.num_days(),
+                nearest_month.format("%Y-%m-%d"),
+                self.one_per_month_tolerance.num_days(),
+            ))
+        }
+        else {
+            Some(format!(
+                "It's older than keep_all_within ({} days) and not the one closest to the 1st of a month within one_per_month_tolerance ({} days), so it's expendable.",
+                self.keep_all_within.num_days(),
+                self.one_per_month_tolerance.num_days(),
+            ))
+        }
+    }
 }
 
 #[cfg(test)]
@@ -111,6 +185,31 @@ mod tests {
         assert_eq!(collect_ids(backups), as_vector("ABCDEMH"));
     }
 
+    #[test]
+    fn test_explain() {
+        let strategy = OlderThanButKeepOnePerMonth::new(
+            Utc.ymd(2014, 6, 15).and_hms(0, 0, 0),
+            Duration::days(1),
+            Duration::days(15),
+            Duration::days(90),
+        );
+        let backups = vec![
+            build_meta("B", Utc.ymd(2014, 6, 15).and_hms(0, 0, 0)),
+            build_meta("K", Utc.ymd(2014, 4, 3).and_hms(0, 0, 0)),
+            build_meta("M", Utc.ymd(2014, 3, 31).and_hms(0, 0, 0)),
+            build_meta("N", Utc.ymd(2014, 3, 1).and_hms(0, 0, 0)),
+        ];
+        let explain = |index: usize| strategy.explain(&backups[index], &backups).unwrap();
+
+        assert_eq!(explain(0), "It's within keep_all_within (1 days), so it's kept.");
+        assert_eq!(
+            explain(2),
+            "It's older than keep_all_within (1 days), but the one closest to 2014-04-01 within one_per_month_tolerance (15 days), so it's kept.",
+        );
+        assert!(explain(1).contains("not the one closest to the 1st of a month"));
+        assert_eq!(explain(3), "It's older than one_per_month_within (90 days), so it's expendable.");
+    }
+
     #[test]
     fn test_expendable_backups_with_no_backups_given() {
         let strategy = OlderThanButKeepOnePerMonth::new(
@@ -127,6 +226,24 @@ mod tests {
         assert!(backups.is_empty());
     }
 
+    #[test]
+    fn test_try_new_when_one_per_month_within_is_less_than_keep_all_within() {
+        let result = OlderThanButKeepOnePerMonth::try_new(
+            Utc.ymd(2014, 6, 15).and_hms(0, 0, 0),
+            Duration::days(2),
+            Duration::days(15),
+            Duration::days(1),
+        );
+
+        match result {
+            Err(error) => assert_eq!(error, StrategyConfigError::KeepAllWithinExceedsOnePerMonthWithin {
+                keep_all_within: Duration::days(2),
+                one_per_month_within: Duration::days(1),
+            }),
+            Ok(_) => panic!("The options should be rejected."),
+        }
+    }
+
     #[test]
     #[should_panic]
     fn test_new_when_one_per_month_within_is_less_than_keep_all_within() {
@@ -0,0 +1,121 @@
+use super::{PruningStrategy, BackupFileMeta};
+use crate::date_time_utilities;
+use chrono::{DateTime, Utc};
+
+/// Wraps another strategy and hides all backups outside of a single calendar month or
+/// year from it, so only that period gets pruned and all others are left untouched.
+pub struct OnlyPeriod {
+    inner: Box<dyn PruningStrategy>,
+
+    /// Beginning of the period.
+    start: DateTime<Utc>,
+
+    /// Beginning of the following period, which isn't part of it anymore.
+    end: DateTime<Utc>,
+}
+
+impl OnlyPeriod {
+
+    /// Only lets `inner` consider the backups of the month `date` lies in.
+    pub fn month(inner: Box<dyn PruningStrategy>, date: DateTime<Utc>) -> OnlyPeriod {
+        OnlyPeriod {
+            inner,
+            start: date_time_utilities::beginning_of_month(date),
+            end: date_time_utilities::beginning_of_next_month(date),
+        }
+    }
+
+    /// Only lets `inner` consider the backups of the year `date` lies in.
+    pub fn year(inner: Box<dyn PruningStrategy>, date: DateTime<Utc>) -> OnlyPeriod {
+        OnlyPeriod {
+            inner,
+            start: date_time_utilities::beginning_of_year(date),
+            end: date_time_utilities::beginning_of_next_year(date),
+        }
+    }
+
+    fn within(&self, backup: &BackupFileMeta) -> bool {
+        self.start <= backup.date && backup.date < self.end
+    }
+}
+
+impl PruningStrategy for OnlyPeriod {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        let (mut considered_backups, mut other_backups): (Vec<BackupFileMeta>, Vec<BackupFileMeta>) = backups
+            .drain(..)
+            .partition(|backup| self.within(backup));
+
+        let expendable_backups = self.inner.expendable_backups(&mut considered_backups);
+
+        backups.append(&mut considered_backups);
+        backups.append(&mut other_backups);
+
+        expendable_backups
+    }
+
+    fn explain(&self, backup: &BackupFileMeta, backups: &[BackupFileMeta]) -> Option<String> {
+        if self.within(backup) {
+            self.inner.explain(backup, backups)
+        }
+        else {
+            Some(String::from("It's outside of the period being pruned, so it's kept."))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{KeepLastN, OlderThan};
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use time::Duration;
+    use chrono::offset::TimeZone;
+
+    fn backups() -> Vec<BackupFileMeta> {
+        vec![
+            build_meta("A", Utc.ymd(2021, 12, 31).and_hms(23, 59, 59)),
+            build_meta("B", Utc.ymd(2022, 6, 30).and_hms(23, 59, 59)),
+            build_meta("C", Utc.ymd(2022, 7, 1).and_hms(0, 0, 0)),
+            build_meta("D", Utc.ymd(2022, 7, 15).and_hms(0, 0, 0)),
+            build_meta("E", Utc.ymd(2022, 7, 31).and_hms(23, 59, 59)),
+            build_meta("F", Utc.ymd(2022, 8, 1).and_hms(0, 0, 0)),
+            build_meta("G", Utc.ymd(2023, 1, 1).and_hms(0, 0, 0)),
+        ]
+    }
+
+    #[test]
+    fn test_expendable_backups_of_a_month() {
+        let strategy = OnlyPeriod::month(Box::new(KeepLastN::new(1)), Utc.ymd(2022, 7, 20).and_hms(0, 0, 0));
+        let mut backups = backups();
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // Only July 2022 is pruned down to one backup.
+        assert_eq!(collect_ids(expendable_backups), as_vector("CD"));
+        assert_eq!(backups.len(), 5);
+    }
+
+    #[test]
+    fn test_expendable_backups_of_a_year() {
+        let reference_time = Utc.ymd(2030, 1, 1).and_hms(0, 0, 0);
+        let strategy = OnlyPeriod::year(Box::new(OlderThan::new(Duration::zero(), reference_time)), Utc.ymd(2022, 7, 20).and_hms(0, 0, 0));
+        let mut backups = backups();
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("BCDEF"));
+        assert_eq!(collect_ids(backups), as_vector("AG"));
+    }
+
+    #[test]
+    fn test_expendable_backups_of_december() {
+        let strategy = OnlyPeriod::month(Box::new(KeepLastN::new(0)), Utc.ymd(2021, 12, 1).and_hms(0, 0, 0));
+        let mut backups = backups();
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("A"));
+        assert_eq!(backups.len(), 6);
+    }
+}
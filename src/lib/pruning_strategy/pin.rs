@@ -0,0 +1,87 @@
+use super::{PruningStrategy, BackupFileMeta};
+use std::collections::HashSet;
+
+/// Wraps another strategy and keeps the backups with the given `ids`, whatever the
+/// `inner` strategy decides.
+pub struct Pin {
+    inner: Box<dyn PruningStrategy>,
+    ids: HashSet<String>,
+}
+
+impl Pin {
+
+    pub fn new(inner: Box<dyn PruningStrategy>, ids: Vec<String>) -> Pin {
+        Pin {
+            inner,
+            ids: ids.into_iter().collect(),
+        }
+    }
+}
+
+impl PruningStrategy for Pin {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        let mut expendable_backups = vec![];
+
+        for backup in self.inner.expendable_backups(backups) {
+            if self.ids.contains(&backup.id) {
+                backups.push(backup);
+            }
+            else {
+                expendable_backups.push(backup);
+            }
+        }
+
+        expendable_backups
+    }
+
+    fn is_streaming_safe(&self) -> bool {
+        self.inner.is_streaming_safe()
+    }
+
+    fn explain(&self, backup: &BackupFileMeta, backups: &[BackupFileMeta]) -> Option<String> {
+        if self.ids.contains(&backup.id) {
+            Some(String::from("It's pinned, so it's kept."))
+        }
+        else {
+            self.inner.explain(backup, backups)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::KeepLastN;
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    #[test]
+    fn test_expendable_backups() {
+        let strategy = Pin::new(Box::new(KeepLastN::new(0)), vec![String::from("B")]);
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0)),
+        ];
+
+        let mut expendable_backups = collect_ids(strategy.expendable_backups(&mut backups));
+        expendable_backups.sort();
+
+        assert_eq!(expendable_backups, as_vector("AC"));
+        assert_eq!(collect_ids(backups), as_vector("B"));
+    }
+
+    #[test]
+    fn test_explain() {
+        let strategy = Pin::new(Box::new(KeepLastN::new(0)), vec![String::from("B")]);
+        let backups = vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+        ];
+
+        assert_eq!(strategy.explain(&backups[1], &backups), Some(String::from("It's pinned, so it's kept.")));
+        assert_eq!(strategy.explain(&backups[0], &backups), None);
+    }
+}
@@ -0,0 +1,183 @@
+use super::{PruningStrategy, BackupFileMeta, StrategyConfigError};
+use chrono::{DateTime, Utc};
+
+/// Wraps another strategy and keeps every backup the `inner` strategy considers
+/// expendable, if its date lies within one of the quarantined `ranges`. Both ends of
+/// a range are inclusive.
+pub struct Quarantine {
+    inner: Box<dyn PruningStrategy>,
+    ranges: Vec<(DateTime<Utc>, DateTime<Utc>)>,
+}
+
+impl Quarantine {
+
+    /// Panics, if a range ends before it starts, see `try_new`.
+    pub fn new(inner: Box<dyn PruningStrategy>, ranges: Vec<(DateTime<Utc>, DateTime<Utc>)>) -> Quarantine {
+        Quarantine::try_new(inner, ranges).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Fails, if a range ends before it starts.
+    pub fn try_new(inner: Box<dyn PruningStrategy>, ranges: Vec<(DateTime<Utc>, DateTime<Utc>)>) -> Result<Quarantine, StrategyConfigError> {
+        if let Some((start, end)) = ranges.iter().find(|(start, end)| start > end) {
+            return Err(StrategyConfigError::RangeEndsBeforeItStarts {
+                start: *start,
+                end: *end,
+            });
+        }
+
+        Ok(Quarantine {
+            inner,
+            ranges,
+        })
+    }
+
+    fn quarantined(&self, backup: &BackupFileMeta) -> bool {
+        self.ranges.iter().any(|(start, end)| *start <= backup.date && backup.date <= *end)
+    }
+}
+
+impl PruningStrategy for Quarantine {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        let mut expendable_backups = vec![];
+
+        for backup in self.inner.expendable_backups(backups) {
+            if self.quarantined(&backup) {
+                backups.push(backup);
+            }
+            else {
+                expendable_backups.push(backup);
+            }
+        }
+
+        // Backups kept again need to be put back in between the others.
+        backups.sort_by_key(|backup| backup.date);
+
+        expendable_backups
+    }
+
+    fn explain(&self, backup: &BackupFileMeta, backups: &[BackupFileMeta]) -> Option<String> {
+        if self.quarantined(backup) {
+            Some(String::from("It's within a quarantined range, so it's kept."))
+        }
+        else {
+            self.inner.explain(backup, backups)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{OlderThan, KeepLastN};
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use time::Duration;
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    fn delete_everything() -> Box<dyn PruningStrategy> {
+        Box::new(OlderThan::new(Duration::zero(), Utc.ymd(2015, 1, 1).and_hms(0, 0, 0)))
+    }
+
+    #[test]
+    fn test_expendable_backups() {
+        let strategy = Quarantine::new(
+            delete_everything(),
+            vec![
+                (Utc.ymd(2014, 3, 1).and_hms(0, 0, 0), Utc.ymd(2014, 3, 31).and_hms(0, 0, 0)),
+                (Utc.ymd(2014, 3, 15).and_hms(0, 0, 0), Utc.ymd(2014, 4, 15).and_hms(0, 0, 0)),
+            ],
+        );
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 2, 28).and_hms(23, 59, 59)),
+
+            // The below are within one or both (overlapping) ranges.
+            build_meta("B", Utc.ymd(2014, 3, 10).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 3, 20).and_hms(0, 0, 0)),
+            build_meta("D", Utc.ymd(2014, 4, 10).and_hms(0, 0, 0)),
+
+            build_meta("E", Utc.ymd(2014, 4, 15).and_hms(0, 0, 1)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("AE"));
+        assert_eq!(collect_ids(backups), as_vector("BCD"));
+    }
+
+    #[test]
+    fn test_expendable_backups_when_a_backup_is_exactly_on_a_boundary() {
+        let strategy = Quarantine::new(
+            delete_everything(),
+            vec![(Utc.ymd(2014, 3, 1).and_hms(0, 0, 0), Utc.ymd(2014, 3, 31).and_hms(0, 0, 0))],
+        );
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 3, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 3, 31).and_hms(0, 0, 0)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert!(expendable_backups.is_empty());
+        assert_eq!(collect_ids(backups), as_vector("AB"));
+    }
+
+    #[test]
+    fn test_expendable_backups_stay_in_chronological_order() {
+        let strategy = Quarantine::new(
+            Box::new(KeepLastN::new(1)),
+            vec![(Utc.ymd(2014, 3, 1).and_hms(0, 0, 0), Utc.ymd(2014, 3, 31).and_hms(0, 0, 0))],
+        );
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 3, 10).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 4, 10).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 5, 10).and_hms(0, 0, 0)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("B"));
+        assert_eq!(collect_ids(backups), as_vector("AC"));
+    }
+
+    #[test]
+    fn test_expendable_backups_with_no_ranges_given() {
+        let strategy = Quarantine::new(delete_everything(), vec![]);
+        let mut backups = vec![
+            build_meta("A", Utc.ymd(2014, 3, 1).and_hms(0, 0, 0)),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("A"));
+        assert!(backups.is_empty());
+    }
+
+    #[test]
+    fn test_try_new_when_a_range_ends_before_it_starts() {
+        let result = Quarantine::try_new(
+            delete_everything(),
+            vec![
+                (Utc.ymd(2014, 1, 1).and_hms(0, 0, 0), Utc.ymd(2014, 1, 31).and_hms(0, 0, 0)),
+                (Utc.ymd(2014, 3, 31).and_hms(0, 0, 0), Utc.ymd(2014, 3, 1).and_hms(0, 0, 0)),
+            ],
+        );
+
+        match result {
+            Err(error) => assert_eq!(error, StrategyConfigError::RangeEndsBeforeItStarts {
+                start: Utc.ymd(2014, 3, 31).and_hms(0, 0, 0),
+                end: Utc.ymd(2014, 3, 1).and_hms(0, 0, 0),
+            }),
+            Ok(_) => panic!("The range should be rejected."),
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_new_when_a_range_ends_before_it_starts() {
+        Quarantine::new(
+            delete_everything(),
+            vec![(Utc.ymd(2014, 3, 31).and_hms(0, 0, 0), Utc.ymd(2014, 3, 1).and_hms(0, 0, 0))],
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use super::{PruningStrategy, BackupFileMeta};
+use chrono::{DateTime, Utc};
+
+/// Wraps another strategy and keeps the backups whose `retain_until` is after
+/// `reference_time`, whatever the `inner` strategy decides. This lets the uploader of a
+/// backup override the retention policy.
+pub struct RespectRetainUntil {
+    inner: Box<dyn PruningStrategy>,
+    reference_time: DateTime<Utc>,
+}
+
+impl RespectRetainUntil {
+
+    pub fn new(inner: Box<dyn PruningStrategy>, reference_time: DateTime<Utc>) -> RespectRetainUntil {
+        RespectRetainUntil {
+            inner,
+            reference_time,
+        }
+    }
+}
+
+impl PruningStrategy for RespectRetainUntil {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        let mut expendable_backups = vec![];
+
+        for backup in self.inner.expendable_backups(backups) {
+            if backup.retain_until.is_some_and(|retain_until| retain_until > self.reference_time) {
+                backups.push(backup);
+            }
+            else {
+                expendable_backups.push(backup);
+            }
+        }
+
+        expendable_backups
+    }
+
+    fn is_streaming_safe(&self) -> bool {
+        self.inner.is_streaming_safe()
+    }
+
+    fn explain(&self, backup: &BackupFileMeta, backups: &[BackupFileMeta]) -> Option<String> {
+        match backup.retain_until {
+            Some(retain_until) if retain_until > self.reference_time => {
+                Some(format!("It's to be retained until {}, so it's kept.", retain_until.to_rfc3339()))
+            },
+            _ => self.inner.explain(backup, backups),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::KeepLastN;
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use chrono::offset::TimeZone;
+
+    fn build_meta_retained_until(id: &str, retain_until: Option<DateTime<Utc>>) -> BackupFileMeta {
+        BackupFileMeta {
+            retain_until,
+            ..build_meta(id, Utc.ymd(2014, 7, 1).and_hms(0, 0, 0))
+        }
+    }
+
+    #[test]
+    fn test_expendable_backups() {
+        let reference_time = Utc.ymd(2014, 8, 1).and_hms(0, 0, 0);
+        let strategy = RespectRetainUntil::new(Box::new(KeepLastN::new(0)), reference_time);
+        let mut backups = vec![
+            build_meta_retained_until("A", None),
+            build_meta_retained_until("B", Some(Utc.ymd(2015, 1, 1).and_hms(0, 0, 0))),
+            build_meta_retained_until("C", Some(reference_time)),
+            build_meta_retained_until("D", Some(Utc.ymd(2014, 7, 15).and_hms(0, 0, 0))),
+        ];
+
+        let mut expendable_backups = collect_ids(strategy.expendable_backups(&mut backups));
+        expendable_backups.sort();
+
+        assert_eq!(expendable_backups, as_vector("ACD"));
+        assert_eq!(collect_ids(backups), as_vector("B"));
+    }
+}
@@ -0,0 +1,141 @@
+use super::{PruningStrategy, BackupFileMeta};
+use std::collections::HashMap;
+use time::Duration;
+use chrono::{DateTime, Utc};
+
+/// How long backups of a tier are kept, see `RespectTierTag`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TierRetention {
+    Forever,
+
+    /// Keep backups until they're older than this.
+    For(Duration),
+}
+
+/// Wraps another strategy and decides on the backups their uploader has assigned to a
+/// known `tier` (e.g. by a `backup-tier=monthly` object tag) by that tier's retention
+/// instead, e.g. keeping all `monthly` backups forever and `daily` ones for 7 days.
+/// Backups without a tier or with one not in `retentions` are left to the `inner`
+/// strategy.
+pub struct RespectTierTag {
+    inner: Box<dyn PruningStrategy>,
+    reference_time: DateTime<Utc>,
+    retentions: HashMap<String, TierRetention>,
+}
+
+impl RespectTierTag {
+
+    pub fn new(
+        inner: Box<dyn PruningStrategy>,
+        reference_time: DateTime<Utc>,
+        retentions: HashMap<String, TierRetention>,
+    ) -> RespectTierTag {
+        RespectTierTag {
+            inner,
+            reference_time,
+            retentions,
+        }
+    }
+
+    fn retention(&self, backup: &BackupFileMeta) -> Option<TierRetention> {
+        backup.tier.as_ref().and_then(|tier| self.retentions.get(tier)).copied()
+    }
+}
+
+impl PruningStrategy for RespectTierTag {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        let (tiered_backups, mut untiered_backups): (Vec<BackupFileMeta>, Vec<BackupFileMeta>) = backups
+            .drain(..)
+            .partition(|backup| self.retention(backup).is_some());
+        let mut expendable_backups = self.inner.expendable_backups(&mut untiered_backups);
+        let mut backups_to_keep = untiered_backups;
+
+        for backup in tiered_backups {
+            match self.retention(&backup) {
+                Some(TierRetention::For(duration)) if backup.is_older_than(duration, self.reference_time) => {
+                    expendable_backups.push(backup);
+                },
+                _ => backups_to_keep.push(backup),
+            }
+        }
+
+        backups_to_keep.sort_by_key(|backup| backup.date);
+        expendable_backups.sort_by_key(|backup| backup.date);
+        *backups = backups_to_keep;
+
+        expendable_backups
+    }
+
+    fn explain(&self, backup: &BackupFileMeta, backups: &[BackupFileMeta]) -> Option<String> {
+        let tier = backup.tier.as_deref().unwrap_or_default();
+
+        match self.retention(backup) {
+            Some(TierRetention::Forever) => Some(format!("It's of the tier {}, which is kept forever, so it's kept.", tier)),
+            Some(TierRetention::For(duration)) if backup.is_older_than(duration, self.reference_time) => {
+                Some(format!("It's of the tier {} and older than its retention ({} days), so it's expendable.", tier, duration.num_days()))
+            },
+            Some(TierRetention::For(duration)) => {
+                Some(format!("It's of the tier {} and within its retention ({} days), so it's kept.", tier, duration.num_days()))
+            },
+            None => self.inner.explain(backup, backups),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::KeepLastN;
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use chrono::offset::TimeZone;
+
+    fn build_meta_with_tier(id: &str, days_ago: i64, tier: Option<&str>) -> BackupFileMeta {
+        BackupFileMeta {
+            tier: tier.map(String::from),
+            ..build_meta(id, reference_time() - Duration::days(days_ago))
+        }
+    }
+
+    fn reference_time() -> DateTime<Utc> {
+        Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)
+    }
+
+    fn retentions() -> HashMap<String, TierRetention> {
+        vec![
+            (String::from("monthly"), TierRetention::Forever),
+            (String::from("daily"), TierRetention::For(Duration::days(7))),
+        ].into_iter().collect()
+    }
+
+    #[test]
+    fn test_expendable_backups() {
+        let strategy = RespectTierTag::new(Box::new(KeepLastN::new(1)), reference_time(), retentions());
+        let mut backups = vec![
+            build_meta_with_tier("A", 400, Some("monthly")),
+            build_meta_with_tier("B", 10, Some("daily")),
+            build_meta_with_tier("C", 9, None),
+            build_meta_with_tier("D", 8, Some("weekly")),
+            build_meta_with_tier("E", 5, Some("daily")),
+            build_meta_with_tier("F", 3, None),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // B is past the daily retention, while the inner strategy keeps only the newest
+        // of C, D (its tier is unknown) and F.
+        assert_eq!(collect_ids(expendable_backups), as_vector("BCD"));
+        assert_eq!(collect_ids(backups), as_vector("AEF"));
+    }
+
+    #[test]
+    fn test_expendable_backups_without_tiers() {
+        let strategy = RespectTierTag::new(Box::new(KeepLastN::new(1)), reference_time(), retentions());
+        let mut backups = vec![build_meta_with_tier("A", 2, None), build_meta_with_tier("B", 1, None)];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("A"));
+        assert_eq!(collect_ids(backups), as_vector("B"));
+    }
+}
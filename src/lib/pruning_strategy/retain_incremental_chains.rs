@@ -0,0 +1,114 @@
+use super::{PruningStrategy, BackupFileMeta, Matcher};
+use chrono::{DateTime, Utc};
+
+/// Lets the `inner` strategy decide on the full backups only, i.e. the ones `is_full`
+/// matches, and keeps each incremental backup exactly if the full backup its chain
+/// starts with is kept. That's the newest full backup up to the incremental's date.
+/// Incrementals that could no longer be applied to a kept full backup, e.g. the ones
+/// older than the oldest kept full backup, are expendable, as are those without any
+/// full backup before them.
+pub struct RetainIncrementalChains {
+    inner: Box<dyn PruningStrategy>,
+    is_full: Matcher,
+}
+
+impl RetainIncrementalChains {
+
+    pub fn new(inner: Box<dyn PruningStrategy>, is_full: Matcher) -> RetainIncrementalChains {
+        RetainIncrementalChains {
+            inner,
+            is_full,
+        }
+    }
+}
+
+impl PruningStrategy for RetainIncrementalChains {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        let (mut backups_to_keep, incremental_backups): (Vec<BackupFileMeta>, Vec<BackupFileMeta>) = backups
+            .drain(..)
+            .partition(|backup| self.is_full.matches(&backup.id));
+        let mut expendable_backups = self.inner.expendable_backups(&mut backups_to_keep);
+
+        // The start of each chain, along with whether its full backup is kept.
+        let mut chain_starts: Vec<(DateTime<Utc>, bool)> = backups_to_keep
+            .iter()
+            .map(|backup| (backup.date, true))
+            .chain(expendable_backups.iter().map(|backup| (backup.date, false)))
+            .collect();
+        chain_starts.sort_by_key(|(date, _)| *date);
+
+        for backup in incremental_backups {
+            let chain_start = chain_starts.partition_point(|(date, _)| *date <= backup.date);
+
+            if chain_start > 0 && chain_starts[chain_start - 1].1 {
+                backups_to_keep.push(backup);
+            }
+            else {
+                expendable_backups.push(backup);
+            }
+        }
+
+        backups_to_keep.sort_by_key(|backup| backup.date);
+        expendable_backups.sort_by_key(|backup| backup.date);
+        *backups = backups_to_keep;
+
+        expendable_backups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{KeepLastN, CustomStrategy};
+    use super::super::tests::{build_meta, collect_ids};
+    use chrono::offset::TimeZone;
+
+    /// Returns the backups with the given `ids`, taken a day after another.
+    fn daily_backups(ids: &[&str]) -> Vec<BackupFileMeta> {
+        ids.iter()
+            .enumerate()
+            .map(|(day, id)| build_meta(id, Utc.ymd(2014, 7, 1 + day as u32).and_hms(0, 0, 0)))
+            .collect()
+    }
+
+    fn is_full() -> Matcher {
+        Matcher::Prefix(String::from("full/"))
+    }
+
+    #[test]
+    fn test_expendable_backups() {
+        let strategy = RetainIncrementalChains::new(Box::new(KeepLastN::new(2)), is_full());
+        let mut backups = daily_backups(&[
+            "incr/0", "full/1", "incr/1a", "incr/1b", "full/2", "incr/2a", "full/3", "incr/3a", "incr/3b",
+        ]);
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // The incrementals before the first full backup and the ones of its chain are
+        // orphaned.
+        assert_eq!(collect_ids(expendable_backups), vec!["incr/0", "full/1", "incr/1a", "incr/1b"]);
+        assert_eq!(collect_ids(backups), vec!["full/2", "incr/2a", "full/3", "incr/3a", "incr/3b"]);
+    }
+
+    #[test]
+    fn test_expendable_backups_when_a_chain_in_between_is_dropped() {
+        let keep_all_but_the_second = CustomStrategy::new(Box::new(|backup, _| backup.id != "full/2"));
+        let strategy = RetainIncrementalChains::new(Box::new(keep_all_but_the_second), is_full());
+        let mut backups = daily_backups(&["full/1", "incr/1a", "full/2", "incr/2a", "incr/2b", "full/3", "incr/3a"]);
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), vec!["full/2", "incr/2a", "incr/2b"]);
+        assert_eq!(collect_ids(backups), vec!["full/1", "incr/1a", "full/3", "incr/3a"]);
+    }
+
+    #[test]
+    fn test_expendable_backups_without_full_backups() {
+        let strategy = RetainIncrementalChains::new(Box::new(KeepLastN::new(2)), is_full());
+        let mut backups = daily_backups(&["incr/1", "incr/2"]);
+
+        assert_eq!(strategy.expendable_backups(&mut backups).len(), 2);
+        assert!(backups.is_empty());
+    }
+}
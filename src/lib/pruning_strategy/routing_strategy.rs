@@ -0,0 +1,138 @@
+use super::{PruningStrategy, BackupFileMeta};
+
+/// Decides which backups a route of a `RoutingStrategy` applies to, based on their `id`.
+pub enum Matcher {
+
+    /// Matches ids starting with the given prefix, e.g. `daily/`.
+    Prefix(String),
+
+    /// Matches ids the given function returns `true` for, e.g. to match regular expressions.
+    Custom(Box<dyn Fn(&str) -> bool + Send>),
+}
+
+impl Matcher {
+
+    pub(crate) fn matches(&self, id: &str) -> bool {
+        match self {
+            Matcher::Prefix(prefix) => id.starts_with(prefix.as_str()),
+            Matcher::Custom(matches) => matches(id),
+        }
+    }
+}
+
+/// Applies different strategies to different sets of backups. Each backup is routed to
+/// the strategy of the first of the `routes` matching it, and each strategy decides on
+/// its backups independently of the others. Backups matching no route are kept.
+pub struct RoutingStrategy {
+    routes: Vec<(Matcher, Box<dyn PruningStrategy>)>,
+}
+
+impl RoutingStrategy {
+
+    pub fn new(routes: Vec<(Matcher, Box<dyn PruningStrategy>)>) -> RoutingStrategy {
+        RoutingStrategy {
+            routes,
+        }
+    }
+}
+
+impl PruningStrategy for RoutingStrategy {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        let mut partitions: Vec<Vec<BackupFileMeta>> = self.routes.iter().map(|_| vec![]).collect();
+        let mut unmatched_backups = vec![];
+
+        for backup in backups.drain(..) {
+            match self.routes.iter().position(|(matcher, _)| matcher.matches(&backup.id)) {
+                Some(index) => partitions[index].push(backup),
+                None => unmatched_backups.push(backup),
+            }
+        }
+
+        let mut expendable_backups = vec![];
+
+        for ((_, strategy), mut partition) in self.routes.iter().zip(partitions) {
+            expendable_backups.append(&mut strategy.expendable_backups(&mut partition));
+            backups.append(&mut partition);
+        }
+
+        backups.append(&mut unmatched_backups);
+
+        expendable_backups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{KeepLastN, OlderThan};
+    use super::super::tests::{build_meta, collect_ids};
+    use time::Duration;
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    fn backups() -> Vec<BackupFileMeta> {
+        vec![
+            build_meta("daily/1", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("daily/2", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+            build_meta("daily/3", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0)),
+            build_meta("monthly/1", Utc.ymd(2014, 5, 1).and_hms(0, 0, 0)),
+            build_meta("monthly/2", Utc.ymd(2014, 6, 1).and_hms(0, 0, 0)),
+            build_meta("monthly/3", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("other/1", Utc.ymd(2014, 1, 1).and_hms(0, 0, 0)),
+        ]
+    }
+
+    fn sorted(mut ids: Vec<String>) -> Vec<String> {
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn test_expendable_backups() {
+        let strategy = RoutingStrategy::new(vec![
+            (
+                Matcher::Prefix(String::from("daily/")),
+                Box::new(OlderThan::new(Duration::days(1), Utc.ymd(2014, 7, 3).and_hms(0, 0, 0))),
+            ),
+            (
+                Matcher::Prefix(String::from("monthly/")),
+                Box::new(KeepLastN::new(2)),
+            ),
+        ]);
+        let mut backups = backups();
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // KeepLastN only considers the monthly backups, so it keeps two of them although
+        // there are newer daily ones.
+        assert_eq!(sorted(collect_ids(expendable_backups)), vec!["daily/1", "monthly/1"]);
+        assert_eq!(sorted(collect_ids(backups)), vec!["daily/2", "daily/3", "monthly/2", "monthly/3", "other/1"]);
+    }
+
+    #[test]
+    fn test_expendable_backups_uses_the_first_matching_route() {
+        let strategy = RoutingStrategy::new(vec![
+            (Matcher::Custom(Box::new(|id| id.ends_with("/1"))), Box::new(KeepLastN::new(1))),
+            (Matcher::Prefix(String::new()), Box::new(KeepLastN::new(100))),
+        ]);
+        let mut backups = backups();
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // Of the backups ending with "/1", only the newest one is kept.
+        assert_eq!(sorted(collect_ids(expendable_backups)), vec!["monthly/1", "other/1"]);
+        assert_eq!(backups.len(), 5);
+    }
+
+    #[test]
+    fn test_expendable_backups_with_no_routes() {
+        let strategy = RoutingStrategy::new(vec![]);
+        let mut backups = backups();
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert!(expendable_backups.is_empty());
+        assert_eq!(backups.len(), 7);
+    }
+}
@@ -0,0 +1,231 @@
+use super::{PruningStrategy, BackupFileMeta, StrategyConfigError};
+use std::collections::HashSet;
+use time::Duration;
+use chrono::{DateTime, Utc};
+
+/// Keeps as few of the backups within `window` before `reference_time` as possible,
+/// while keeping the gaps between consecutive ones within `max_gap`, e.g. to meet a
+/// Recovery Point Objective of one backup per 24 hours for the last 90 days. The oldest
+/// and the newest backup within the window are always kept. Gaps between stored
+/// backups, that already exceed `max_gap`, can't be closed, so the backups on both
+/// sides of them are kept. Older backups are left to the `inner` strategy.
+pub struct SatisfyRpo {
+    inner: Box<dyn PruningStrategy>,
+    reference_time: DateTime<Utc>,
+    window: Duration,
+    max_gap: Duration,
+}
+
+impl SatisfyRpo {
+
+    /// Panics, if `max_gap` isn't positive, see `try_new`.
+    pub fn new(inner: Box<dyn PruningStrategy>, reference_time: DateTime<Utc>, window: Duration, max_gap: Duration) -> SatisfyRpo {
+        SatisfyRpo::try_new(inner, reference_time, window, max_gap).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Fails, if `max_gap` isn't positive, as no backups could be far enough apart then.
+    pub fn try_new(
+        inner: Box<dyn PruningStrategy>,
+        reference_time: DateTime<Utc>,
+        window: Duration,
+        max_gap: Duration,
+    ) -> Result<SatisfyRpo, StrategyConfigError> {
+        if max_gap <= Duration::zero() {
+            return Err(StrategyConfigError::Zero("max_gap"));
+        }
+
+        Ok(SatisfyRpo {
+            inner,
+            reference_time,
+            window,
+            max_gap,
+        })
+    }
+
+    fn within_window(&self, backup: &BackupFileMeta) -> bool {
+        !backup.is_older_than(self.window, self.reference_time)
+    }
+
+    /// Returns the ids of the backups to keep among the `recent_backups`, which need to
+    /// be in chronological order. Keeps the oldest one, and then each one, whose
+    /// successor would be too far from the last kept one. Jumping as far as possible
+    /// each time keeps the fewest backups.
+    fn needed_ids(&self, recent_backups: &[&BackupFileMeta]) -> HashSet<String> {
+        let mut needed_ids = HashSet::new();
+        let mut last_kept: Option<&BackupFileMeta> = None;
+
+        for (index, backup) in recent_backups.iter().enumerate() {
+            let needed = match (last_kept, recent_backups.get(index + 1)) {
+                (None, _) | (_, None) => true,
+                (Some(last_kept), Some(next)) => next.date - last_kept.date > self.max_gap,
+            };
+
+            if needed {
+                needed_ids.insert(backup.id.clone());
+                last_kept = Some(backup);
+            }
+        }
+
+        needed_ids
+    }
+}
+
+impl PruningStrategy for SatisfyRpo {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        backups.sort_by_key(|backup| backup.date);
+
+        let (recent_backups, mut older_backups): (Vec<BackupFileMeta>, Vec<BackupFileMeta>) = backups
+            .drain(..)
+            .partition(|backup| self.within_window(backup));
+        let mut expendable_backups = self.inner.expendable_backups(&mut older_backups);
+        let mut backups_to_keep = older_backups;
+
+        let needed_ids = self.needed_ids(&recent_backups.iter().collect::<Vec<_>>());
+        for backup in recent_backups {
+            if needed_ids.contains(&backup.id) {
+                backups_to_keep.push(backup);
+            }
+            else {
+                expendable_backups.push(backup);
+            }
+        }
+
+        backups_to_keep.sort_by_key(|backup| backup.date);
+        expendable_backups.sort_by_key(|backup| backup.date);
+        *backups = backups_to_keep;
+
+        expendable_backups
+    }
+
+    fn explain(&self, backup: &BackupFileMeta, backups: &[BackupFileMeta]) -> Option<String> {
+        if !self.within_window(backup) {
+            return self.inner.explain(backup, backups);
+        }
+
+        let mut recent_backups: Vec<&BackupFileMeta> = backups.iter().filter(|other| self.within_window(other)).collect();
+        recent_backups.sort_by_key(|other| other.date);
+
+        if self.needed_ids(&recent_backups).contains(&backup.id) {
+            Some(format!(
+                "It's needed to keep the gaps between the backups of the last {} days within {} hours, so it's kept.",
+                self.window.num_days(),
+                self.max_gap.num_hours(),
+            ))
+        }
+        else {
+            Some(format!(
+                "It's within the last {} days, but not needed to keep the gaps between backups within {} hours, so it's expendable.",
+                self.window.num_days(),
+                self.max_gap.num_hours(),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::KeepLastN;
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use chrono::offset::TimeZone;
+
+    fn reference_time() -> DateTime<Utc> {
+        Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)
+    }
+
+    fn build_meta_hours_ago(id: &str, hours_ago: i64) -> BackupFileMeta {
+        build_meta(id, reference_time() - Duration::hours(hours_ago))
+    }
+
+    /// Returns the gaps between consecutive `backups`, which need to be in chronological
+    /// order.
+    fn gaps(backups: &[BackupFileMeta]) -> Vec<Duration> {
+        backups.windows(2).map(|pair| pair[1].date - pair[0].date).collect()
+    }
+
+    #[test]
+    fn test_expendable_backups() {
+        let strategy = SatisfyRpo::new(Box::new(KeepLastN::new(0)), reference_time(), Duration::days(90), Duration::hours(24));
+
+        // One backup every 6 hours for the last 3 days, of which 4 are enough.
+        let mut backups: Vec<BackupFileMeta> = (0..=12)
+            .rev()
+            .map(|index| build_meta_hours_ago(&index.to_string(), index * 6))
+            .collect();
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(backups.clone()), vec!["12", "8", "4", "0"]);
+        assert_eq!(expendable_backups.len(), 9);
+        assert!(gaps(&backups).iter().all(|gap| *gap <= Duration::hours(24)));
+    }
+
+    #[test]
+    fn test_expendable_backups_with_gaps_and_older_backups() {
+        let strategy = SatisfyRpo::new(Box::new(KeepLastN::new(0)), reference_time(), Duration::days(10), Duration::hours(24));
+        let mut backups = vec![
+            build_meta_hours_ago("A", 24 * 20),
+            build_meta_hours_ago("B", 24 * 9),
+            build_meta_hours_ago("C", 24 * 8 + 12),
+            build_meta_hours_ago("D", 24 * 8),
+
+            // Nothing can close the 5 days from D to E.
+            build_meta_hours_ago("E", 24 * 3),
+            build_meta_hours_ago("F", 24 * 2 + 12),
+            build_meta_hours_ago("G", 24 * 2),
+            build_meta_hours_ago("H", 1),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        // A is left to the inner strategy, G is needed to reach H.
+        assert_eq!(collect_ids(expendable_backups), as_vector("ACF"));
+        assert_eq!(collect_ids(backups), as_vector("BDEGH"));
+    }
+
+    #[test]
+    fn test_expendable_backups_never_exceed_max_gap() {
+        let max_gap = Duration::hours(24);
+        let strategy = SatisfyRpo::new(Box::new(KeepLastN::new(0)), reference_time(), Duration::days(90), max_gap);
+
+        // Backups up to 20 hours apart, at pseudo-random intervals.
+        let mut hours_ago = 24 * 90;
+        let mut seed: i64 = 7;
+        let mut backups = vec![];
+        while hours_ago > 0 {
+            backups.push(build_meta_hours_ago(&hours_ago.to_string(), hours_ago));
+            seed = (seed * 1_103_515_245 + 12_345) % 2_147_483_648;
+            hours_ago -= 1 + seed % 20;
+        }
+        let span = backups.last().unwrap().date - backups[0].date;
+
+        strategy.expendable_backups(&mut backups);
+
+        assert!(gaps(&backups).iter().all(|gap| *gap <= max_gap));
+
+        // Any kept backup but the newest is followed by the next but one more than
+        // max_gap later, or the one in between wouldn't have been needed.
+        assert!(backups.windows(3).all(|triple| triple[2].date - triple[0].date > max_gap));
+        assert!(backups.len() as i64 <= 2 * (span.num_hours() / max_gap.num_hours()) + 2);
+    }
+
+    #[test]
+    fn test_explain() {
+        let strategy = SatisfyRpo::new(Box::new(KeepLastN::new(0)), reference_time(), Duration::days(10), Duration::hours(24));
+        let backups = vec![build_meta_hours_ago("A", 48), build_meta_hours_ago("B", 30), build_meta_hours_ago("C", 12)];
+
+        assert_eq!(
+            strategy.explain(&backups[1], &backups),
+            Some(String::from("It's needed to keep the gaps between the backups of the last 10 days within 24 hours, so it's kept.")),
+        );
+        assert!(strategy.explain(&backups[2], &backups).unwrap().ends_with("so it's kept."));
+    }
+
+    #[test]
+    fn test_try_new() {
+        let strategy = SatisfyRpo::try_new(Box::new(KeepLastN::new(0)), reference_time(), Duration::days(90), Duration::zero());
+
+        assert_eq!(strategy.err(), Some(StrategyConfigError::Zero("max_gap")));
+    }
+}
@@ -0,0 +1,103 @@
+use super::{PruningStrategy, BackupFileMeta};
+
+/// Wraps another strategy and hides all backups stored in one of the given
+/// `storage_classes` from it, so they are always kept. Useful to leave backups archived
+/// in e.g. S3 Glacier untouched.
+pub struct SkipStorageClass {
+    inner: Box<dyn PruningStrategy>,
+    storage_classes: Vec<String>,
+}
+
+impl SkipStorageClass {
+
+    pub fn new(inner: Box<dyn PruningStrategy>, storage_classes: Vec<String>) -> SkipStorageClass {
+        SkipStorageClass {
+            inner,
+            storage_classes,
+        }
+    }
+
+    fn skipped(&self, backup: &BackupFileMeta) -> bool {
+        match &backup.storage_class {
+            Some(storage_class) => self.storage_classes.contains(storage_class),
+            None => false,
+        }
+    }
+}
+
+impl PruningStrategy for SkipStorageClass {
+
+    fn expendable_backups(&self, backups: &mut Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        let (mut skipped_backups, mut considered_backups): (Vec<BackupFileMeta>, Vec<BackupFileMeta>) = backups
+            .drain(..)
+            .partition(|backup| self.skipped(backup));
+
+        let expendable_backups = self.inner.expendable_backups(&mut considered_backups);
+
+        backups.append(&mut considered_backups);
+        backups.append(&mut skipped_backups);
+
+        expendable_backups
+    }
+
+    fn is_streaming_safe(&self) -> bool {
+        self.inner.is_streaming_safe()
+    }
+
+    fn explain(&self, backup: &BackupFileMeta, backups: &[BackupFileMeta]) -> Option<String> {
+        match &backup.storage_class {
+            Some(storage_class) if self.skipped(backup) => Some(format!("It's stored in {}, which is skipped, so it's kept.", storage_class)),
+            _ => self.inner.explain(backup, backups),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::KeepLastN;
+    use super::super::tests::{build_meta, collect_ids, as_vector};
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    fn build_meta_with_storage_class(id: &str, day: u32, storage_class: Option<&str>) -> BackupFileMeta {
+        BackupFileMeta {
+            storage_class: storage_class.map(String::from),
+            ..build_meta(id, Utc.ymd(2014, 7, day).and_hms(0, 0, 0))
+        }
+    }
+
+    #[test]
+    fn test_expendable_backups() {
+        let strategy = SkipStorageClass::new(
+            Box::new(KeepLastN::new(1)),
+            vec![String::from("GLACIER"), String::from("DEEP_ARCHIVE")],
+        );
+        let mut backups = vec![
+            build_meta_with_storage_class("A", 1, Some("DEEP_ARCHIVE")),
+            build_meta_with_storage_class("B", 2, Some("STANDARD")),
+            build_meta_with_storage_class("C", 3, Some("GLACIER")),
+            build_meta_with_storage_class("D", 4, None),
+            build_meta_with_storage_class("E", 5, Some("STANDARD_IA")),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("BD"));
+        assert_eq!(collect_ids(backups), as_vector("EAC"));
+    }
+
+    #[test]
+    fn test_expendable_backups_with_no_storage_classes_given() {
+        let strategy = SkipStorageClass::new(Box::new(KeepLastN::new(1)), vec![]);
+        let mut backups = vec![
+            build_meta_with_storage_class("A", 1, Some("GLACIER")),
+            build_meta_with_storage_class("B", 2, Some("STANDARD")),
+        ];
+
+        let expendable_backups = strategy.expendable_backups(&mut backups);
+
+        assert_eq!(collect_ids(expendable_backups), as_vector("A"));
+        assert_eq!(collect_ids(backups), as_vector("B"));
+    }
+}
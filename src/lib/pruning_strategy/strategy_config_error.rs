@@ -0,0 +1,99 @@
+use std::error::Error;
+use std::fmt;
+use time::Duration;
+use chrono::{DateTime, Utc};
+
+/// Contradictory or invalid parameters given to a strategy's `try_new`.
+#[derive(Debug, PartialEq)]
+pub enum StrategyConfigError {
+
+    /// Backups would have to be kept for longer than there are monthly backups to keep.
+    KeepAllWithinExceedsOnePerMonthWithin {
+        keep_all_within: Duration,
+        one_per_month_within: Duration,
+    },
+
+    /// A date range ends before it starts.
+    RangeEndsBeforeItStarts {
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    },
+
+    /// The parameter with the given name is zero, but needs to be positive.
+    Zero(&'static str),
+
+    /// No strategies have been given to combine.
+    NoStrategies,
+
+    /// The given day is not a day of a month, i.e. not within 1 to 31.
+    InvalidDayOfMonth(u8),
+
+    /// The given month is not within 1 to 12.
+    InvalidMonth(u8),
+
+    /// The given base of exponentially growing periods is less than 2, so they wouldn't
+    /// grow.
+    BaseTooSmall(u32),
+
+    /// The given percentage is not within 0 to 100.
+    InvalidPercentage(u8),
+}
+
+/// Describes `duration` in the unit options are usually given in.
+fn describe(duration: &Duration) -> String {
+    if *duration == Duration::days(duration.num_days()) {
+        format!("{} days", duration.num_days())
+    }
+    else {
+        format!("{} seconds", duration.num_seconds())
+    }
+}
+
+impl fmt::Display for StrategyConfigError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StrategyConfigError::KeepAllWithinExceedsOnePerMonthWithin { keep_all_within, one_per_month_within } => write!(
+                f,
+                "keep_all_within ({}) must not exceed one_per_month_within ({}).",
+                describe(keep_all_within),
+                describe(one_per_month_within),
+            ),
+            StrategyConfigError::RangeEndsBeforeItStarts { start, end } => write!(
+                f,
+                "The range {}..{} ends before it starts.",
+                start.to_rfc3339(),
+                end.to_rfc3339(),
+            ),
+            StrategyConfigError::Zero(parameter) => write!(f, "{} must be greater than 0.", parameter),
+            StrategyConfigError::NoStrategies => write!(f, "At least one strategy is needed."),
+            StrategyConfigError::InvalidDayOfMonth(day) => write!(f, "{} is not a day of a month.", day),
+            StrategyConfigError::InvalidMonth(month) => write!(f, "{} is not a month.", month),
+            StrategyConfigError::BaseTooSmall(base) => write!(f, "The base must be at least 2, but is {}.", base),
+            StrategyConfigError::InvalidPercentage(percentage) => write!(f, "{}% is not a percentage from 0 to 100.", percentage),
+        }
+    }
+}
+
+impl Error for StrategyConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::offset::TimeZone;
+
+    #[test]
+    fn test_display() {
+        let error = StrategyConfigError::KeepAllWithinExceedsOnePerMonthWithin {
+            keep_all_within: Duration::days(30),
+            one_per_month_within: Duration::hours(36),
+        };
+        assert_eq!(error.to_string(), "keep_all_within (30 days) must not exceed one_per_month_within (129600 seconds).");
+
+        let error = StrategyConfigError::RangeEndsBeforeItStarts {
+            start: Utc.ymd(2014, 3, 31).and_hms(0, 0, 0),
+            end: Utc.ymd(2014, 3, 1).and_hms(0, 0, 0),
+        };
+        assert_eq!(error.to_string(), "The range 2014-03-31T00:00:00+00:00..2014-03-01T00:00:00+00:00 ends before it starts.");
+    }
+}
@@ -0,0 +1,95 @@
+//! Builds receipts of deletions, that can be stored apart from the backups (e.g. in an
+//! audit bucket) to tell later which backups have been deleted when and why.
+use super::storage_client::DeletionReport;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// A deleted backup, as listed in a receipt.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct DeletedBackup {
+    pub id: String,
+
+    /// Size in bytes.
+    pub size: u64,
+}
+
+/// Record of the backups deleted by a single run.
+#[derive(Debug, Serialize)]
+pub struct Receipt {
+    pub created_at: DateTime<Utc>,
+
+    /// Description of the strategy that considered the backups expendable.
+    pub strategy: String,
+    pub deleted: Vec<DeletedBackup>,
+}
+
+impl Receipt {
+
+    pub fn new(deletion_report: &DeletionReport, strategy: String, created_at: DateTime<Utc>) -> Receipt {
+        Receipt {
+            created_at,
+            strategy,
+            deleted: deletion_report.deleted
+                .iter()
+                .map(|backup| DeletedBackup {
+                    id: backup.id.clone(),
+                    size: backup.size,
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the key to store the receipt under, i.e. `prefix` followed by the time
+    /// it has been created at, e.g. `receipts/2014-07-01T00-00-00.000000Z.json`. That's
+    /// precise to the microsecond, so runs finishing within the same second (e.g. of
+    /// several regions) don't overwrite each other's receipts.
+    pub fn key(&self, prefix: &str) -> String {
+        format!("{}{}.json", prefix, self.created_at.format("%Y-%m-%dT%H-%M-%S%.6fZ"))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pruning_strategy::tests::build_meta;
+    use chrono::offset::TimeZone;
+
+    fn receipt() -> Receipt {
+        let deletion_report = DeletionReport {
+            deleted: vec![
+                build_meta("backups/2014-06-01.dump", Utc.ymd(2014, 6, 1).and_hms(0, 0, 0)),
+                build_meta("backups/2014-06-02.dump", Utc.ymd(2014, 6, 2).and_hms(0, 0, 0)),
+            ],
+            failed: vec![(build_meta("backups/2014-06-03.dump", Utc.ymd(2014, 6, 3).and_hms(0, 0, 0)), String::from("AccessDenied"))],
+            ..Default::default()
+        };
+
+        Receipt::new(&deletion_report, String::from("keep_all_within=7d"), Utc.ymd(2014, 7, 1).and_hms(3, 4, 5))
+    }
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!(
+            receipt().to_json(),
+            concat!(
+                r#"{"created_at":"2014-07-01T03:04:05Z","strategy":"keep_all_within=7d","deleted":["#,
+                r#"{"id":"backups/2014-06-01.dump","size":0},{"id":"backups/2014-06-02.dump","size":0}]}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_key() {
+        assert_eq!(receipt().key("receipts/"), "receipts/2014-07-01T03-04-05.000000Z.json");
+
+        let receipt = Receipt {
+            created_at: Utc.ymd(2014, 7, 1).and_hms_micro(3, 4, 5, 678),
+            ..receipt()
+        };
+        assert_eq!(receipt.key("receipts/"), "receipts/2014-07-01T03-04-05.000678Z.json");
+    }
+}
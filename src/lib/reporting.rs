@@ -0,0 +1,226 @@
+//! Helpers for analyzing a set of backups without changing any of them.
+use super::BackupFileMeta;
+use super::date_time_utilities;
+use std::collections::BTreeMap;
+use std::fmt;
+use time::Duration;
+use chrono::{DateTime, Utc, Datelike};
+
+/// A calendar period backups can be grouped by. Weeks start on Monday.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Period {
+
+    /// Returns the beginning of the period `date` belongs to.
+    pub fn beginning(self, date: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Period::Day => date_time_utilities::beginning_of_day(date),
+            Period::Week => date_time_utilities::beginning_of_week(date),
+            Period::Month => date_time_utilities::beginning_of_month(date),
+            Period::Year => date_time_utilities::beginning_of_year(date),
+        }
+    }
+}
+
+/// Identifies a single period, such as July 2014.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PeriodKey {
+    pub period: Period,
+    pub start: DateTime<Utc>,
+}
+
+impl fmt::Display for PeriodKey {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.period {
+            Period::Day => write!(f, "{}", self.start.format("%Y-%m-%d")),
+            Period::Week => {
+                let week = self.start.iso_week();
+                write!(f, "{}-W{:02}", week.year(), week.week())
+            },
+            Period::Month => write!(f, "{}", self.start.format("%Y-%m")),
+            Period::Year => write!(f, "{}", self.start.format("%Y")),
+        }
+    }
+}
+
+/// Groups `backups` by the `period` they were created in. Only periods containing
+/// at least one backup are included.
+pub fn group_by_period(backups: &[BackupFileMeta], period: Period) -> BTreeMap<PeriodKey, Vec<&BackupFileMeta>> {
+    let mut groups: BTreeMap<PeriodKey, Vec<&BackupFileMeta>> = BTreeMap::new();
+
+    for backup in backups {
+        let key = PeriodKey {
+            period,
+            start: period.beginning(backup.date),
+        };
+        groups.entry(key).or_default().push(backup);
+    }
+
+    groups
+}
+
+/// How much of the timeline a set of backups covers, e.g. to check the kept ones leave
+/// no multi-month gaps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coverage {
+
+    /// Dates of the oldest and newest backup, or `None`, if there are no backups.
+    pub oldest: Option<DateTime<Utc>>,
+    pub newest: Option<DateTime<Utc>>,
+
+    /// Longest time between two consecutive backups, or zero, if there are fewer than
+    /// two backups.
+    pub max_gap: Duration,
+
+    /// Number of backups per month from the oldest to the newest one. Months without
+    /// any backups are included with 0, so gaps stand out.
+    pub per_month_counts: BTreeMap<PeriodKey, usize>,
+}
+
+/// Returns the coverage of `backups`, which may be given in any order.
+pub fn coverage(backups: &[BackupFileMeta]) -> Coverage {
+    let mut dates: Vec<DateTime<Utc>> = backups.iter().map(|backup| backup.date).collect();
+    dates.sort();
+
+    let max_gap = dates
+        .windows(2)
+        .map(|pair| pair[1] - pair[0])
+        .max()
+        .unwrap_or_else(Duration::zero);
+
+    let mut per_month_counts = BTreeMap::new();
+    if let (Some(oldest), Some(newest)) = (dates.first(), dates.last()) {
+        let mut month = date_time_utilities::beginning_of_month(*oldest);
+        while month <= *newest {
+            per_month_counts.insert(PeriodKey { period: Period::Month, start: month }, 0);
+            month = date_time_utilities::beginning_of_next_month(month);
+        }
+    }
+    for (key, backups) in group_by_period(backups, Period::Month) {
+        per_month_counts.insert(key, backups.len());
+    }
+
+    Coverage {
+        oldest: dates.first().copied(),
+        newest: dates.last().copied(),
+        max_gap,
+        per_month_counts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pruning_strategy::tests::build_meta;
+    use chrono::offset::TimeZone;
+
+    fn group_ids(backups: &[BackupFileMeta], period: Period) -> Vec<(String, String)> {
+        group_by_period(backups, period)
+            .into_iter()
+            .map(|(key, backups)| {
+                let ids: String = backups.iter().map(|backup| backup.id.as_str()).collect();
+                (key.to_string(), ids)
+            })
+            .collect()
+    }
+
+    fn groups(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+        expected.iter().map(|(key, ids)| (key.to_string(), ids.to_string())).collect()
+    }
+
+    fn backups() -> Vec<BackupFileMeta> {
+        vec![
+            build_meta("A", Utc.ymd(2014, 11, 30).and_hms(23, 59, 59)), // Sunday
+            build_meta("B", Utc.ymd(2014, 12, 1).and_hms(0, 0, 0)), // Monday
+            build_meta("C", Utc.ymd(2014, 12, 1).and_hms(18, 0, 0)),
+            build_meta("D", Utc.ymd(2014, 12, 31).and_hms(23, 59, 59)), // Wednesday
+            build_meta("E", Utc.ymd(2015, 1, 1).and_hms(0, 0, 0)), // Thursday
+        ]
+    }
+
+    #[test]
+    fn test_group_by_period_by_day() {
+        assert_eq!(group_ids(&backups(), Period::Day), groups(&[
+            ("2014-11-30", "A"),
+            ("2014-12-01", "BC"),
+            ("2014-12-31", "D"),
+            ("2015-01-01", "E"),
+        ]));
+    }
+
+    #[test]
+    fn test_group_by_period_by_week() {
+        assert_eq!(group_ids(&backups(), Period::Week), groups(&[
+            ("2014-W48", "A"),
+            ("2014-W49", "BC"),
+            ("2015-W01", "DE"),
+        ]));
+    }
+
+    #[test]
+    fn test_group_by_period_by_month() {
+        assert_eq!(group_ids(&backups(), Period::Month), groups(&[
+            ("2014-11", "A"),
+            ("2014-12", "BCD"),
+            ("2015-01", "E"),
+        ]));
+    }
+
+    #[test]
+    fn test_group_by_period_by_year() {
+        assert_eq!(group_ids(&backups(), Period::Year), groups(&[
+            ("2014", "ABCD"),
+            ("2015", "E"),
+        ]));
+    }
+
+    #[test]
+    fn test_group_by_period_with_no_backups_given() {
+        assert!(group_by_period(&[], Period::Month).is_empty());
+    }
+
+    #[test]
+    fn test_coverage() {
+        let backups = vec![
+            build_meta("C", Utc.ymd(2014, 5, 20).and_hms(0, 0, 0)),
+            build_meta("A", Utc.ymd(2014, 1, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 1, 31).and_hms(0, 0, 0)),
+            build_meta("D", Utc.ymd(2014, 5, 21).and_hms(12, 0, 0)),
+        ];
+
+        let coverage = coverage(&backups);
+
+        assert_eq!(coverage.oldest, Some(Utc.ymd(2014, 1, 1).and_hms(0, 0, 0)));
+        assert_eq!(coverage.newest, Some(Utc.ymd(2014, 5, 21).and_hms(12, 0, 0)));
+
+        // Nothing has been kept from February to mid May.
+        assert_eq!(coverage.max_gap, Duration::days(109));
+        let counts: Vec<(String, usize)> = coverage.per_month_counts
+            .iter()
+            .map(|(key, count)| (key.to_string(), *count))
+            .collect();
+        assert_eq!(counts, vec![
+            (String::from("2014-01"), 2),
+            (String::from("2014-02"), 0),
+            (String::from("2014-03"), 0),
+            (String::from("2014-04"), 0),
+            (String::from("2014-05"), 2),
+        ]);
+    }
+
+    #[test]
+    fn test_coverage_of_few_backups() {
+        let empty = coverage(&[]);
+        assert_eq!((empty.oldest, empty.max_gap, empty.per_month_counts.len()), (None, Duration::zero(), 0));
+
+        let single = coverage(&[build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0))]);
+        assert_eq!((single.newest, single.max_gap, single.per_month_counts.len()), (Some(Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)), Duration::zero(), 1));
+    }
+}
@@ -2,17 +2,74 @@
 //! client implements the `StorageClient` trait, so they can all be used for
 //! pruning in a consistent manner.
 mod aws_s3;
+mod in_memory;
+mod local_filesystem;
+#[cfg(feature = "opendal")]
+mod open_dal;
+mod presigned_url;
+mod storage_url;
+mod deletion_report;
+mod storage_error;
 
+use std::iter;
+use chrono::{DateTime, Utc};
 use super::BackupFileMeta;
-pub use aws_s3::AwsS3;
+pub use aws_s3::{AwsS3, ApiCallEstimate, IntegrityFailure};
+pub use in_memory::InMemory;
+pub use local_filesystem::LocalFilesystem;
+#[cfg(feature = "opendal")]
+pub use open_dal::OpenDal;
+pub use presigned_url::PresignedUrl;
+pub use storage_url::{StorageUrl, from_url};
+pub use deletion_report::{DeletionReport, DeletionCounts};
+pub use storage_error::StorageError;
 
-/// Methods required to use a client for pruning.
-pub trait StorageClient {
+/// Methods required to use a client for pruning. Clients need to be `Send`, so
+/// several of them can be pruned concurrently.
+pub trait StorageClient: Send {
 
     /// Returns a list of all stored backups.
-    fn stored_backups(&self) -> Vec<BackupFileMeta>;
+    fn stored_backups(&self) -> Result<Vec<BackupFileMeta>, StorageError>;
 
-    /// Deletes all given `backups`. Returns the number of successfully deleted
-    /// objects.
-    fn delete_backups(&self, backups: Vec<BackupFileMeta>) -> usize;
+    /// Yields all stored backups, fetching them from the host as they are needed if
+    /// the client supports it. Useful to process large numbers of backups without
+    /// holding all of them in memory. Lists all of them at once by default.
+    fn stored_backups_iter(&self) -> Box<dyn Iterator<Item = Result<BackupFileMeta, StorageError>> + '_> {
+        match self.stored_backups() {
+            Ok(backups) => Box::new(backups.into_iter().map(Ok)),
+            Err(error) => Box::new(iter::once(Err(error))),
+        }
+    }
+
+    /// Returns the stored backups as rewritten by `transform`, e.g. to take their date
+    /// from their name or to normalize their ids, dropping the ones it returns `None` for.
+    /// The result is sorted by date again, as rewritten dates may change the order.
+    fn stored_backups_with(
+        &self,
+        transform: &mut dyn FnMut(BackupFileMeta) -> Option<BackupFileMeta>,
+    ) -> Result<Vec<BackupFileMeta>, StorageError> {
+        let mut backups: Vec<BackupFileMeta> = self.stored_backups()?.into_iter().filter_map(transform).collect();
+        backups.sort_by_key(|backup| backup.date);
+
+        Ok(backups)
+    }
+
+    /// Returns the stored backups dated within `start..=end`. Filters all stored
+    /// backups by default, clients able to skip the others while listing should do so.
+    fn backups_in_range(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<BackupFileMeta>, StorageError> {
+        Ok(self.stored_backups()?
+            .into_iter()
+            .filter(|backup| start <= backup.date && backup.date <= end)
+            .collect())
+    }
+
+    /// Deletes all given `backups`. Returns a report on which of them have been
+    /// deleted successfully.
+    fn delete_backups(&self, backups: Vec<BackupFileMeta>) -> DeletionReport;
+
+    /// Deletes all given `backups` like `delete_backups`, but only tells how many of
+    /// them have been deleted. Clients may save work by not reporting which ones.
+    fn delete_backups_counting(&self, backups: Vec<BackupFileMeta>) -> DeletionCounts {
+        DeletionCounts::from(&self.delete_backups(backups))
+    }
 }
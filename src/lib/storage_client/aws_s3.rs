@@ -1,21 +1,55 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::str::FromStr;
-use rusoto_core::Region as AWSRegion;
-use chrono::{DateTime, Utc};
-use rusoto_s3::{S3, S3Client};
-use super::{StorageClient, BackupFileMeta};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::vec;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use rusoto_core::{Region as AWSRegion, RusotoError, DefaultCredentialsProvider, HttpClient, ProvideAwsCredentials};
+use chrono::{DateTime, NaiveDate, Utc};
+use rusoto_s3::{S3, S3Client, ListObjectsV2Error, GetObjectError, HeadObjectError};
+use super::{StorageClient, BackupFileMeta, DeletionReport, DeletionCounts, StorageError};
 
 /// A client for AWS S3.
 ///
-/// _NOTE_ Due to limitations of the AWS S3 API, cleaning with this client only works as expected
-/// if your directory contains up to 1000 backups. Otherwise you might have to run the cleaner
-/// several times in a row or clean manually so you're under 1000.
+/// Objects archived in S3 Glacier or Glacier Deep Archive can be deleted without
+/// restoring them first. Note, that AWS charges for deleting them before their minimum
+/// storage duration has passed, though.
+///
+/// If backups are accompanied by a sidecar object marking their run successful, see
+/// `with_success_sidecar`. If their uploader decides how long to keep them, see
+/// `with_retain_until_metadata`, and which retention tier they belong to, see
+/// `with_tier_tag`.
+///
+/// If each backup comes with companion objects (e.g. a checksum), that should be deleted
+/// along with it, see `with_companions`.
+///
+/// Instead of deleting backups, they may be tagged for a separate process to delete
+/// them, see `with_tag_instead_of_delete`. To stop deleting in time, see
+/// `with_time_budget`.
+///
+/// In versioned buckets, each version of an object may be listed and deleted as a
+/// backup of its own, see `with_versions`.
+///
+/// Listing is paginated and deletion is split into batches, as the AWS S3 API handles at
+/// most 1000 objects per request.
 ///
 /// # Requirements
 ///
-/// This implementation uses the access key stored in the environment variable `AWS_ACCESS_KEY_ID`
-/// and the secret access key in `AWS_SECRET_ACCESS_KEY`. The respective AWS user needs to be
-/// allowed to list all elements in the bucket, as well as delete objects from the backups
+/// This implementation takes credentials from the first of these sources providing them:
+///
+/// 1. The environment variables `AWS_ACCESS_KEY_ID` and `AWS_SECRET_ACCESS_KEY` (and
+///    `AWS_SESSION_TOKEN`, if the credentials are temporary).
+/// 2. The profile `AWS_PROFILE` (or `default`) of `~/.aws/credentials`.
+/// 3. The task role of an ECS container.
+/// 4. The instance profile of an EC2 instance, via the instance metadata service.
+///
+/// So within AWS, no credentials need to be configured. Others may be given using
+/// `with_credentials_provider`. The respective AWS user or role needs to be allowed to
+/// list all elements in the bucket, as well as delete objects from the backups
 /// directory.
 ///
 /// Assuming a bucket called `backups-cleaner-test-bucket` and backups in the directory `backups`,
@@ -45,109 +79,2308 @@ use super::{StorageClient, BackupFileMeta};
 /// }
 /// ```
 pub struct AwsS3 {
-    s3_client: S3Client,
+
+    /// Shared by all clients derived by `with_prefix`.
+    s3_client: Arc<S3Client>,
     bucket: String,
     prefix: String,
+    page_size: Option<i64>,
+    listed_objects: Mutex<Option<usize>>,
+    success_sidecar: Option<String>,
+    expiry_tag: Option<(String, String)>,
+    prune_empty_markers: bool,
+    max_list: Option<usize>,
+    mfa: Option<String>,
+    retain_until_metadata: Option<String>,
+    tier_tag: Option<String>,
+    companion_suffixes: Vec<String>,
+    start_after: Option<String>,
+    protected_keys: HashSet<String>,
+    versions: bool,
+    deadline: Option<Instant>,
+}
+
+/// Number of requests to AWS S3 a run is expected to make.
+#[derive(Debug, PartialEq)]
+pub struct ApiCallEstimate {
+    pub list_requests: usize,
+    pub delete_requests: usize,
+}
+
+/// Why a backup failed verification, see `AwsS3::verify_backups`.
+#[derive(Debug, PartialEq)]
+pub enum IntegrityFailure {
+
+    /// The backup doesn't exist anymore.
+    Missing,
+
+    /// The backup's entity tag doesn't match the listed one anymore, so its content has
+    /// been replaced.
+    ChecksumMismatch {
+        expected: String,
+        actual: Option<String>,
+    },
+
+    /// The backup could not be checked.
+    Error(StorageError),
+}
+
+impl fmt::Display for IntegrityFailure {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntegrityFailure::Missing => write!(f, "It does not exist anymore."),
+            IntegrityFailure::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "Its ETag changed from {} to {}.",
+                expected,
+                actual.as_deref().unwrap_or("none"),
+            ),
+            IntegrityFailure::Error(error) => write!(f, "{}", error),
+        }
+    }
 }
 
+/// Number of `head_object` requests made at the same time, e.g. by `verify_backups`.
+const HEAD_CONCURRENCY: usize = 8;
+
 impl AwsS3 {
 
+    /// Maximum number of objects the AWS S3 API lists or deletes per request.
+    pub const MAX_KEYS_PER_REQUEST: usize = 1000;
+
     pub fn new(region: String, bucket: String, prefix: String) -> AwsS3 {
         let region = AWSRegion::from_str(&region).unwrap();
-        let s3_client = S3Client::new(region);
 
+        AwsS3::with_s3_client(default_s3_client(region), bucket, prefix)
+    }
+
+    /// Creates a client like `new`, authenticating with the credentials of `provider`
+    /// instead of the default chain of sources, e.g. to assume a role first.
+    pub fn with_credentials_provider<P>(region: String, bucket: String, prefix: String, provider: P) -> AwsS3
+        where P: ProvideAwsCredentials + Send + Sync + 'static, P::Future: Send
+    {
+        let region = AWSRegion::from_str(&region).unwrap();
+        let dispatcher = HttpClient::new().expect("The HTTP client can always be created.");
+
+        AwsS3::with_s3_client(S3Client::new_with(dispatcher, provider, region), bucket, prefix)
+    }
+
+    /// Creates a client for the `bucket`, wherever it is located. Asks AWS S3 for the
+    /// bucket's region first, so that requires the `s3:GetBucketLocation` permission.
+    pub fn new_autodetect(bucket: String, prefix: String) -> Result<AwsS3, StorageError> {
+
+        // As documented by AWS, `us-east-1` can be asked for the location of any bucket.
+        let region = detect_region(&default_s3_client(AWSRegion::UsEast1), &bucket)?;
+
+        Ok(AwsS3::with_s3_client(default_s3_client(region), bucket, prefix))
+    }
+
+    fn with_s3_client(s3_client: S3Client, bucket: String, prefix: String) -> AwsS3 {
         AwsS3 {
-            s3_client,
+            s3_client: Arc::new(s3_client),
             bucket,
             prefix,
+            page_size: None,
+            listed_objects: Mutex::new(None),
+            success_sidecar: None,
+            expiry_tag: None,
+            prune_empty_markers: false,
+            max_list: None,
+            mfa: None,
+            retain_until_metadata: None,
+            tier_tag: None,
+            companion_suffixes: vec![],
+            start_after: None,
+            protected_keys: HashSet::new(),
+            versions: false,
+            deadline: None,
         }
     }
 
-    fn object_to_backup_file_meta(&self, object: rusoto_s3::Object) -> BackupFileMeta {
-        let last_modified_string = object.last_modified.unwrap();
-        let last_modified = last_modified_string.parse::<DateTime<Utc>>().unwrap();
-        let id = object.key.unwrap();
+    /// Returns a client for the backups under another `prefix` of the same bucket,
+    /// configured like this one. Shares the underlying connection instead of building a
+    /// new one, so it's cheap, e.g. to prune each of the `subfolders` separately.
+    pub fn with_prefix(&self, prefix: String) -> AwsS3 {
+        AwsS3 {
+            s3_client: self.s3_client.clone(),
+            bucket: self.bucket.clone(),
+            prefix,
+            page_size: self.page_size,
+            listed_objects: Mutex::new(None),
+            success_sidecar: self.success_sidecar.clone(),
+            expiry_tag: self.expiry_tag.clone(),
+            prune_empty_markers: self.prune_empty_markers,
+            max_list: self.max_list,
+            mfa: self.mfa.clone(),
+            retain_until_metadata: self.retain_until_metadata.clone(),
+            tier_tag: self.tier_tag.clone(),
+            companion_suffixes: self.companion_suffixes.clone(),
+            start_after: self.start_after.clone(),
+            protected_keys: self.protected_keys.clone(),
+            versions: self.versions,
+            deadline: self.deadline,
+        }
+    }
 
-        BackupFileMeta {
-            id: id.clone(),
-            human_readable_id: id.clone(),
-            date: last_modified,
+    /// Lists each version of the objects as a backup of its own, along with its
+    /// `version_id`, and deletes exactly the versions given instead of the latest ones,
+    /// e.g. to prune the noncurrent versions of a versioned bucket by
+    /// `KeepLastNVersionsPerKey`. Delete markers aren't listed. Needs the
+    /// `s3:ListBucketVersions` and `s3:DeleteObjectVersion` permissions.
+    pub fn with_versions(mut self) -> AwsS3 {
+        self.versions = true;
+        self
+    }
+
+    /// Stops sending delete requests once `budget` (counted from now) has passed, e.g. to
+    /// finish before a cron job is killed. The request in flight is finished and the
+    /// backups not tried yet are reported as `remaining`, for the next run to continue.
+    /// Deletes the oldest backups first then, so the most expendable ones are handled.
+    pub fn with_time_budget(mut self, budget: Duration) -> AwsS3 {
+        self.deadline = Some(Instant::now() + budget);
+        self
+    }
+
+    /// Lists at most `page_size` objects per request, instead of the default 1000.
+    /// Smaller pages need more requests, but each of them is faster and cheaper to
+    /// retry on unstable connections.
+    pub fn with_page_size(mut self, page_size: usize) -> AwsS3 {
+
+        // Panic, if the API wouldn't accept the page size.
+        assert!((1..=AwsS3::MAX_KEYS_PER_REQUEST).contains(&page_size));
+
+        self.page_size = Some(page_size as i64);
+        self
+    }
+
+    /// Treats objects whose key ends with `suffix` (e.g. `.success`) as markers, that
+    /// the backup with the same key but without the suffix has been completed
+    /// successfully. Markers aren't listed as backups themselves, and each backup's
+    /// `successful` is set according to whether it has a marker. Markers aren't deleted
+    /// along with their backups.
+    ///
+    /// As markers may be listed on another page than their backup, `stored_backups_iter`
+    /// lists all objects at once, if this is set.
+    pub fn with_success_sidecar(mut self, suffix: String) -> AwsS3 {
+        self.success_sidecar = Some(suffix);
+        self
+    }
+
+    /// Sets each backup's `retain_until` from the object metadata `key` (e.g.
+    /// `retain-until` for `x-amz-meta-retain-until`), which is either an RFC 3339
+    /// timestamp or a date (`YYYY-MM-DD`, meaning midnight UTC). Listing fails, if a
+    /// value can't be parsed.
+    ///
+    /// Metadata isn't listed, so this makes a `head_object` request per backup, which
+    /// takes a lot longer and requires the `s3:GetObject` permission. Like with success
+    /// sidecars, `stored_backups_iter` lists all objects at once, if this is set.
+    pub fn with_retain_until_metadata(mut self, key: String) -> AwsS3 {
+        self.retain_until_metadata = Some(key.to_lowercase());
+        self
+    }
+
+    /// Treats objects whose key is that of a backup followed by `.` and one of the
+    /// `suffixes` (e.g. `backups/1.dump.sha256` for `sha256`) as its companions. They
+    /// aren't listed as backups themselves, but are deleted along with their backup in
+    /// the same request. Only companions failing to be deleted are reported, as
+    /// `failed_companions`, missing ones are no error, as S3 considers deleting a
+    /// missing key successful. Tagging leaves them untouched.
+    pub fn with_companions<S: Into<String>>(mut self, suffixes: Vec<S>) -> AwsS3 {
+        self.companion_suffixes = suffixes
+            .into_iter()
+            .map(|suffix| format!(".{}", suffix.into().trim_start_matches('.')))
+            .collect();
+        self
+    }
+
+    /// Never lists nor deletes the objects with exactly one of the given `keys` (e.g.
+    /// `backups/latest.dump`, a copy of the newest backup), so no strategy gets to
+    /// consider them. Backups with such a key passed to `delete_backups` anyway are
+    /// reported as `protected`.
+    pub fn with_protected_keys(mut self, keys: Vec<String>) -> AwsS3 {
+        self.protected_keys = keys.into_iter().collect();
+        self
+    }
+
+    /// Sets each backup's `tier` from its object tag `key` (e.g. `backup-tier`), if it
+    /// has one.
+    ///
+    /// Tags aren't listed, so this makes a `get_object_tagging` request per backup, which
+    /// takes a lot longer and requires the `s3:GetObjectTagging` permission. Like with
+    /// success sidecars, `stored_backups_iter` lists all objects at once, if this is set.
+    pub fn with_tier_tag(mut self, key: String) -> AwsS3 {
+        self.tier_tag = Some(key);
+        self
+    }
+
+    /// Makes `delete_backups` tag each backup with `key` set to `value` (e.g.
+    /// `lifecycle=expired`) instead of deleting it. Tagged backups are reported as
+    /// `tagged`. Note, that tagging replaces any tags an object had before, and that it
+    /// requires the `s3:PutObjectTagging` permission instead of `s3:DeleteObject`.
+    pub fn with_tag_instead_of_delete(mut self, key: String, value: String) -> AwsS3 {
+        self.expiry_tag = Some((key, value));
+        self
+    }
+
+    /// Makes `delete_backups` also delete the folder markers (zero-byte objects with a key
+    /// ending in `/`) under the prefix, once nothing but markers is left within their
+    /// folder. This lists the bucket again after deleting. Removed markers aren't
+    /// reported, as they're no backups, but failing to delete them is.
+    pub fn with_prune_empty_markers(mut self) -> AwsS3 {
+        self.prune_empty_markers = true;
+        self
+    }
+
+    /// Lists only the objects whose keys sort after `key` (e.g. the newest backup
+    /// processed by the last run), skipping the ones before it on the side of S3.
+    /// That only makes sense, if keys sort chronologically (e.g.
+    /// `backups/2014-07-01T00:00:00.dump`), as backups are missed otherwise. Note, that
+    /// strategies only see the listed backups, e.g. a monthly one before `key` doesn't
+    /// count.
+    pub fn with_start_after(mut self, key: String) -> AwsS3 {
+        self.start_after = Some(key);
+        self
+    }
+
+    /// Makes listing fail with `StorageError::TooManyObjects` as soon as more than `max`
+    /// objects have been listed, to bound the cost of listing misconfigured prefixes of
+    /// huge buckets. There's no cap by default.
+    pub fn with_max_list(mut self, max: usize) -> AwsS3 {
+        self.max_list = Some(max);
+        self
+    }
+
+    /// Authenticates delete requests with the MFA device with the given `serial` (e.g.
+    /// `arn:aws:iam::123456789012:mfa/admin`) and its current `token`, as required by
+    /// buckets with MFA delete enabled. As tokens are only valid for a short time, the
+    /// client should be used right away.
+    ///
+    /// MFA delete can only be enabled on versioned buckets, where it's only required to
+    /// delete specific versions. Deleting backups by key only adds delete markers, which
+    /// doesn't require MFA, but leaves the previous versions stored.
+    pub fn with_mfa(mut self, serial: String, token: String) -> AwsS3 {
+        self.mfa = Some(format!("{} {}", serial, token));
+        self
+    }
+
+    /// Estimates the number of requests a run deleting `expendable_count` backups
+    /// makes. Listing is estimated from the number of objects listed by the last call
+    /// to `stored_backups` (including companions and other objects that aren't backups),
+    /// if any, and otherwise assumes only the expendable backups are stored.
+    pub fn estimate_api_calls(&self, expendable_count: usize) -> ApiCallEstimate {
+        let objects = self.listed_objects.lock().unwrap().unwrap_or(expendable_count);
+
+        ApiCallEstimate {
+            // Listing takes a request, even if there are no objects.
+            list_requests: objects.div_ceil(self.objects_per_page()).max(1),
+            delete_requests: expendable_count.div_ceil(self.backups_per_delete_request()),
         }
     }
 
-    fn backup_file_meta_to_object_identifier(&self, backup_file_meta: BackupFileMeta) -> rusoto_s3::ObjectIdentifier {
-        rusoto_s3::ObjectIdentifier {
-            key: backup_file_meta.id, version_id: None
+    /// Returns the prefixes of the immediate subfolders of the prefix, i.e. the keys up
+    /// to and including the next `/` (e.g. `backups/customers/` for the prefix
+    /// `backups/`). Useful to prune each of them separately.
+    pub fn subfolders(&self) -> Result<Vec<String>, StorageError> {
+        let mut subfolders = vec![];
+        let mut continuation_token = None;
+
+        loop {
+            let list_result = self.s3_client
+                .list_objects_v2(self.subfolders_request(continuation_token))
+                .with_timeout(Duration::from_secs(3))
+                .sync()
+                .map_err(|error| self.list_error(error))?;
+
+            subfolders.extend(
+                list_result.common_prefixes
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|common_prefix| common_prefix.prefix)
+            );
+
+            match list_result.next_continuation_token {
+                Some(token) if list_result.is_truncated == Some(true) => continuation_token = Some(token),
+                _ => return Ok(subfolders),
+            }
         }
     }
-}
 
-impl StorageClient for AwsS3 {
+    /// Returns the contents of the object stored under `key` in the bucket, e.g. a
+    /// manifest. The `key` is not relative to the prefix.
+    pub fn object_contents(&self, key: &str) -> Result<String, StorageError> {
+        let request = rusoto_s3::GetObjectRequest {
+            bucket: self.bucket.clone(),
+            key: String::from(key),
+            ..Default::default()
+        };
+        let output = self.s3_client
+            .get_object(request)
+            .with_timeout(Duration::from_secs(3))
+            .sync()
+            .map_err(|error| match error {
+                RusotoError::Service(GetObjectError::NoSuchKey(_)) => StorageError::Request(format!("{} does not exist", key)),
+                error => storage_error(&self.bucket, error),
+            })?;
+
+        let mut contents = String::new();
+        if let Some(body) = output.body {
+            body.into_blocking_read()
+                .read_to_string(&mut contents)
+                .map_err(|error| StorageError::Network(error.to_string()))?;
+        }
+
+        Ok(contents)
+    }
+
+    /// Stores `contents` as JSON under `key` in the bucket, e.g. a receipt. The `key` is
+    /// not relative to the prefix. Replaces any object stored under `key` before.
+    pub fn put_object(&self, key: &str, contents: String) -> Result<(), StorageError> {
+        let request = rusoto_s3::PutObjectRequest {
+            bucket: self.bucket.clone(),
+            key: String::from(key),
+            content_type: Some(String::from("application/json")),
+            content_length: Some(contents.len() as i64),
+            body: Some(contents.into_bytes().into()),
+            ..Default::default()
+        };
+
+        self.s3_client
+            .put_object(request)
+            .with_timeout(Duration::from_secs(3))
+            .sync()
+            .map(|_| ())
+            .map_err(|error| storage_error(&self.bucket, error))
+    }
+
+    /// Checks each of the `backups` still exists and, if its `etag` is known, that its
+    /// content hasn't changed, making a `head_object` request per backup. Returns the
+    /// backups failing the check, along with why.
+    pub fn verify_backups(&self, backups: &[BackupFileMeta]) -> Vec<(BackupFileMeta, IntegrityFailure)> {
+        if backups.is_empty() {
+            return vec![];
+        }
+
+        let chunk_size = backups.len().div_ceil(HEAD_CONCURRENCY);
+
+        thread::scope(|scope| {
+            let workers: Vec<_> = backups
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .filter_map(|backup| self.verify_backup(backup).err().map(|failure| (backup.clone(), failure)))
+                        .collect::<Vec<_>>()
+                }))
+                .collect();
+
+            workers.into_iter().flat_map(|worker| worker.join().unwrap()).collect()
+        })
+    }
+
+    fn verify_backup(&self, backup: &BackupFileMeta) -> Result<(), IntegrityFailure> {
+        let request = rusoto_s3::HeadObjectRequest {
+            bucket: self.bucket.clone(),
+            key: backup.id.clone(),
+            ..Default::default()
+        };
+        let output = self.s3_client
+            .head_object(request)
+            .with_timeout(Duration::from_secs(3))
+            .sync()
+            .map_err(|error| match error {
+                RusotoError::Service(HeadObjectError::NoSuchKey(_)) => IntegrityFailure::Missing,
+                RusotoError::Unknown(ref response) if response.status == 404 => IntegrityFailure::Missing,
+                error => IntegrityFailure::Error(storage_error(&self.bucket, error)),
+            })?;
+
+        match &backup.etag {
+            Some(expected) if output.e_tag.as_ref() != Some(expected) => Err(IntegrityFailure::ChecksumMismatch {
+                expected: expected.clone(),
+                actual: output.e_tag,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Sets `retain_until` and `tier` on each of the `backup_file_metas`, requesting
+    /// their metadata and tags respectively. Leaves them untouched, if neither the
+    /// metadata key nor the tag key has been set.
+    fn fetch_object_details(&self, backup_file_metas: Vec<BackupFileMeta>) -> Result<Vec<BackupFileMeta>, StorageError> {
+        if backup_file_metas.is_empty() || (self.retain_until_metadata.is_none() && self.tier_tag.is_none()) {
+            return Ok(backup_file_metas);
+        }
+
+        let chunk_size = backup_file_metas.len().div_ceil(HEAD_CONCURRENCY);
+
+        thread::scope(|scope| {
+            let workers: Vec<_> = backup_file_metas
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|backup_file_meta| Ok(BackupFileMeta {
+                            retain_until: match &self.retain_until_metadata {
+                                Some(key) => self.retain_until(backup_file_meta, key)?,
+                                None => backup_file_meta.retain_until,
+                            },
+                            tier: match &self.tier_tag {
+                                Some(key) => self.tier(backup_file_meta, key)?,
+                                None => backup_file_meta.tier.clone(),
+                            },
+                            ..backup_file_meta.clone()
+                        }))
+                        .collect::<Result<Vec<_>, StorageError>>()
+                }))
+                .collect();
+
+            let mut backup_file_metas = vec![];
+            for worker in workers {
+                backup_file_metas.append(&mut worker.join().unwrap()?);
+            }
+
+            Ok(backup_file_metas)
+        })
+    }
+
+    fn retain_until(&self, backup_file_meta: &BackupFileMeta, key: &str) -> Result<Option<DateTime<Utc>>, StorageError> {
+        let request = rusoto_s3::HeadObjectRequest {
+            bucket: self.bucket.clone(),
+            key: backup_file_meta.id.clone(),
+            ..Default::default()
+        };
+        let output = match self.s3_client.head_object(request).with_timeout(Duration::from_secs(3)).sync() {
+            Ok(output) => output,
+
+            // Deleted since listing, so there's nothing left to retain.
+            Err(RusotoError::Service(HeadObjectError::NoSuchKey(_))) => return Ok(None),
+            Err(RusotoError::Unknown(ref response)) if response.status == 404 => return Ok(None),
+            Err(error) => return Err(storage_error(&self.bucket, error)),
+        };
+
+        match output.metadata.as_ref().and_then(|metadata| metadata.get(key)) {
+            Some(value) => parse_retain_until(value).map(Some).ok_or_else(|| StorageError::Request(format!(
+                "'{}' of {} is neither an RFC 3339 timestamp nor a date (YYYY-MM-DD)",
+                value,
+                backup_file_meta.id
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    /// Number of backups deleted per request, so that they fit in along with their
+    /// companions.
+    fn backups_per_delete_request(&self) -> usize {
+        AwsS3::MAX_KEYS_PER_REQUEST / (1 + self.companion_suffixes.len())
+    }
+
+    /// Whether `key` is that of a companion, see `with_companions`.
+    fn is_companion(&self, key: &str) -> bool {
+        self.companion_suffixes.iter().any(|suffix| key.ends_with(suffix.as_str()))
+    }
+
+    /// Splits off the backups with a protected key, see `with_protected_keys`.
+    fn split_off_protected(&self, backup_file_metas: Vec<BackupFileMeta>) -> (Vec<BackupFileMeta>, Vec<BackupFileMeta>) {
+        backup_file_metas
+            .into_iter()
+            .partition(|backup_file_meta| self.protected_keys.contains(&backup_file_meta.id))
+    }
+
+    fn tier(&self, backup_file_meta: &BackupFileMeta, key: &str) -> Result<Option<String>, StorageError> {
+        let request = rusoto_s3::GetObjectTaggingRequest {
+            bucket: self.bucket.clone(),
+            key: backup_file_meta.id.clone(),
+            version_id: None,
+        };
+        let output = match self.s3_client.get_object_tagging(request).with_timeout(Duration::from_secs(3)).sync() {
+            Ok(output) => output,
+
+            // Deleted since listing, like for `retain_until`.
+            Err(RusotoError::Unknown(ref response)) if response.status == 404 => return Ok(None),
+            Err(error) => return Err(storage_error(&self.bucket, error)),
+        };
+
+        Ok(output.tag_set.into_iter().find(|tag| tag.key == key).map(|tag| tag.value))
+    }
+
+    fn objects_per_page(&self) -> usize {
+        self.page_size.map_or(AwsS3::MAX_KEYS_PER_REQUEST, |page_size| page_size as usize)
+    }
 
-    fn stored_backups(&self) -> Vec<BackupFileMeta> {
-        let list_request = rusoto_s3::ListObjectsV2Request {
+    fn list_request(&self, continuation_token: Option<String>) -> rusoto_s3::ListObjectsV2Request {
+        rusoto_s3::ListObjectsV2Request {
             bucket: self.bucket.clone(),
             prefix: Some(self.prefix.clone()),
             delimiter: None,
             encoding_type: None,
-            max_keys: None,
+            max_keys: self.page_size,
             request_payer: None,
-            continuation_token: None,
+            continuation_token,
             fetch_owner: None,
+            start_after: self.start_after.clone(),
+        }
+    }
+
+    fn versions_request(&self, key_marker: Option<String>, version_id_marker: Option<String>) -> rusoto_s3::ListObjectVersionsRequest {
+        rusoto_s3::ListObjectVersionsRequest {
+            bucket: self.bucket.clone(),
+            prefix: Some(self.prefix.clone()),
+            delimiter: None,
+            encoding_type: None,
+            max_keys: self.page_size,
+            key_marker,
+            version_id_marker,
+        }
+    }
+
+    /// Lists all versions of the objects under the prefix, see `with_versions`. Also
+    /// returns the number of versions and delete markers listed, including skipped ones.
+    fn list_versions(&self) -> Result<(Vec<BackupFileMeta>, usize), StorageError> {
+        let mut backup_file_metas = vec![];
+        let mut listed = 0;
+        let mut markers = (None, None);
+
+        loop {
+            let list_result = self.s3_client
+                .list_object_versions(self.versions_request(markers.0.take(), markers.1.take()))
+                .with_timeout(Duration::from_secs(3))
+                .sync()
+                .map_err(|error| storage_error(&self.bucket, error))?;
+            let versions = list_result.versions.unwrap_or_default();
+            listed += versions.len() + list_result.delete_markers.as_ref().map_or(0, Vec::len);
+
+            if let Some(max) = self.max_list {
+                if listed > max {
+                    return Err(StorageError::TooManyObjects(self.bucket.clone(), max));
+                }
+            }
+
+            for version in versions {
+                if version.key.as_ref().is_some_and(|key| self.is_companion(key) || self.protected_keys.contains(key)) {
+                    continue;
+                }
+
+                let object = rusoto_s3::Object {
+                    e_tag: version.e_tag,
+                    key: version.key,
+                    last_modified: version.last_modified,
+                    owner: version.owner,
+                    size: version.size,
+                    storage_class: version.storage_class,
+                };
+                backup_file_metas.push(BackupFileMeta {
+                    version_id: version.version_id,
+                    ..self.object_to_backup_file_meta(object)?
+                });
+            }
+
+            if list_result.is_truncated != Some(true) || list_result.next_key_marker.is_none() {
+                break;
+            }
+            markers = (list_result.next_key_marker, list_result.next_version_id_marker);
+        }
+
+        backup_file_metas.sort_by_key(|backup_file_meta| backup_file_meta.date);
+
+        Ok((backup_file_metas, listed))
+    }
+
+    fn subfolders_request(&self, continuation_token: Option<String>) -> rusoto_s3::ListObjectsV2Request {
+        rusoto_s3::ListObjectsV2Request {
+            delimiter: Some(String::from("/")),
             start_after: None,
-        };
-        let objects = self.s3_client
-            .list_objects_v2(list_request)
-            .with_timeout(Duration::from_secs(3))
-            .sync()
-            .unwrap()
-            .contents
-            .unwrap();
+            ..self.list_request(continuation_token)
+        }
+    }
 
-        objects.into_iter().map(|object| self.object_to_backup_file_meta(object)).collect()
+    fn list_error(&self, error: RusotoError<ListObjectsV2Error>) -> StorageError {
+        match error {
+            RusotoError::Service(ListObjectsV2Error::NoSuchBucket(_)) => StorageError::BucketNotFound(self.bucket.clone()),
+            error => storage_error(&self.bucket, error),
+        }
     }
 
-    fn delete_backups(&self, backup_file_metas: Vec<BackupFileMeta>) -> usize {
+    /// Builds a request deleting `backup_file_metas`. If `quiet`, S3 only responds with
+    /// the objects it failed to delete, which is shorter and faster to parse.
+    fn delete_request(&self, backup_file_metas: &[BackupFileMeta], quiet: bool) -> rusoto_s3::DeleteObjectsRequest {
         let objects_to_delete: Vec<rusoto_s3::ObjectIdentifier> = backup_file_metas
-            .into_iter()
-            .map(|backup_file_meta| self.backup_file_meta_to_object_identifier(backup_file_meta))
+            .iter()
+            .flat_map(|backup_file_meta| {
+                let companions = self.companion_suffixes.iter().map(move |suffix| rusoto_s3::ObjectIdentifier {
+                    key: format!("{}{}", backup_file_meta.id, suffix),
+                    version_id: None,
+                });
+
+                std::iter::once(self.backup_file_meta_to_object_identifier(backup_file_meta)).chain(companions)
+            })
             .collect();
 
-        let delete_request = rusoto_s3::DeleteObjectsRequest {
+        rusoto_s3::DeleteObjectsRequest {
             bucket: self.bucket.clone(),
             bypass_governance_retention: None,
-            mfa: None,
+            mfa: self.mfa.clone(),
             request_payer: None,
             delete: rusoto_s3::Delete {
                 objects: objects_to_delete,
-                quiet: None,
+                quiet: Some(quiet),
+            },
+        }
+    }
+
+    fn tagging_request(&self, backup_file_meta: &BackupFileMeta, key: &str, value: &str) -> rusoto_s3::PutObjectTaggingRequest {
+        rusoto_s3::PutObjectTaggingRequest {
+            bucket: self.bucket.clone(),
+            content_md5: None,
+            key: backup_file_meta.id.clone(),
+            tagging: rusoto_s3::Tagging {
+                tag_set: vec![rusoto_s3::Tag {
+                    key: String::from(key),
+                    value: String::from(value),
+                }],
             },
+            version_id: None,
+        }
+    }
+
+    /// Tags each of the `backup_file_metas` with `key` set to `value`, one request each.
+    fn tag_backups(&self, backup_file_metas: Vec<BackupFileMeta>, key: &str, value: &str) -> DeletionReport {
+        let mut report = DeletionReport::default();
+
+        for backup_file_meta in backup_file_metas {
+            let tagging_result = self.s3_client
+                .put_object_tagging(self.tagging_request(&backup_file_meta, key, value))
+                .with_timeout(Duration::from_secs(3))
+                .sync();
+
+            match tagging_result {
+                Ok(_) => report.tagged.push(backup_file_meta),
+                Err(error) => report.failed.push((backup_file_meta, error.to_string())),
+            }
+        }
+
+        report
+    }
+
+    /// Fails, if the object's timestamp can't be parsed, e.g. as an S3-compatible store
+    /// formats it differently.
+    fn object_to_backup_file_meta(&self, object: rusoto_s3::Object) -> Result<BackupFileMeta, StorageError> {
+        let id = object.key.unwrap_or_default();
+        let last_modified = parse_object_timestamp(&id, object.last_modified.as_deref().unwrap_or_default())
+            .map_err(|error| StorageError::Request(error.to_string()))?;
+
+        Ok(BackupFileMeta {
+            id: id.clone(),
+            human_readable_id: id,
+            date: last_modified,
+            size: object.size.unwrap_or(0) as u64,
+            storage_class: object.storage_class,
+            etag: object.e_tag,
+            successful: None,
+            retain_until: None,
+            tier: None,
+            version_id: None,
+        })
+    }
+
+    /// Removes the success markers from `backup_file_metas`, and sets `successful` on
+    /// the remaining backups. Leaves them untouched, if no marker suffix has been set.
+    fn pair_with_success_sidecars(&self, backup_file_metas: Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+        let suffix = match &self.success_sidecar {
+            Some(suffix) => suffix,
+            None => return backup_file_metas,
         };
 
-        let delete_result = self.s3_client
-            .delete_objects(delete_request)
-            .with_timeout(Duration::from_secs(3))
-            .sync()
-            .unwrap();
+        let (sidecars, backup_file_metas): (Vec<BackupFileMeta>, Vec<BackupFileMeta>) = backup_file_metas
+            .into_iter()
+            .partition(|backup_file_meta| backup_file_meta.id.ends_with(suffix.as_str()));
+        let successful_ids: HashSet<&str> = sidecars
+            .iter()
+            .map(|sidecar| &sidecar.id[..sidecar.id.len() - suffix.len()])
+            .collect();
+
+        backup_file_metas
+            .into_iter()
+            .map(|backup_file_meta| BackupFileMeta {
+                successful: Some(successful_ids.contains(backup_file_meta.id.as_str())),
+                ..backup_file_meta
+            })
+            .collect()
+    }
+
+    fn backup_file_meta_to_object_identifier(&self, backup_file_meta: &BackupFileMeta) -> rusoto_s3::ObjectIdentifier {
+        rusoto_s3::ObjectIdentifier {
+            key: backup_file_meta.id.clone(), version_id: backup_file_meta.version_id.clone()
+        }
+    }
+
+    /// Deletes `backup_file_metas` with as few requests as possible. If `quiet`, S3 only
+    /// reports failures, so all other backups are taken as deleted.
+    fn delete_in_batches(&self, backup_file_metas: Vec<BackupFileMeta>, quiet: bool) -> DeletionReport {
+        let mut report = DeletionReport::default();
+        let mut remaining_backup_file_metas = backup_file_metas;
+        if self.deadline.is_some() {
+            remaining_backup_file_metas.sort_by_key(|backup_file_meta| backup_file_meta.date);
+        }
+        let mut first_batch = true;
+
+        while !remaining_backup_file_metas.is_empty() {
+
+            // At least one batch is sent, so every run makes progress.
+            if !first_batch && self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                report.remaining = remaining_backup_file_metas;
+                break;
+            }
+            first_batch = false;
+
+            let batch_size = remaining_backup_file_metas.len().min(self.backups_per_delete_request());
+            let rest = remaining_backup_file_metas.split_off(batch_size);
+            let batch = mem::replace(&mut remaining_backup_file_metas, rest);
+
+            let delete_result = self.s3_client
+                .delete_objects(self.delete_request(&batch, quiet))
+                .with_timeout(Duration::from_secs(3))
+                .sync();
+
+            match delete_result {
+                Ok(delete_result) => report.append(self.deletion_report(batch, delete_result, quiet)),
+                Err(error) => {
+                    let reason = error.to_string();
+                    report.failed.extend(batch.into_iter().map(|backup_file_meta| (backup_file_meta, reason.clone())));
+                },
+            }
+        }
+
+        report
+    }
+
+    /// Sorts the requested `backup_file_metas` into deleted, protected and failed ones,
+    /// according to the result of the delete request. Results of `quiet` requests only
+    /// list failures.
+    fn deletion_report(
+        &self,
+        backup_file_metas: Vec<BackupFileMeta>,
+        delete_result: rusoto_s3::DeleteObjectsOutput,
+        quiet: bool,
+    ) -> DeletionReport {
+        // Keyed by version too, as several versions of a key may be deleted at once.
+        let mut requested: HashMap<(String, Option<String>), BackupFileMeta> = backup_file_metas
+            .into_iter()
+            .map(|backup_file_meta| ((backup_file_meta.id.clone(), backup_file_meta.version_id.clone()), backup_file_meta))
+            .collect();
+        let mut report = DeletionReport::default();
+
+        for deleted_object in delete_result.deleted.unwrap_or_default() {
+            let version_id = deleted_object.version_id;
+            if let Some(backup_file_meta) = deleted_object.key.and_then(|key| requested.remove(&(key, version_id))) {
+                report.deleted.push(backup_file_meta);
+            }
+        }
 
-        delete_result.deleted.unwrap().len()
+        for error in delete_result.errors.unwrap_or_default() {
+            let version_id = error.version_id.clone();
+            let key = error.key.clone().unwrap_or_default();
+
+            if let Some(backup_file_meta) = requested.remove(&(key.clone(), version_id)) {
+                if protected_by_object_lock(&error) {
+                    report.protected.push(backup_file_meta);
+                    continue;
+                }
+
+                let denied_by_kms = denied_by_kms(&error);
+                let reason = error.message.or(error.code).unwrap_or_else(|| String::from("Unknown error"));
+                if denied_by_kms {
+                    report.kms_denied.push((backup_file_meta, reason));
+                }
+                else {
+                    report.failed.push((backup_file_meta, reason));
+                }
+            }
+            else if self.is_companion(&key) && error.code.as_deref() != Some("NoSuchKey") {
+                let reason = error.message.or(error.code).unwrap_or_else(|| String::from("Unknown error"));
+                report.failed_companions.push((key, reason));
+            }
+        }
+
+        for (_, backup_file_meta) in requested {
+            if quiet {
+                report.deleted.push(backup_file_meta);
+            }
+            else {
+                report.failed.push((backup_file_meta, String::from("Not reported by S3")));
+            }
+        }
+
+        report
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Whether deleting an object failed, because S3 Object Lock retains it. S3 reports
+/// that as access being denied, telling the reason only in the message.
+fn protected_by_object_lock(error: &rusoto_s3::S3Error) -> bool {
+    let code = error.code.as_deref();
+    let message = error.message.as_ref().map(|message| message.to_lowercase()).unwrap_or_default();
 
-    #[test]
-    fn test_new() {
-        let aws_s3_client = AwsS3::new(
-            String::from("eu-west-2"),
-            String::from("my-database-backups"),
-            String::from("backups/")
-        );
+    code == Some("ObjectLocked") || (code == Some("AccessDenied") && message.contains("object lock"))
+}
 
-        assert_eq!(aws_s3_client.bucket, String::from("my-database-backups"));
-        assert_eq!(aws_s3_client.prefix, String::from("backups/"));
+/// Whether deleting an object failed, because the policy of the KMS key it's encrypted
+/// with denies it. S3 reports that with a code of KMS (e.g. `KMS.AccessDeniedException`),
+/// or as access being denied, telling the reason only in the message.
+fn denied_by_kms(error: &rusoto_s3::S3Error) -> bool {
+    let code = error.code.as_deref();
+    let message = error.message.as_deref().unwrap_or_default();
+
+    code.is_some_and(|code| code.starts_with("KMS.")) || (code == Some("AccessDenied") && message.contains("KMS"))
+}
+
+/// Returns the folder markers among `objects`, which no other objects except for
+/// markers lie within.
+fn empty_markers(objects: Vec<BackupFileMeta>) -> Vec<BackupFileMeta> {
+    let is_marker = |object: &BackupFileMeta| object.id.ends_with('/') && object.size == 0;
+    let (markers, other_objects): (Vec<BackupFileMeta>, Vec<BackupFileMeta>) = objects.into_iter().partition(is_marker);
+
+    markers
+        .into_iter()
+        .filter(|marker| !other_objects.iter().any(|object| object.id.starts_with(marker.id.as_str())))
+        .collect()
+}
+
+/// Maps errors of requests concerning `bucket`, that aren't specific to the request.
+fn storage_error<E: Error + 'static>(bucket: &str, error: RusotoError<E>) -> StorageError {
+    match error {
+        RusotoError::Unknown(ref response) if response.status == 403 => StorageError::AccessDenied(String::from(bucket)),
+        RusotoError::Unknown(ref response) if response.status == 404 => StorageError::BucketNotFound(String::from(bucket)),
+        RusotoError::Credentials(error) => StorageError::Authentication(error.to_string()),
+        RusotoError::HttpDispatch(error) => StorageError::Network(error.to_string()),
+        error => StorageError::Request(error.to_string()),
+    }
+}
+
+/// Returns a client for `region` taking credentials from the default chain of sources
+/// (see `AwsS3`), which ends with the container and instance metadata services.
+fn default_s3_client(region: AWSRegion) -> S3Client {
+    let provider = DefaultCredentialsProvider::new().expect("The default credentials provider can always be created.");
+    let dispatcher = HttpClient::new().expect("The HTTP client can always be created.");
+
+    S3Client::new_with(dispatcher, provider, region)
+}
+
+/// Asks AWS S3 using `s3_client` for the region `bucket` is located in.
+fn detect_region(s3_client: &S3Client, bucket: &str) -> Result<AWSRegion, StorageError> {
+    let location = s3_client
+        .get_bucket_location(rusoto_s3::GetBucketLocationRequest { bucket: String::from(bucket) })
+        .with_timeout(Duration::from_secs(3))
+        .sync()
+        .map_err(|error| storage_error(bucket, error))?;
+
+    region_from_location_constraint(location.location_constraint)
+}
+
+/// Buckets in `us-east-1` have no location constraint, and ones created as `EU` are
+/// located in `eu-west-1`.
+fn region_from_location_constraint(location_constraint: Option<String>) -> Result<AWSRegion, StorageError> {
+    match location_constraint.as_deref() {
+        None | Some("") => Ok(AWSRegion::UsEast1),
+        Some("EU") => Ok(AWSRegion::EuWest1),
+        Some(region) => AWSRegion::from_str(region)
+            .map_err(|_| StorageError::Request(format!("Unknown region '{}'", region))),
+    }
+}
+
+/// A timestamp of an object that couldn't be parsed.
+#[derive(Debug, PartialEq)]
+struct ParseError {
+    key: String,
+    raw: String,
+    reason: String,
+}
+
+impl fmt::Display for ParseError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The timestamp '{}' of {} is invalid: {}", self.raw, self.key, self.reason)
+    }
+}
+
+/// Parses the `raw` last modified timestamp of the object with the given `key`, as
+/// listed by S3 (e.g. `2014-07-01T00:00:00.000Z`).
+fn parse_object_timestamp(key: &str, raw: &str) -> Result<DateTime<Utc>, ParseError> {
+    raw.parse::<DateTime<Utc>>().map_err(|error| ParseError {
+        key: String::from(key),
+        raw: String::from(raw),
+        reason: error.to_string(),
+    })
+}
+
+/// Parses `value` as RFC 3339 timestamp or as date (`YYYY-MM-DD`, meaning midnight UTC).
+fn parse_retain_until(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date_time) = value.parse::<DateTime<Utc>>() {
+        return Some(date_time);
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .map(|date| DateTime::from_utc(date.and_hms(0, 0, 0), Utc))
+}
+
+/// Lists the objects of a bucket page by page, requesting the next page as soon as
+/// the current one has been consumed.
+struct Listing<'a> {
+    client: &'a AwsS3,
+    page: vec::IntoIter<BackupFileMeta>,
+
+    /// Objects modified outside of this range (inclusive) are skipped, if it's given.
+    range: Option<(DateTime<Utc>, DateTime<Utc>)>,
+
+    /// Whether companions and objects with a protected key are listed too.
+    unfiltered: bool,
+
+    /// Token to request the next page with, or `None`, if it's the first page.
+    continuation_token: Option<String>,
+    exhausted: bool,
+
+    /// Number of objects listed so far, including skipped ones.
+    listed: usize,
+}
+
+impl<'a> Listing<'a> {
+
+    fn new(client: &'a AwsS3, range: Option<(DateTime<Utc>, DateTime<Utc>)>) -> Listing<'a> {
+        Listing {
+            client,
+            page: vec![].into_iter(),
+            range,
+            unfiltered: false,
+            continuation_token: None,
+            exhausted: false,
+            listed: 0,
+        }
+    }
+
+    /// Lists all objects, including companions and objects with a protected key.
+    fn unfiltered(client: &'a AwsS3) -> Listing<'a> {
+        Listing {
+            unfiltered: true,
+            ..Listing::new(client, None)
+        }
+    }
+
+    /// Whether `object` is skipped, as it's a companion or has a protected key.
+    fn filtered_out(&self, object: &rusoto_s3::Object) -> bool {
+        !self.unfiltered && object.key.as_ref().is_some_and(|key| self.client.is_companion(key) || self.client.protected_keys.contains(key))
+    }
+
+    fn fetch_next_page(&mut self) -> Result<(), StorageError> {
+        let list_result = self.client.s3_client
+            .list_objects_v2(self.client.list_request(self.continuation_token.take()))
+            .with_timeout(Duration::from_secs(3))
+            .sync()
+            .map_err(|error| self.client.list_error(error))?;
+        let objects = list_result.contents.unwrap_or_default();
+
+        self.listed += objects.len();
+        if let Some(max) = self.client.max_list {
+            if self.listed > max {
+                return Err(StorageError::TooManyObjects(self.client.bucket.clone(), max));
+            }
+        }
+
+        self.page = objects
+            .into_iter()
+            .filter(|object| !self.filtered_out(object))
+            .filter(|object| self.wanted(object))
+            .map(|object| self.client.object_to_backup_file_meta(object))
+            .collect::<Result<Vec<BackupFileMeta>, StorageError>>()?
+            .into_iter();
+
+        match list_result.next_continuation_token {
+            Some(token) if list_result.is_truncated == Some(true) => self.continuation_token = Some(token),
+            _ => self.exhausted = true,
+        }
+
+        Ok(())
+    }
+
+    /// Whether `object` is within the range, checking only its date without building
+    /// the whole `BackupFileMeta`. Success markers are always wanted, as they may have
+    /// been written after the range ends.
+    fn wanted(&self, object: &rusoto_s3::Object) -> bool {
+        let (start, end) = match self.range {
+            Some(range) => range,
+            None => return true,
+        };
+
+        if let (Some(suffix), Some(key)) = (&self.client.success_sidecar, &object.key) {
+            if key.ends_with(suffix.as_str()) {
+                return true;
+            }
+        }
+
+        match object.last_modified.as_ref().and_then(|date| date.parse::<DateTime<Utc>>().ok()) {
+            Some(date) => start <= date && date <= end,
+            None => true,
+        }
+    }
+}
+
+impl<'a> Iterator for Listing<'a> {
+    type Item = Result<BackupFileMeta, StorageError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(backup_file_meta) = self.page.next() {
+                return Some(Ok(backup_file_meta));
+            }
+
+            if self.exhausted {
+                return None;
+            }
+
+            if let Err(error) = self.fetch_next_page() {
+                self.exhausted = true;
+                return Some(Err(error));
+            }
+        }
+    }
+}
+
+impl StorageClient for AwsS3 {
+
+    fn stored_backups(&self) -> Result<Vec<BackupFileMeta>, StorageError> {
+        let (listing, listed) = if self.versions {
+            self.list_versions()?
+        }
+        else {
+            let mut listing = Listing::new(self, None);
+            let backup_file_metas = listing.by_ref().collect::<Result<_, _>>()?;
+            (backup_file_metas, listing.listed)
+        };
+
+        // Requests are made for all objects listed, even the ones that aren't backups.
+        *self.listed_objects.lock().unwrap() = Some(listed);
+
+        let backup_file_metas = self.pair_with_success_sidecars(listing);
+        let backup_file_metas = self.fetch_object_details(backup_file_metas)?;
+
+        Ok(backup_file_metas)
+    }
+
+    fn stored_backups_iter(&self) -> Box<dyn Iterator<Item = Result<BackupFileMeta, StorageError>> + '_> {
+        if self.success_sidecar.is_none() && self.retain_until_metadata.is_none() && self.tier_tag.is_none() && !self.versions {
+            return Box::new(Listing::new(self, None));
+        }
+
+        match self.stored_backups() {
+            Ok(backup_file_metas) => Box::new(backup_file_metas.into_iter().map(Ok)),
+            Err(error) => Box::new(std::iter::once(Err(error))),
+        }
+    }
+
+    fn backups_in_range(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<BackupFileMeta>, StorageError> {
+        if self.versions {
+            let mut backup_file_metas = self.stored_backups()?;
+            backup_file_metas.retain(|backup_file_meta| start <= backup_file_meta.date && backup_file_meta.date <= end);
+
+            return Ok(backup_file_metas);
+        }
+
+        let listing = Listing::new(self, Some((start, end))).collect::<Result<_, _>>()?;
+
+        self.fetch_object_details(self.pair_with_success_sidecars(listing))
+    }
+
+    fn delete_backups(&self, backup_file_metas: Vec<BackupFileMeta>) -> DeletionReport {
+        let (protected, backup_file_metas) = self.split_off_protected(backup_file_metas);
+
+        let mut report = if let Some((key, value)) = &self.expiry_tag {
+            self.tag_backups(backup_file_metas, key, value)
+        }
+        else {
+            self.delete_in_batches(backup_file_metas, false)
+        };
+        report.protected.extend(protected);
+
+        if self.prune_empty_markers && !report.deleted.is_empty() {
+
+            // Pruning itself succeeded, so markers are just left in place, if the bucket
+            // can't be listed. Companions and protected objects keep their folders too.
+            if let Ok(remaining_objects) = Listing::unfiltered(self).collect::<Result<Vec<BackupFileMeta>, _>>() {
+                let (_, markers) = self.split_off_protected(empty_markers(remaining_objects));
+
+                if !markers.is_empty() {
+                    let mut markers_report = self.delete_in_batches(markers, true);
+                    report.failed.append(&mut markers_report.failed);
+                    report.kms_denied.append(&mut markers_report.kms_denied);
+                }
+            }
+        }
+
+        report
+    }
+
+    fn delete_backups_counting(&self, backup_file_metas: Vec<BackupFileMeta>) -> DeletionCounts {
+        if self.expiry_tag.is_some() || self.prune_empty_markers {
+            return DeletionCounts::from(&self.delete_backups(backup_file_metas));
+        }
+
+        let (protected, backup_file_metas) = self.split_off_protected(backup_file_metas);
+        let mut counts = DeletionCounts::from(&self.delete_in_batches(backup_file_metas, true));
+        counts.protected += protected.len();
+
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pruning_strategy::tests::{build_meta, collect_ids};
+    use rusoto_mock::{MockCredentialsProvider, MockRequestDispatcher};
+    use rusoto_core::DispatchSignedRequest;
+    use rusoto_core::signature::SignedRequest;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use chrono::offset::TimeZone;
+
+    fn mocked_client<D>(dispatcher: D) -> AwsS3
+        where D: DispatchSignedRequest + Send + Sync + 'static, D::Future: Send + 'static
+    {
+        AwsS3::with_s3_client(
+            S3Client::new_with(dispatcher, MockCredentialsProvider, AWSRegion::EuWest2),
+            String::from("my-database-backups"),
+            String::from("backups/"),
+        )
+    }
+
+    /// Answers each request with the next of `dispatchers`, panicking, if there are more
+    /// requests than dispatchers.
+    struct SequentialDispatcher {
+        dispatchers: Vec<MockRequestDispatcher>,
+        requests: AtomicUsize,
+    }
+
+    impl SequentialDispatcher {
+
+        fn new(dispatchers: Vec<MockRequestDispatcher>) -> SequentialDispatcher {
+            SequentialDispatcher {
+                dispatchers,
+                requests: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    impl DispatchSignedRequest for SequentialDispatcher {
+        type Future = <MockRequestDispatcher as DispatchSignedRequest>::Future;
+
+        fn dispatch(&self, request: SignedRequest, timeout: Option<Duration>) -> Self::Future {
+            let index = self.requests.fetch_add(1, Ordering::SeqCst);
+
+            match self.dispatchers.get(index) {
+                Some(dispatcher) => dispatcher.dispatch(request, timeout),
+                None => panic!("Unexpected request {} {}", request.method, request.path),
+            }
+        }
+    }
+
+    fn payload(request: &SignedRequest) -> String {
+        match &request.payload {
+            Some(rusoto_core::signature::SignedRequestPayload::Buffer(payload)) => String::from_utf8(payload.to_vec()).unwrap(),
+            _ => String::new(),
+        }
+    }
+
+    fn list_body(objects: &[(&str, u64)]) -> String {
+        let contents: String = objects
+            .iter()
+            .map(|(key, size)| format!(
+                "<Contents><Key>{}</Key><LastModified>2014-07-01T00:00:00.000Z</LastModified><Size>{}</Size></Contents>",
+                key,
+                size,
+            ))
+            .collect();
+
+        format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?><ListBucketResult><IsTruncated>false</IsTruncated>{}</ListBucketResult>", contents)
+    }
+
+    fn delete_body(keys: &[&str]) -> String {
+        let deleted: String = keys.iter().map(|key| format!("<Deleted><Key>{}</Key></Deleted>", key)).collect();
+
+        format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?><DeleteResult>{}</DeleteResult>", deleted)
+    }
+
+    fn error_body(code: &str) -> String {
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><Error><Code>{}</Code><Message>Error</Message><RequestId>1</RequestId></Error>",
+            code,
+        )
+    }
+
+    #[test]
+    fn test_new() {
+        let aws_s3_client = AwsS3::new(
+            String::from("eu-west-2"),
+            String::from("my-database-backups"),
+            String::from("backups/")
+        );
+
+        assert_eq!(aws_s3_client.bucket, String::from("my-database-backups"));
+        assert_eq!(aws_s3_client.prefix, String::from("backups/"));
+    }
+
+    /// Provides the credentials of `MockCredentialsProvider`, counting how often they've
+    /// been asked for.
+    struct CountingCredentialsProvider(Arc<AtomicUsize>);
+
+    impl ProvideAwsCredentials for CountingCredentialsProvider {
+        type Future = <MockCredentialsProvider as ProvideAwsCredentials>::Future;
+
+        fn credentials(&self) -> Self::Future {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            MockCredentialsProvider.credentials()
+        }
+    }
+
+    #[test]
+    fn test_credentials_are_looked_up_per_request() {
+        let lookups = Arc::new(AtomicUsize::new(0));
+
+        // Credentials are only looked up once a request is made, so they may come from
+        // the instance metadata service later on.
+        let aws_s3_client = AwsS3::with_credentials_provider(
+            String::from("eu-west-2"),
+            String::from("my-database-backups"),
+            String::from("backups/"),
+            CountingCredentialsProvider(lookups.clone()),
+        );
+        assert_eq!(aws_s3_client.prefix, "backups/");
+        assert_eq!(lookups.load(Ordering::SeqCst), 0);
+
+        let dispatcher = MockRequestDispatcher::with_status(200).with_body(&list_body(&[("backups/1.dump", 1024)]));
+        let aws_s3_client = AwsS3::with_s3_client(
+            S3Client::new_with(dispatcher, CountingCredentialsProvider(lookups.clone()), AWSRegion::EuWest2),
+            String::from("my-database-backups"),
+            String::from("backups/"),
+        );
+        assert_eq!(aws_s3_client.stored_backups().unwrap().len(), 1);
+        assert_eq!(lookups.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_with_prefix() {
+        let dispatcher = MockRequestDispatcher::with_status(200)
+            .with_body(&list_body(&[("backups/daily/1.dump", 1024)]))
+            .with_request_checker(|request| {
+                assert_eq!(request.params.get("prefix"), Some(&Some(String::from("backups/daily/"))));
+            });
+        let aws_s3_client = mocked_client(dispatcher).with_page_size(10).with_success_sidecar(String::from(".success"));
+
+        let daily_client = aws_s3_client.with_prefix(String::from("backups/daily/"));
+
+        assert_eq!(daily_client.prefix, "backups/daily/");
+        assert_eq!(aws_s3_client.prefix, "backups/");
+        assert!(Arc::ptr_eq(&daily_client.s3_client, &aws_s3_client.s3_client));
+        assert_eq!(daily_client.page_size, Some(10));
+        assert_eq!(daily_client.success_sidecar, Some(String::from(".success")));
+        assert_eq!(daily_client.stored_backups().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_object_to_backup_file_meta() {
+        let aws_s3_client = AwsS3::new(
+            String::from("eu-west-2"),
+            String::from("my-database-backups"),
+            String::from("backups/")
+        );
+        let object = rusoto_s3::Object {
+            key: Some(String::from("backups/2014-07-01.dump")),
+            last_modified: Some(String::from("2014-07-01T00:00:00.000Z")),
+            size: Some(1024),
+            storage_class: Some(String::from("GLACIER")),
+            e_tag: Some(String::from("\"9b2cf535f27731c974343645a3985328\"")),
+            ..Default::default()
+        };
+
+        let backup_file_meta = aws_s3_client.object_to_backup_file_meta(object).unwrap();
+
+        assert_eq!(backup_file_meta.id, "backups/2014-07-01.dump");
+        assert_eq!(backup_file_meta.size, 1024);
+        assert_eq!(backup_file_meta.storage_class, Some(String::from("GLACIER")));
+        assert!(backup_file_meta.is_archived());
+        assert_eq!(backup_file_meta.etag, Some(String::from("\"9b2cf535f27731c974343645a3985328\"")));
+    }
+
+    #[test]
+    fn test_delete_backups_is_verbose() {
+        let dispatcher = MockRequestDispatcher::with_status(200)
+            .with_body(&delete_body(&["backups/2014-07-01.dump"]))
+            .with_request_checker(|request| assert!(payload(request).contains("<Quiet>false</Quiet>")));
+        let aws_s3_client = mocked_client(dispatcher);
+
+        let report = aws_s3_client.delete_backups(vec![build_meta("backups/2014-07-01.dump", Utc::now())]);
+
+        assert_eq!(report.deleted.len(), 1);
+    }
+
+    #[test]
+    fn test_delete_backups_counting_is_quiet() {
+        let dispatcher = MockRequestDispatcher::with_status(200)
+            .with_body(r#"<?xml version="1.0" encoding="UTF-8"?><DeleteResult><Error><Key>backups/2014-07-02.dump</Key><Code>InternalError</Code><Message>Error</Message></Error></DeleteResult>"#)
+            .with_request_checker(|request| assert!(payload(request).contains("<Quiet>true</Quiet>")));
+        let aws_s3_client = mocked_client(dispatcher);
+
+        let counts = aws_s3_client.delete_backups_counting(vec![
+            build_meta("backups/2014-07-01.dump", Utc::now()),
+            build_meta("backups/2014-07-02.dump", Utc::now()),
+        ]);
+
+        assert_eq!(counts, DeletionCounts {
+            deleted: 1,
+            failed: 1,
+            protected: 0,
+            remaining: 0,
+            bytes_freed: 0,
+        });
+    }
+
+    #[test]
+    fn test_deletion_report() {
+        let aws_s3_client = AwsS3::new(
+            String::from("eu-west-2"),
+            String::from("my-database-backups"),
+            String::from("backups/")
+        );
+        let backup_file_metas = ["A", "B", "C"].iter().map(|id| BackupFileMeta {
+            size: 10,
+            ..build_meta(id, Utc::now())
+        }).collect();
+        let delete_result = rusoto_s3::DeleteObjectsOutput {
+            deleted: Some(vec![rusoto_s3::DeletedObject {
+                key: Some(String::from("A")),
+                ..Default::default()
+            }]),
+            errors: Some(vec![rusoto_s3::S3Error {
+                key: Some(String::from("B")),
+                code: Some(String::from("InternalError")),
+                message: Some(String::from("We encountered an internal error. Please try again.")),
+                version_id: None,
+            }]),
+            request_charged: None,
+        };
+
+        let report = aws_s3_client.deletion_report(backup_file_metas, delete_result, false);
+
+        assert_eq!(report.deleted.len(), 1);
+        assert_eq!(report.deleted[0].id, "A");
+        assert_eq!(report.bytes_freed(), 10);
+
+        let mut failed: Vec<(String, String)> = report.failed
+            .into_iter()
+            .map(|(backup_file_meta, reason)| (backup_file_meta.id, reason))
+            .collect();
+        failed.sort();
+        assert_eq!(failed, vec![
+            (String::from("B"), String::from("We encountered an internal error. Please try again.")),
+            (String::from("C"), String::from("Not reported by S3")),
+        ]);
+    }
+
+    #[test]
+    fn test_delete_backups_when_some_are_protected_by_object_lock() {
+        let body = concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>",
+            "<DeleteResult xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\">",
+            "<Deleted><Key>backups/2014-07-01.dump</Key></Deleted>",
+            "<Error><Key>backups/2014-07-02.dump</Key><Code>AccessDenied</Code>",
+            "<Message>Access Denied because object protected by object lock.</Message></Error>",
+            "<Error><Key>backups/2014-07-03.dump</Key><Code>AccessDenied</Code>",
+            "<Message>Access Denied</Message></Error>",
+            "</DeleteResult>",
+        );
+        let aws_s3_client = mocked_client(MockRequestDispatcher::with_status(200).with_body(body));
+        let backup_file_metas = ["backups/2014-07-01.dump", "backups/2014-07-02.dump", "backups/2014-07-03.dump"]
+            .iter()
+            .map(|id| build_meta(id, Utc::now()))
+            .collect();
+
+        let report = aws_s3_client.delete_backups(backup_file_metas);
+
+        assert_eq!(report.deleted.len(), 1);
+        assert_eq!(report.deleted[0].id, "backups/2014-07-01.dump");
+        assert_eq!(report.protected.len(), 1);
+        assert_eq!(report.protected[0].id, "backups/2014-07-02.dump");
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0.id, "backups/2014-07-03.dump");
+        assert_eq!(report.to_string(), "Deleted 1 backups (0 bytes), 1 protected, 1 failed.");
+    }
+
+    #[test]
+    fn test_delete_backups_when_some_are_denied_by_kms() {
+        let body = concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>",
+            "<DeleteResult xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\">",
+            "<Error><Key>backups/2014-07-01.dump</Key><Code>KMS.AccessDeniedException</Code>",
+            "<Message>User is not authorized to perform kms:Decrypt.</Message></Error>",
+            "<Error><Key>backups/2014-07-02.dump</Key><Code>AccessDenied</Code>",
+            "<Message>Access Denied</Message></Error>",
+            "</DeleteResult>",
+        );
+        let aws_s3_client = mocked_client(MockRequestDispatcher::with_status(200).with_body(body));
+        let backup_file_metas = ["backups/2014-07-01.dump", "backups/2014-07-02.dump"]
+            .iter()
+            .map(|id| build_meta(id, Utc::now()))
+            .collect();
+
+        let report = aws_s3_client.delete_backups(backup_file_metas);
+
+        assert_eq!(report.kms_denied.len(), 1);
+        assert_eq!(report.kms_denied[0].0.id, "backups/2014-07-01.dump");
+        assert_eq!(report.kms_denied[0].1, "User is not authorized to perform kms:Decrypt.");
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0.id, "backups/2014-07-02.dump");
+        assert_eq!(report.to_string(), "Deleted 0 backups (0 bytes), 2 failed (1 denied by KMS).");
+    }
+
+    #[test]
+    fn test_delete_backups_leaves_markers_of_folders_with_backups() {
+        let aws_s3_client = mocked_client(SequentialDispatcher::new(vec![
+            MockRequestDispatcher::with_status(200).with_body(&delete_body(&["backups/daily/1.dump"])),
+            MockRequestDispatcher::with_status(200).with_body(&list_body(&[("backups/daily/", 0), ("backups/daily/2.dump", 1024)])),
+        ])).with_prune_empty_markers();
+
+        let report = aws_s3_client.delete_backups(vec![build_meta("backups/daily/1.dump", Utc::now())]);
+
+        assert_eq!(report.deleted.len(), 1);
+        assert!(report.failed.is_empty());
+    }
+
+    #[test]
+    fn test_delete_backups_leaves_markers_of_folders_with_companions_or_protected_objects() {
+        let aws_s3_client = mocked_client(SequentialDispatcher::new(vec![
+            MockRequestDispatcher::with_status(200).with_body(&delete_body(&["backups/daily/1.dump", "backups/weekly/1.dump"])),
+            MockRequestDispatcher::with_status(200).with_body(&list_body(&[
+                ("backups/daily/", 0),
+                ("backups/daily/2.dump.sha256", 64),
+                ("backups/weekly/", 0),
+                ("backups/weekly/latest.dump", 1024),
+                ("backups/monthly/", 0),
+            ])),
+        ]))
+            .with_prune_empty_markers()
+            .with_companions(vec!["sha256"])
+            .with_protected_keys(vec![String::from("backups/weekly/latest.dump"), String::from("backups/monthly/")]);
+
+        let report = aws_s3_client.delete_backups(vec![
+            build_meta("backups/daily/1.dump", Utc::now()),
+            build_meta("backups/weekly/1.dump", Utc::now()),
+        ]);
+
+        // None of the markers is empty or unprotected, so no further request is sent.
+        assert_eq!(report.deleted.len(), 2);
+        assert!(report.failed.is_empty());
+    }
+
+    #[test]
+    fn test_delete_backups_prunes_empty_markers() {
+        let aws_s3_client = mocked_client(SequentialDispatcher::new(vec![
+            MockRequestDispatcher::with_status(200).with_body(&delete_body(&["backups/daily/1.dump", "backups/daily/2.dump"])),
+            MockRequestDispatcher::with_status(200).with_body(&list_body(&[
+                ("backups/daily/", 0),
+                ("backups/monthly/", 0),
+                ("backups/monthly/1.dump", 1024),
+            ])),
+            MockRequestDispatcher::with_status(200)
+                .with_body(&delete_body(&["backups/daily/"]))
+                .with_request_checker(|request| {
+                    let payload = payload(request);
+                    assert!(payload.contains("<Key>backups/daily/</Key>"));
+                    assert!(!payload.contains("monthly"));
+                }),
+        ])).with_prune_empty_markers();
+
+        let report = aws_s3_client.delete_backups(vec![
+            build_meta("backups/daily/1.dump", Utc::now()),
+            build_meta("backups/daily/2.dump", Utc::now()),
+        ]);
+
+        // The marker isn't a backup, so it's not reported.
+        assert_eq!(report.deleted.len(), 2);
+        assert!(report.failed.is_empty());
+    }
+
+    #[test]
+    fn test_delete_backups_with_companions() {
+        let dispatcher = MockRequestDispatcher::with_status(200)
+            .with_body(&delete_body(&["backups/1.dump", "backups/1.dump.sha256", "backups/1.dump.meta"]))
+            .with_request_checker(|request| {
+                let payload = payload(request);
+                assert!(payload.contains("<Key>backups/1.dump</Key>"));
+                assert!(payload.contains("<Key>backups/1.dump.sha256</Key>"));
+                assert!(payload.contains("<Key>backups/1.dump.meta</Key>"));
+            });
+        let aws_s3_client = mocked_client(dispatcher).with_companions(vec!["sha256", "meta"]);
+
+        let report = aws_s3_client.delete_backups(vec![build_meta("backups/1.dump", Utc::now())]);
+
+        // Companions aren't reported.
+        assert_eq!(collect_ids(report.deleted), vec!["backups/1.dump"]);
+        assert!(report.failed.is_empty());
+        assert!(report.failed_companions.is_empty());
+    }
+
+    #[test]
+    fn test_delete_backups_when_a_companion_is_denied() {
+        let dispatcher = MockRequestDispatcher::with_status(200).with_body(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><DeleteResult><Deleted><Key>backups/1.dump</Key></Deleted>\
+            <Error><Key>backups/1.dump.sha256</Key><Code>AccessDenied</Code><Message>Access Denied</Message></Error>\
+            <Error><Key>backups/1.dump.meta</Key><Code>NoSuchKey</Code><Message>Not Found</Message></Error></DeleteResult>",
+        );
+        let aws_s3_client = mocked_client(dispatcher).with_companions(vec!["sha256", "meta"]);
+
+        let report = aws_s3_client.delete_backups(vec![build_meta("backups/1.dump", Utc::now())]);
+
+        assert_eq!(collect_ids(report.deleted.clone()), vec!["backups/1.dump"]);
+        assert!(report.failed.is_empty());
+        assert_eq!(report.failed_companions, vec![(String::from("backups/1.dump.sha256"), String::from("Access Denied"))]);
+        assert_eq!(report.to_string(), "Deleted 1 backups (0 bytes), 0 failed, 1 companions failed.");
+    }
+
+    #[test]
+    fn test_stored_backups_with_companions() {
+        let body = list_body(&[("backups/1.dump", 1024), ("backups/1.dump.sha256", 64), ("backups/1.dump.meta", 128)]);
+        let aws_s3_client = mocked_client(MockRequestDispatcher::with_status(200).with_body(&body))
+            .with_companions(vec![".sha256", ".meta"]);
+
+        assert_eq!(collect_ids(aws_s3_client.stored_backups().unwrap()), vec!["backups/1.dump"]);
+    }
+
+    #[test]
+    fn test_stored_backups_with_protected_keys() {
+        use crate::pruning_strategy::{PruningStrategy, KeepLastN};
+
+        let body = list_body(&[("backups/1.dump", 1024), ("backups/2.dump", 1024), ("backups/latest.dump", 1024)]);
+        let aws_s3_client = mocked_client(MockRequestDispatcher::with_status(200).with_body(&body))
+            .with_protected_keys(vec![String::from("backups/latest.dump")]);
+
+        let mut stored_backups = aws_s3_client.stored_backups().unwrap();
+        let expendable_backups = KeepLastN::new(0).expendable_backups(&mut stored_backups);
+
+        // The strategy would delete all backups, if it got to see the protected one.
+        assert_eq!(collect_ids(expendable_backups), vec!["backups/1.dump", "backups/2.dump"]);
+    }
+
+    #[test]
+    fn test_delete_backups_with_protected_keys() {
+        let aws_s3_client = mocked_client(
+            MockRequestDispatcher::with_status(200)
+                .with_body(&delete_body(&["backups/1.dump"]))
+                .with_request_checker(|request| assert!(!payload(request).contains("latest"))),
+        ).with_protected_keys(vec![String::from("backups/latest.dump")]);
+
+        let report = aws_s3_client.delete_backups(vec![
+            build_meta("backups/1.dump", Utc::now()),
+            build_meta("backups/latest.dump", Utc::now()),
+        ]);
+
+        assert_eq!(collect_ids(report.deleted), vec!["backups/1.dump"]);
+        assert_eq!(collect_ids(report.protected), vec!["backups/latest.dump"]);
+        assert!(report.failed.is_empty());
+    }
+
+    fn versions_body(versions: &[(&str, &str, u32)]) -> String {
+        let versions: String = versions
+            .iter()
+            .map(|(key, version_id, day)| format!(
+                "<Version><Key>{}</Key><VersionId>{}</VersionId><LastModified>2014-07-{:02}T00:00:00.000Z</LastModified><Size>1024</Size></Version>",
+                key,
+                version_id,
+                day,
+            ))
+            .collect();
+
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><ListVersionsResult><IsTruncated>false</IsTruncated>{}\
+            <DeleteMarker><Key>backups/db2.dump</Key><VersionId>m1</VersionId><LastModified>2014-07-04T00:00:00.000Z</LastModified></DeleteMarker>\
+            </ListVersionsResult>",
+            versions,
+        )
+    }
+
+    #[test]
+    fn test_prune_versions_per_key() {
+        use crate::pruning_strategy::{PruningStrategy, KeepLastNVersionsPerKey};
+
+        let list_dispatcher = MockRequestDispatcher::with_status(200)
+            .with_body(&versions_body(&[
+                ("backups/db1.dump", "v3", 3),
+                ("backups/db1.dump", "v2", 2),
+                ("backups/db1.dump", "v1", 1),
+                ("backups/db2.dump", "w2", 2),
+                ("backups/db2.dump", "w1", 1),
+            ]))
+            .with_request_checker(|request| assert!(request.params.contains_key("versions")));
+        let delete_dispatcher = MockRequestDispatcher::with_status(200)
+            .with_body(r#"<?xml version="1.0" encoding="UTF-8"?><DeleteResult><Deleted><Key>backups/db1.dump</Key><VersionId>v1</VersionId></Deleted></DeleteResult>"#)
+            .with_request_checker(|request| {
+                let payload = payload(request);
+                assert!(payload.contains("<Key>backups/db1.dump</Key><VersionId>v1</VersionId>"));
+                assert!(!payload.contains("v2") && !payload.contains("v3") && !payload.contains("<Key>backups/db2.dump</Key>"));
+            });
+        let aws_s3_client = mocked_client(SequentialDispatcher::new(vec![list_dispatcher, delete_dispatcher])).with_versions();
+
+        let mut stored_backups = aws_s3_client.stored_backups().unwrap();
+        let expendable_backups = KeepLastNVersionsPerKey::new(2).expendable_backups(&mut stored_backups);
+
+        // The delete marker isn't a version to keep, so db2 keeps both of its versions.
+        let versions = |backups: &[BackupFileMeta]| -> Vec<String> {
+            backups.iter().map(|backup| backup.version_id.clone().unwrap()).collect()
+        };
+        assert_eq!(versions(&expendable_backups), vec!["v1"]);
+        assert_eq!(versions(&stored_backups), vec!["w1", "v2", "w2", "v3"]);
+
+        let report = aws_s3_client.delete_backups(expendable_backups);
+        assert_eq!(report.deleted.len(), 1);
+        assert!(report.failed.is_empty());
+    }
+
+    #[test]
+    fn test_delete_backups_within_time_budget() {
+        let backup_file_metas: Vec<BackupFileMeta> = (0..AwsS3::MAX_KEYS_PER_REQUEST as i64 + 1)
+            .rev()
+            .map(|minutes| build_meta(&format!("backups/{}.dump", minutes), Utc.ymd(2014, 7, 1).and_hms(0, 0, 0) + chrono::Duration::minutes(minutes)))
+            .collect();
+        let first_batch: Vec<String> = (0..AwsS3::MAX_KEYS_PER_REQUEST).map(|minutes| format!("backups/{}.dump", minutes)).collect();
+        let dispatcher = MockRequestDispatcher::with_status(200)
+            .with_body(&delete_body(&first_batch.iter().map(String::as_str).collect::<Vec<_>>()));
+
+        // Just one request is answered, there must not be another.
+        let aws_s3_client = mocked_client(SequentialDispatcher::new(vec![dispatcher])).with_time_budget(Duration::from_secs(0));
+
+        let report = aws_s3_client.delete_backups(backup_file_metas);
+
+        assert_eq!(report.deleted.len(), AwsS3::MAX_KEYS_PER_REQUEST);
+        assert!(report.failed.is_empty());
+
+        // The newest backup is left for the next run.
+        assert_eq!(collect_ids(report.remaining), vec!["backups/1000.dump"]);
+    }
+
+    #[test]
+    fn test_estimate_api_calls_counts_the_objects_listed() {
+        let aws_s3_client = mocked_client(MockRequestDispatcher::with_status(200).with_body(&list_body(&[
+            ("backups/1.dump", 1024),
+            ("backups/1.dump.sha256", 64),
+            ("backups/2.dump", 1024),
+            ("backups/2.dump.sha256", 64),
+            ("backups/latest.dump", 1024),
+        ])))
+            .with_page_size(2)
+            .with_companions(vec!["sha256"])
+            .with_protected_keys(vec![String::from("backups/latest.dump")]);
+
+        assert_eq!(aws_s3_client.stored_backups().unwrap().len(), 2);
+
+        // All 5 objects are listed, taking 3 pages.
+        assert_eq!(aws_s3_client.estimate_api_calls(1).list_requests, 3);
+    }
+
+    #[test]
+    fn test_estimate_api_calls_with_companions() {
+        let aws_s3_client = mocked_client(MockRequestDispatcher::with_status(200))
+            .with_companions(vec!["sha256", "meta"]);
+
+        // 333 backups and their companions fit into a request.
+        assert_eq!(aws_s3_client.estimate_api_calls(333).delete_requests, 1);
+        assert_eq!(aws_s3_client.estimate_api_calls(334).delete_requests, 2);
+    }
+
+    #[test]
+    fn test_list_request() {
+        let aws_s3_client = AwsS3::new(
+            String::from("eu-west-2"),
+            String::from("my-database-backups"),
+            String::from("backups/")
+        );
+
+        let list_request = aws_s3_client.list_request(Some(String::from("token")));
+
+        assert_eq!(list_request.bucket, "my-database-backups");
+        assert_eq!(list_request.prefix, Some(String::from("backups/")));
+        assert_eq!(list_request.continuation_token, Some(String::from("token")));
+        assert_eq!(list_request.max_keys, None);
+        assert_eq!(list_request.start_after, None);
+    }
+
+    #[test]
+    fn test_list_request_with_start_after() {
+        let aws_s3_client = AwsS3::new(
+            String::from("eu-west-2"),
+            String::from("my-database-backups"),
+            String::from("backups/")
+        ).with_start_after(String::from("backups/2014-07-01.dump"));
+
+        assert_eq!(aws_s3_client.list_request(None).start_after, Some(String::from("backups/2014-07-01.dump")));
+        assert_eq!(aws_s3_client.subfolders_request(None).start_after, None);
+    }
+
+    /// Mocks a listing of two pages holding two backups each.
+    fn paginated_client() -> AwsS3 {
+        let first_page = list_body(&[("backups/1.dump", 1024), ("backups/2.dump", 1024)]).replace(
+            "<IsTruncated>false</IsTruncated>",
+            "<IsTruncated>true</IsTruncated><NextContinuationToken>2</NextContinuationToken>",
+        );
+
+        mocked_client(SequentialDispatcher::new(vec![
+            MockRequestDispatcher::with_status(200).with_body(&first_page),
+            MockRequestDispatcher::with_status(200).with_body(&list_body(&[("backups/3.dump", 1024), ("backups/4.dump", 1024)])),
+        ]))
+    }
+
+    #[test]
+    fn test_stored_backups_with_max_list() {
+        let aws_s3_client = paginated_client().with_max_list(3);
+
+        let error = aws_s3_client.stored_backups().unwrap_err();
+
+        assert_eq!(error, StorageError::TooManyObjects(String::from("my-database-backups"), 3));
+        assert_eq!(error.to_string(), "Bucket 'my-database-backups' has more than 3 objects; refusing to proceed.");
+    }
+
+    #[test]
+    fn test_stored_backups_with_max_list_of_versions() {
+        let body = versions_body(&[
+            ("backups/db1.dump", "v2", 2),
+            ("backups/db1.dump", "v1", 1),
+            ("backups/db2.dump", "w1", 1),
+        ]);
+        let versioned_client = |max| {
+            mocked_client(MockRequestDispatcher::with_status(200).with_body(&body)).with_versions().with_max_list(max)
+        };
+
+        // The delete marker is listed too.
+        assert_eq!(versioned_client(3).stored_backups().unwrap_err(), StorageError::TooManyObjects(String::from("my-database-backups"), 3));
+        assert_eq!(versioned_client(4).stored_backups().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_stored_backups_within_max_list() {
+        let aws_s3_client = paginated_client().with_max_list(4);
+
+        assert_eq!(aws_s3_client.stored_backups().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_with_mfa() {
+        let aws_s3_client = mocked_client(MockRequestDispatcher::default())
+            .with_mfa(String::from("arn:aws:iam::123456789012:mfa/admin"), String::from("123456"));
+
+        let delete_request = aws_s3_client.delete_request(&[build_meta("backups/2014-07-01.dump", Utc::now())], false);
+
+        assert_eq!(delete_request.mfa, Some(String::from("arn:aws:iam::123456789012:mfa/admin 123456")));
+    }
+
+    #[test]
+    fn test_delete_backups_with_mfa() {
+        let dispatcher = MockRequestDispatcher::with_status(200)
+            .with_body(&delete_body(&["backups/2014-07-01.dump"]))
+            .with_request_checker(|request| {
+                assert_eq!(request.headers.get("x-amz-mfa"), Some(&vec![b"arn:aws:iam::123456789012:mfa/admin 123456".to_vec()]));
+            });
+        let aws_s3_client = mocked_client(dispatcher)
+            .with_mfa(String::from("arn:aws:iam::123456789012:mfa/admin"), String::from("123456"));
+
+        let deletion_report = aws_s3_client.delete_backups(vec![build_meta("backups/2014-07-01.dump", Utc::now())]);
+
+        assert_eq!(deletion_report.deleted.len(), 1);
+    }
+
+    #[test]
+    fn test_delete_request_without_mfa() {
+        let aws_s3_client = mocked_client(MockRequestDispatcher::default());
+
+        assert_eq!(aws_s3_client.delete_request(&[build_meta("backups/2014-07-01.dump", Utc::now())], false).mfa, None);
+    }
+
+    #[test]
+    fn test_with_page_size() {
+        let aws_s3_client = AwsS3::new(
+            String::from("eu-west-2"),
+            String::from("my-database-backups"),
+            String::from("backups/")
+        ).with_page_size(100);
+
+        assert_eq!(aws_s3_client.list_request(None).max_keys, Some(100));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_with_page_size_when_the_page_size_is_too_large() {
+        AwsS3::new(
+            String::from("eu-west-2"),
+            String::from("my-database-backups"),
+            String::from("backups/")
+        ).with_page_size(1001);
+    }
+
+    #[test]
+    fn test_estimate_api_calls() {
+        let aws_s3_client = AwsS3::new(
+            String::from("eu-west-2"),
+            String::from("my-database-backups"),
+            String::from("backups/")
+        ).with_page_size(100);
+        *aws_s3_client.listed_objects.lock().unwrap() = Some(350);
+
+        assert_eq!(aws_s3_client.estimate_api_calls(2001), ApiCallEstimate {
+            list_requests: 4,
+            delete_requests: 3,
+        });
+    }
+
+    #[test]
+    fn test_estimate_api_calls_before_listing() {
+        let aws_s3_client = AwsS3::new(
+            String::from("eu-west-2"),
+            String::from("my-database-backups"),
+            String::from("backups/")
+        );
+
+        assert_eq!(aws_s3_client.estimate_api_calls(0), ApiCallEstimate {
+            list_requests: 1,
+            delete_requests: 0,
+        });
+        assert_eq!(aws_s3_client.estimate_api_calls(1000), ApiCallEstimate {
+            list_requests: 1,
+            delete_requests: 1,
+        });
+        assert_eq!(aws_s3_client.estimate_api_calls(1001), ApiCallEstimate {
+            list_requests: 2,
+            delete_requests: 2,
+        });
+    }
+
+    #[test]
+    fn test_stored_backups() {
+        let aws_s3_client = mocked_client(MockRequestDispatcher::with_status(200).with_body(r#"<?xml version="1.0" encoding="UTF-8"?>
+            <ListBucketResult>
+                <Name>my-database-backups</Name>
+                <Prefix>backups/</Prefix>
+                <KeyCount>1</KeyCount>
+                <MaxKeys>1000</MaxKeys>
+                <IsTruncated>false</IsTruncated>
+                <Contents>
+                    <Key>backups/2014-07-01.dump</Key>
+                    <LastModified>2014-07-01T00:00:00.000Z</LastModified>
+                    <Size>1024</Size>
+                    <StorageClass>STANDARD</StorageClass>
+                </Contents>
+            </ListBucketResult>"#));
+
+        let backup_file_metas = aws_s3_client.stored_backups().unwrap();
+
+        assert_eq!(backup_file_metas.len(), 1);
+        assert_eq!(backup_file_metas[0].id, "backups/2014-07-01.dump");
+    }
+
+    #[test]
+    fn test_backups_in_range() {
+        let aws_s3_client = mocked_client(MockRequestDispatcher::with_status(200).with_body(r#"<?xml version="1.0" encoding="UTF-8"?>
+            <ListBucketResult>
+                <Name>my-database-backups</Name>
+                <Prefix>backups/</Prefix>
+                <KeyCount>4</KeyCount>
+                <MaxKeys>1000</MaxKeys>
+                <IsTruncated>false</IsTruncated>
+                <Contents>
+                    <Key>backups/2014-07-01.dump</Key>
+                    <LastModified>2014-07-01T00:00:00.000Z</LastModified>
+                    <Size>1024</Size>
+                </Contents>
+                <Contents>
+                    <Key>backups/2014-07-02.dump</Key>
+                    <LastModified>2014-07-02T00:00:00.000Z</LastModified>
+                    <Size>1024</Size>
+                </Contents>
+                <Contents>
+                    <Key>backups/2014-07-02.dump.success</Key>
+                    <LastModified>2014-07-03T00:00:00.000Z</LastModified>
+                    <Size>0</Size>
+                </Contents>
+                <Contents>
+                    <Key>backups/2014-07-03.dump</Key>
+                    <LastModified>2014-07-03T00:00:00.000Z</LastModified>
+                    <Size>1024</Size>
+                </Contents>
+            </ListBucketResult>"#)).with_success_sidecar(String::from(".success"));
+
+        let backup_file_metas = aws_s3_client
+            .backups_in_range(Utc.ymd(2014, 7, 2).and_hms(0, 0, 0), Utc.ymd(2014, 7, 2).and_hms(12, 0, 0))
+            .unwrap();
+
+        // The success marker is paired, although it has been written after the range.
+        assert_eq!(backup_file_metas.len(), 1);
+        assert_eq!(backup_file_metas[0].id, "backups/2014-07-02.dump");
+        assert_eq!(backup_file_metas[0].successful, Some(true));
+    }
+
+    #[test]
+    fn test_object_contents() {
+        let dispatcher = MockRequestDispatcher::with_status(200)
+            .with_body(r#"{"backups": ["backups/2014-07-01.dump"]}"#)
+            .with_request_checker(|request| {
+                assert_eq!(request.method, "GET");
+                assert_eq!(request.path, "/my-database-backups/manifests/restore.json");
+            });
+        let aws_s3_client = mocked_client(dispatcher);
+
+        assert_eq!(aws_s3_client.object_contents("manifests/restore.json").unwrap(), r#"{"backups": ["backups/2014-07-01.dump"]}"#);
+    }
+
+    #[test]
+    fn test_object_contents_when_access_is_denied() {
+        let aws_s3_client = mocked_client(MockRequestDispatcher::with_status(403).with_body(&error_body("AccessDenied")));
+
+        assert_eq!(
+            aws_s3_client.object_contents("manifests/restore.json"),
+            Err(StorageError::AccessDenied(String::from("my-database-backups")))
+        );
+    }
+
+    #[test]
+    fn test_put_object() {
+        let dispatcher = MockRequestDispatcher::with_status(200)
+            .with_request_checker(|request| {
+                assert_eq!(request.method, "PUT");
+                assert_eq!(request.path, "/my-database-backups/receipts/2014-07-01T00-00-00Z.json");
+                assert_eq!(request.headers.get("content-type"), Some(&vec![b"application/json".to_vec()]));
+                assert_eq!(request.headers.get("content-length"), Some(&vec![b"14".to_vec()]));
+            });
+        let aws_s3_client = mocked_client(dispatcher);
+
+        assert_eq!(aws_s3_client.put_object("receipts/2014-07-01T00-00-00Z.json", String::from(r#"{"deleted":[]}"#)), Ok(()));
+    }
+
+    #[test]
+    fn test_put_object_when_access_is_denied() {
+        let aws_s3_client = mocked_client(MockRequestDispatcher::with_status(403).with_body(&error_body("AccessDenied")));
+
+        assert_eq!(
+            aws_s3_client.put_object("receipts/2014-07-01T00-00-00Z.json", String::from("{}")),
+            Err(StorageError::AccessDenied(String::from("my-database-backups")))
+        );
+    }
+
+    fn build_meta_with_etag(id: &str, etag: &str) -> BackupFileMeta {
+        BackupFileMeta {
+            etag: Some(String::from(etag)),
+            ..build_meta(id, Utc::now())
+        }
+    }
+
+    #[test]
+    fn test_verify_backups_when_a_backup_is_missing() {
+        let aws_s3_client = mocked_client(MockRequestDispatcher::with_status(404).with_body(&error_body("NoSuchKey")));
+
+        let failures = aws_s3_client.verify_backups(&[build_meta("backups/2014-07-01.dump", Utc::now())]);
+
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0.id, "backups/2014-07-01.dump");
+        assert_eq!(failures[0].1, IntegrityFailure::Missing);
+    }
+
+    #[test]
+    fn test_verify_backups_when_a_checksum_does_not_match() {
+        let aws_s3_client = mocked_client(MockRequestDispatcher::with_status(200).with_header("ETag", "\"b\""));
+
+        let failures = aws_s3_client.verify_backups(&[
+            build_meta_with_etag("backups/2014-07-01.dump", "\"a\""),
+            build_meta_with_etag("backups/2014-07-02.dump", "\"b\""),
+            build_meta("backups/2014-07-03.dump", Utc::now()),
+        ]);
+
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0.id, "backups/2014-07-01.dump");
+        assert_eq!(failures[0].1, IntegrityFailure::ChecksumMismatch {
+            expected: String::from("\"a\""),
+            actual: Some(String::from("\"b\"")),
+        });
+    }
+
+    #[test]
+    fn test_stored_backups_with_retain_until_metadata() {
+        use crate::pruning_strategy::{PruningStrategy, KeepLastN, RespectRetainUntil};
+
+        let aws_s3_client = mocked_client(SequentialDispatcher::new(vec![
+            MockRequestDispatcher::with_status(200).with_body(&list_body(&[("backups/2014-07-01.dump", 1)])),
+            MockRequestDispatcher::with_status(200)
+                .with_header("x-amz-meta-retain-until", "2099-01-01T00:00:00Z")
+                .with_request_checker(|request| assert_eq!(request.method, "HEAD")),
+        ])).with_retain_until_metadata(String::from("Retain-Until"));
+
+        let mut backups = aws_s3_client.stored_backups().unwrap();
+        assert_eq!(backups[0].retain_until, Some(Utc.ymd(2099, 1, 1).and_hms(0, 0, 0)));
+
+        // Everything would be expendable, but the uploader asked to retain the backup.
+        let strategy = RespectRetainUntil::new(Box::new(KeepLastN::new(0)), Utc::now());
+        assert!(strategy.expendable_backups(&mut backups).is_empty());
+        assert_eq!(backups.len(), 1);
+    }
+
+    #[test]
+    fn test_stored_backups_with_an_invalid_retain_until() {
+        let aws_s3_client = mocked_client(SequentialDispatcher::new(vec![
+            MockRequestDispatcher::with_status(200).with_body(&list_body(&[("backups/2014-07-01.dump", 1)])),
+            MockRequestDispatcher::with_status(200).with_header("x-amz-meta-retain-until", "forever"),
+        ])).with_retain_until_metadata(String::from("retain-until"));
+
+        match aws_s3_client.stored_backups() {
+            Err(StorageError::Request(reason)) => assert!(reason.contains("'forever'")),
+            result => panic!("Listing should fail, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_stored_backups_without_retain_until_metadata() {
+        let aws_s3_client = mocked_client(SequentialDispatcher::new(vec![
+            MockRequestDispatcher::with_status(200).with_body(&list_body(&[("backups/2014-07-01.dump", 1)])),
+        ]));
+
+        // No metadata is requested.
+        assert_eq!(aws_s3_client.stored_backups().unwrap()[0].retain_until, None);
+    }
+
+    #[test]
+    fn test_stored_backups_with_tier_tag() {
+        let tagging_body = concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><Tagging><TagSet>",
+            "<Tag><Key>owner</Key><Value>db</Value></Tag>",
+            "<Tag><Key>backup-tier</Key><Value>monthly</Value></Tag>",
+            "</TagSet></Tagging>",
+        );
+        let aws_s3_client = mocked_client(SequentialDispatcher::new(vec![
+            MockRequestDispatcher::with_status(200).with_body(&list_body(&[("backups/2014-07-01.dump", 1)])),
+            MockRequestDispatcher::with_status(200)
+                .with_body(tagging_body)
+                .with_request_checker(|request| assert!(request.params.contains_key("tagging"))),
+        ])).with_tier_tag(String::from("backup-tier"));
+
+        assert_eq!(aws_s3_client.stored_backups().unwrap()[0].tier, Some(String::from("monthly")));
+    }
+
+    #[test]
+    fn test_stored_backups_with_tier_tag_when_a_backup_is_untagged() {
+        let aws_s3_client = mocked_client(SequentialDispatcher::new(vec![
+            MockRequestDispatcher::with_status(200).with_body(&list_body(&[("backups/2014-07-01.dump", 1)])),
+            MockRequestDispatcher::with_status(200)
+                .with_body("<?xml version=\"1.0\" encoding=\"UTF-8\"?><Tagging><TagSet></TagSet></Tagging>"),
+        ])).with_tier_tag(String::from("backup-tier"));
+
+        assert_eq!(aws_s3_client.stored_backups().unwrap()[0].tier, None);
+    }
+
+    #[test]
+    fn test_parse_object_timestamp() {
+        assert_eq!(
+            parse_object_timestamp("backups/1.dump", "2014-07-01T00:00:00.000Z"),
+            Ok(Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+        );
+
+        let message = parse_object_timestamp("backups/1.dump", "Tue, 01 Jul 2014 00:00:00 GMT").unwrap_err().to_string();
+        assert!(message.contains("backups/1.dump"));
+        assert!(message.contains("'Tue, 01 Jul 2014 00:00:00 GMT'"));
+    }
+
+    #[test]
+    fn test_stored_backups_with_an_invalid_timestamp() {
+        let body = concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><ListBucketResult><IsTruncated>false</IsTruncated>",
+            "<Contents><Key>backups/1.dump</Key><LastModified>yesterday</LastModified><Size>1</Size></Contents>",
+            "</ListBucketResult>",
+        );
+        let aws_s3_client = mocked_client(MockRequestDispatcher::with_status(200).with_body(body));
+
+        match aws_s3_client.stored_backups() {
+            Err(StorageError::Request(reason)) => assert!(reason.contains("'yesterday' of backups/1.dump")),
+            result => panic!("Listing should fail, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_parse_retain_until() {
+        assert_eq!(parse_retain_until("2014-07-12T01:02:03+02:00"), Some(Utc.ymd(2014, 7, 11).and_hms(23, 2, 3)));
+        assert_eq!(parse_retain_until("2014-07-12"), Some(Utc.ymd(2014, 7, 12).and_hms(0, 0, 0)));
+        assert_eq!(parse_retain_until("next year"), None);
+    }
+
+    #[test]
+    fn test_pair_with_success_sidecars() {
+        let aws_s3_client = AwsS3::new(
+            String::from("eu-west-2"),
+            String::from("my-database-backups"),
+            String::from("backups/")
+        ).with_success_sidecar(String::from(".success"));
+        let backup_file_metas = vec![
+            build_meta("backups/2014-07-01.dump", Utc::now()),
+            build_meta("backups/2014-07-01.dump.success", Utc::now()),
+            build_meta("backups/2014-07-02.dump", Utc::now()),
+            build_meta("backups/2014-07-03.dump", Utc::now()),
+            build_meta("backups/2014-07-03.dump.success", Utc::now()),
+        ];
+
+        let backup_file_metas: Vec<(String, Option<bool>)> = aws_s3_client
+            .pair_with_success_sidecars(backup_file_metas)
+            .into_iter()
+            .map(|backup_file_meta| (backup_file_meta.id, backup_file_meta.successful))
+            .collect();
+
+        assert_eq!(backup_file_metas, vec![
+            (String::from("backups/2014-07-01.dump"), Some(true)),
+            (String::from("backups/2014-07-02.dump"), Some(false)),
+            (String::from("backups/2014-07-03.dump"), Some(true)),
+        ]);
+    }
+
+    #[test]
+    fn test_pair_with_success_sidecars_without_a_suffix() {
+        let aws_s3_client = AwsS3::new(
+            String::from("eu-west-2"),
+            String::from("my-database-backups"),
+            String::from("backups/")
+        );
+        let backup_file_metas = vec![
+            build_meta("backups/2014-07-01.dump", Utc::now()),
+            build_meta("backups/2014-07-01.dump.success", Utc::now()),
+        ];
+
+        let backup_file_metas = aws_s3_client.pair_with_success_sidecars(backup_file_metas);
+
+        assert_eq!(backup_file_metas.len(), 2);
+        assert!(backup_file_metas.iter().all(|backup_file_meta| backup_file_meta.successful.is_none()));
+    }
+
+    #[test]
+    fn test_subfolders() {
+        let aws_s3_client = mocked_client(MockRequestDispatcher::with_status(200)
+            .with_request_checker(|request| {
+                assert_eq!(request.params.get("delimiter"), Some(&Some(String::from("/"))));
+                assert_eq!(request.params.get("prefix"), Some(&Some(String::from("backups/"))));
+            })
+            .with_body(r#"<?xml version="1.0" encoding="UTF-8"?>
+                <ListBucketResult>
+                    <Name>my-database-backups</Name>
+                    <Prefix>backups/</Prefix>
+                    <Delimiter>/</Delimiter>
+                    <KeyCount>3</KeyCount>
+                    <MaxKeys>1000</MaxKeys>
+                    <IsTruncated>false</IsTruncated>
+                    <CommonPrefixes><Prefix>backups/billing/</Prefix></CommonPrefixes>
+                    <CommonPrefixes><Prefix>backups/customers/</Prefix></CommonPrefixes>
+                    <CommonPrefixes><Prefix>backups/inventory/</Prefix></CommonPrefixes>
+                </ListBucketResult>"#));
+
+        assert_eq!(aws_s3_client.subfolders().unwrap(), vec![
+            String::from("backups/billing/"),
+            String::from("backups/customers/"),
+            String::from("backups/inventory/"),
+        ]);
+    }
+
+    #[test]
+    fn test_stored_backups_when_the_bucket_does_not_exist() {
+        let aws_s3_client = mocked_client(MockRequestDispatcher::with_status(404).with_body(&error_body("NoSuchBucket")));
+
+        assert_eq!(
+            aws_s3_client.stored_backups().unwrap_err(),
+            StorageError::BucketNotFound(String::from("my-database-backups")),
+        );
+    }
+
+    #[test]
+    fn test_stored_backups_when_access_is_denied() {
+        let aws_s3_client = mocked_client(MockRequestDispatcher::with_status(403).with_body(&error_body("AccessDenied")));
+
+        assert_eq!(
+            aws_s3_client.stored_backups().unwrap_err(),
+            StorageError::AccessDenied(String::from("my-database-backups")),
+        );
+    }
+
+    #[test]
+    fn test_stored_backups_when_the_host_is_unreachable() {
+        let aws_s3_client = mocked_client(MockRequestDispatcher::with_dispatch_error(
+            rusoto_core::request::HttpDispatchError::new(String::from("Connection refused"))
+        ));
+
+        assert_eq!(
+            aws_s3_client.stored_backups().unwrap_err(),
+            StorageError::Network(String::from("Connection refused")),
+        );
+    }
+
+    #[test]
+    fn test_stored_backups_with_another_error() {
+        let aws_s3_client = mocked_client(MockRequestDispatcher::with_status(500).with_body(&error_body("InternalError")));
+
+        match aws_s3_client.stored_backups() {
+            Err(StorageError::Request(_)) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_delete_backups_with_tag_instead_of_delete() {
+        let dispatcher = MockRequestDispatcher::with_status(200).with_request_checker(|request| {
+            assert_eq!(request.method, "PUT");
+            assert_eq!(request.path, "/my-database-backups/backups/2014-07-01.dump");
+            assert!(request.params.contains_key("tagging"));
+
+            let payload = match &request.payload {
+                Some(rusoto_core::signature::SignedRequestPayload::Buffer(payload)) => String::from_utf8(payload.to_vec()).unwrap(),
+                _ => panic!("The tags are missing."),
+            };
+            assert!(payload.contains("<Key>lifecycle</Key><Value>expired</Value>"));
+        });
+        let aws_s3_client = mocked_client(dispatcher)
+            .with_tag_instead_of_delete(String::from("lifecycle"), String::from("expired"));
+
+        let report = aws_s3_client.delete_backups(vec![build_meta("backups/2014-07-01.dump", Utc::now())]);
+
+        assert_eq!(report.tagged.len(), 1);
+        assert!(report.deleted.is_empty());
+        assert!(report.failed.is_empty());
+    }
+
+    #[test]
+    fn test_detect_region() {
+        let s3_client = S3Client::new_with(
+            MockRequestDispatcher::with_status(200).with_body(r#"<?xml version="1.0" encoding="UTF-8"?>
+                <LocationConstraint xmlns="http://s3.amazonaws.com/doc/2006-03-01/">eu-west-2</LocationConstraint>"#),
+            MockCredentialsProvider,
+            AWSRegion::UsEast1,
+        );
+
+        assert_eq!(detect_region(&s3_client, "my-database-backups"), Ok(AWSRegion::EuWest2));
+    }
+
+    #[test]
+    fn test_detect_region_when_the_bucket_does_not_exist() {
+        let s3_client = S3Client::new_with(
+            MockRequestDispatcher::with_status(404).with_body(&error_body("NoSuchBucket")),
+            MockCredentialsProvider,
+            AWSRegion::UsEast1,
+        );
+
+        assert_eq!(
+            detect_region(&s3_client, "my-database-backups"),
+            Err(StorageError::BucketNotFound(String::from("my-database-backups"))),
+        );
+    }
+
+    #[test]
+    fn test_region_from_location_constraint() {
+        assert_eq!(region_from_location_constraint(None), Ok(AWSRegion::UsEast1));
+        assert_eq!(region_from_location_constraint(Some(String::new())), Ok(AWSRegion::UsEast1));
+        assert_eq!(region_from_location_constraint(Some(String::from("EU"))), Ok(AWSRegion::EuWest1));
+        assert_eq!(region_from_location_constraint(Some(String::from("ap-south-1"))), Ok(AWSRegion::ApSouth1));
+        assert!(region_from_location_constraint(Some(String::from("moon-1"))).is_err());
     }
 
     #[test]
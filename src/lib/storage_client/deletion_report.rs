@@ -0,0 +1,160 @@
+use super::BackupFileMeta;
+use std::fmt;
+
+/// Outcome of deleting a set of backups.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DeletionReport {
+
+    /// Backups that have successfully been deleted.
+    pub deleted: Vec<BackupFileMeta>,
+
+    /// Backups that could not be deleted, along with the reason.
+    pub failed: Vec<(BackupFileMeta, String)>,
+
+    /// Backups that have been tagged as expired instead of being deleted, if the client
+    /// has been configured so.
+    pub tagged: Vec<BackupFileMeta>,
+
+    /// Backups that have not been deleted, because they're retained by e.g. S3 Object
+    /// Lock. Unlike failures, these are expected and won't be deletable until the
+    /// retention ends.
+    pub protected: Vec<BackupFileMeta>,
+
+    /// Backups that could not be deleted, because the policy of the KMS key they're
+    /// encrypted with denies it, along with the reason. Fixing that takes changing the
+    /// key policy rather than the bucket policy.
+    pub kms_denied: Vec<(BackupFileMeta, String)>,
+
+    /// Backups that haven't been tried to be deleted, because the time budget of the run
+    /// has run out (see `AwsS3::with_time_budget`). They're left for the next run.
+    pub remaining: Vec<BackupFileMeta>,
+
+    /// Keys of the companions (see `AwsS3::with_companions`) that could not be deleted,
+    /// along with the reason. Their backups are reported on their own.
+    pub failed_companions: Vec<(String, String)>,
+}
+
+/// Numbers of backups a deletion has handled, for when it doesn't matter which ones.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DeletionCounts {
+    pub deleted: usize,
+    pub failed: usize,
+    pub protected: usize,
+    pub remaining: usize,
+
+    /// Total size of the deleted backups in bytes.
+    pub bytes_freed: u64,
+}
+
+impl From<&DeletionReport> for DeletionCounts {
+
+    fn from(report: &DeletionReport) -> DeletionCounts {
+        DeletionCounts {
+            deleted: report.deleted.len(),
+            failed: report.failed_count(),
+            protected: report.protected.len(),
+            remaining: report.remaining.len(),
+            bytes_freed: report.bytes_freed(),
+        }
+    }
+}
+
+impl DeletionReport {
+
+    /// Moves all entries of `other` into `self`.
+    pub fn append(&mut self, mut other: DeletionReport) {
+        self.deleted.append(&mut other.deleted);
+        self.failed.append(&mut other.failed);
+        self.tagged.append(&mut other.tagged);
+        self.protected.append(&mut other.protected);
+        self.kms_denied.append(&mut other.kms_denied);
+        self.remaining.append(&mut other.remaining);
+        self.failed_companions.append(&mut other.failed_companions);
+    }
+
+    /// Returns the number of backups that have been deleted.
+    pub fn deleted_count(&self) -> usize {
+        self.deleted.len()
+    }
+
+    /// Returns the number of backups that could not be deleted, including the ones
+    /// denied by KMS, but not counting protected ones.
+    pub fn failed_count(&self) -> usize {
+        self.failed.len() + self.kms_denied.len()
+    }
+
+    /// Returns the total size of all deleted backups in bytes.
+    pub fn bytes_freed(&self) -> u64 {
+        self.deleted.iter().map(|backup| backup.size).sum()
+    }
+}
+
+impl fmt::Display for DeletionReport {
+
+    /// Summarizes the report in one line, e.g. `Deleted 3 backups (1024 bytes), 1 failed.`
+    /// Tagged, protected, KMS denied and remaining backups, as well as failed companions,
+    /// are only mentioned, if there are any.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Deleted {} backups ({} bytes)", self.deleted_count(), self.bytes_freed())?;
+
+        if !self.tagged.is_empty() {
+            write!(f, ", tagged {}", self.tagged.len())?;
+        }
+        if !self.protected.is_empty() {
+            write!(f, ", {} protected", self.protected.len())?;
+        }
+
+        write!(f, ", {} failed", self.failed_count())?;
+
+        if !self.kms_denied.is_empty() {
+            write!(f, " ({} denied by KMS)", self.kms_denied.len())?;
+        }
+        if !self.remaining.is_empty() {
+            write!(f, ", {} left for the next run", self.remaining.len())?;
+        }
+        if !self.failed_companions.is_empty() {
+            write!(f, ", {} companions failed", self.failed_companions.len())?;
+        }
+
+        write!(f, ".")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pruning_strategy::tests::build_meta;
+    use chrono::Utc;
+
+    fn build_meta_with_size(id: &str, size: u64) -> BackupFileMeta {
+        BackupFileMeta {
+            size,
+            ..build_meta(id, Utc::now())
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        let mut report = DeletionReport {
+            deleted: vec![build_meta_with_size("A", 1000), build_meta_with_size("B", 24)],
+            failed: vec![(build_meta_with_size("C", 1), String::from("Access Denied"))],
+            ..Default::default()
+        };
+
+        assert_eq!(report.deleted_count(), 2);
+        assert_eq!(report.failed_count(), 1);
+        assert_eq!(report.to_string(), "Deleted 2 backups (1024 bytes), 1 failed.");
+
+        report.protected.push(build_meta_with_size("D", 1));
+        assert_eq!(report.to_string(), "Deleted 2 backups (1024 bytes), 1 protected, 1 failed.");
+        assert_eq!(DeletionReport::default().to_string(), "Deleted 0 backups (0 bytes), 0 failed.");
+
+        report.kms_denied.push((build_meta_with_size("E", 1), String::from("KMS.AccessDeniedException")));
+        assert_eq!(report.failed_count(), 2);
+        assert_eq!(report.to_string(), "Deleted 2 backups (1024 bytes), 1 protected, 2 failed (1 denied by KMS).");
+
+        report.remaining.push(build_meta_with_size("F", 1));
+        assert_eq!(report.to_string(), "Deleted 2 backups (1024 bytes), 1 protected, 2 failed (1 denied by KMS), 1 left for the next run.");
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+use super::{StorageClient, BackupFileMeta, DeletionReport, StorageError};
+
+/// A client that keeps its backups in memory. Comes in handy for trying out
+/// strategies and for testing.
+pub struct InMemory {
+    backups: Mutex<Vec<BackupFileMeta>>,
+    undeletable_ids: HashSet<String>,
+}
+
+impl InMemory {
+
+    pub fn new(backups: Vec<BackupFileMeta>) -> InMemory {
+        InMemory {
+            backups: Mutex::new(backups),
+            undeletable_ids: HashSet::new(),
+        }
+    }
+
+    /// Fails to delete the backups with the given `ids`, as if access to them was
+    /// denied, e.g. to try out how partial failures are handled.
+    pub fn with_undeletable(mut self, ids: Vec<String>) -> InMemory {
+        self.undeletable_ids = ids.into_iter().collect();
+        self
+    }
+}
+
+impl StorageClient for InMemory {
+
+    fn stored_backups(&self) -> Result<Vec<BackupFileMeta>, StorageError> {
+        Ok(self.backups.lock().unwrap().clone())
+    }
+
+    fn delete_backups(&self, backup_file_metas: Vec<BackupFileMeta>) -> DeletionReport {
+        let mut backups = self.backups.lock().unwrap();
+        let mut report = DeletionReport::default();
+
+        for backup_file_meta in backup_file_metas {
+            if self.undeletable_ids.contains(&backup_file_meta.id) {
+                report.failed.push((backup_file_meta, String::from("Access Denied")));
+                continue;
+            }
+
+            match backups.iter().position(|backup| backup.id == backup_file_meta.id) {
+                Some(index) => report.deleted.push(backups.remove(index)),
+                None => report.failed.push((backup_file_meta, String::from("No such backup"))),
+            }
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pruning_strategy::tests::{build_meta, collect_ids, as_vector};
+    use chrono::Utc;
+    use chrono::offset::TimeZone;
+
+    #[test]
+    fn test_delete_backups() {
+        let client = InMemory::new(vec![
+            build_meta("A", Utc::now()),
+            build_meta("B", Utc::now()),
+        ]);
+
+        let report = client.delete_backups(vec![build_meta("A", Utc::now()), build_meta("C", Utc::now())]);
+
+        assert_eq!(collect_ids(report.deleted), as_vector("A"));
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0.id, "C");
+        assert_eq!(collect_ids(client.stored_backups().unwrap()), as_vector("B"));
+    }
+
+    #[test]
+    fn test_stored_backups_iter() {
+        let client = InMemory::new(vec![
+            build_meta("A", Utc::now()),
+            build_meta("B", Utc::now()),
+            build_meta("C", Utc::now()),
+        ]);
+
+        let mut backups = client.stored_backups_iter();
+
+        assert_eq!(backups.next().unwrap().unwrap().id, "A");
+
+        let rest: Vec<BackupFileMeta> = backups.collect::<Result<_, _>>().unwrap();
+        assert_eq!(collect_ids(rest), as_vector("BC"));
+    }
+
+    #[test]
+    fn test_backups_in_range() {
+        let client = InMemory::new(vec![
+            build_meta("A", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("B", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+            build_meta("C", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0)),
+            build_meta("D", Utc.ymd(2014, 7, 4).and_hms(0, 0, 0)),
+        ]);
+
+        let backups = client.backups_in_range(Utc.ymd(2014, 7, 2).and_hms(0, 0, 0), Utc.ymd(2014, 7, 3).and_hms(0, 0, 0));
+
+        assert_eq!(collect_ids(backups.unwrap()), as_vector("BC"));
+    }
+
+    #[test]
+    fn test_stored_backups_with() {
+        let client = InMemory::new(vec![
+            build_meta("backups/2014-07-03.dump", Utc.ymd(2014, 7, 1).and_hms(0, 0, 0)),
+            build_meta("backups/2014-07-02.dump", Utc.ymd(2014, 7, 2).and_hms(0, 0, 0)),
+            build_meta("backups/notes.txt", Utc.ymd(2014, 7, 3).and_hms(0, 0, 0)),
+        ]);
+
+        // Takes the date from the name, dropping everything not named by date.
+        let backups = client.stored_backups_with(&mut |backup| {
+            let name = backup.id.strip_prefix("backups/")?.strip_suffix(".dump")?;
+            let date = Utc.datetime_from_str(&format!("{} 00:00:00", name), "%Y-%m-%d %H:%M:%S").ok()?;
+
+            Some(BackupFileMeta { date, ..backup })
+        });
+
+        let backups = backups.unwrap();
+        assert_eq!(collect_ids(backups.clone()), vec!["backups/2014-07-02.dump", "backups/2014-07-03.dump"]);
+        assert_eq!(backups[1].date, Utc.ymd(2014, 7, 3).and_hms(0, 0, 0));
+    }
+}
@@ -0,0 +1,280 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use super::{StorageClient, BackupFileMeta, DeletionReport, StorageError};
+
+/// A client for backups stored as files in a local directory, including its
+/// subdirectories, but not the ones linked to symbolically.
+///
+/// Each backup's `id` is its canonical absolute path, so it can be turned back into a
+/// path for deletion as is. On Windows, that's the extended-length form (e.g.
+/// `\\?\C:\backups\2014-07-01.dump`), which isn't limited to 260 characters. The
+/// `human_readable_id` is the path relative to the directory. The date of a backup is
+/// the time it has last been modified.
+pub struct LocalFilesystem {
+    directory: PathBuf,
+}
+
+impl LocalFilesystem {
+
+    pub fn new(directory: PathBuf) -> LocalFilesystem {
+        LocalFilesystem {
+            directory,
+        }
+    }
+
+    fn storage_error(&self, error: io::Error) -> StorageError {
+        let directory = self.directory.display().to_string();
+
+        match error.kind() {
+            io::ErrorKind::NotFound => StorageError::BucketNotFound(directory),
+            io::ErrorKind::PermissionDenied => StorageError::AccessDenied(directory),
+            _ => StorageError::Request(error.to_string()),
+        }
+    }
+
+    /// Appends all files within `directory` and its subdirectories to `backup_file_metas`.
+    /// Symbolic links to files are followed, the ones to directories are skipped.
+    fn list_directory(&self, root: &Path, directory: &Path, backup_file_metas: &mut Vec<BackupFileMeta>) -> Result<(), StorageError> {
+        for entry in fs::read_dir(directory).map_err(|error| self.storage_error(error))? {
+            let entry = entry.map_err(|error| self.storage_error(error))?;
+            let is_symlink = entry.file_type().map_err(|error| self.storage_error(error))?.is_symlink();
+            let path = entry.path();
+            let metadata = fs::metadata(&path).map_err(|error| self.storage_error(error))?;
+
+            if metadata.is_dir() {
+
+                // Following them could recurse forever, e.g. for a link to `..`.
+                if !is_symlink {
+                    self.list_directory(root, &path, backup_file_metas)?;
+                }
+                continue;
+            }
+
+            let id = path.to_str()
+                .ok_or_else(|| StorageError::Request(format!("{} is not valid UTF-8", path.display())))?;
+            let human_readable_id = path.strip_prefix(root).unwrap_or(&path);
+            let modified = metadata.modified().map_err(|error| self.storage_error(error))?;
+
+            backup_file_metas.push(BackupFileMeta {
+                id: String::from(id),
+                human_readable_id: human_readable_id.display().to_string(),
+                date: DateTime::<Utc>::from(modified),
+                size: metadata.len(),
+                storage_class: None,
+                etag: None,
+                successful: None,
+                retain_until: None,
+                tier: None,
+                version_id: None,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl StorageClient for LocalFilesystem {
+
+    fn stored_backups(&self) -> Result<Vec<BackupFileMeta>, StorageError> {
+        let root = fs::canonicalize(&self.directory).map_err(|error| self.storage_error(error))?;
+        let mut backup_file_metas = vec![];
+
+        self.list_directory(&root, &root, &mut backup_file_metas)?;
+
+        Ok(backup_file_metas)
+    }
+
+    fn delete_backups(&self, backup_file_metas: Vec<BackupFileMeta>) -> DeletionReport {
+        let mut report = DeletionReport::default();
+        let root = match fs::canonicalize(&self.directory) {
+            Ok(root) => root,
+            Err(error) => {
+                let reason = error.to_string();
+                report.failed.extend(backup_file_metas.into_iter().map(|backup_file_meta| (backup_file_meta, reason.clone())));
+                return report;
+            },
+        };
+
+        for backup_file_meta in backup_file_metas {
+            let path = Path::new(&backup_file_meta.id);
+
+            // Never delete anything outside of the directory, whatever the id says.
+            if !path.starts_with(&root) {
+                report.failed.push((backup_file_meta, format!("Not within {}", root.display())));
+                continue;
+            }
+
+            match fs::remove_file(path) {
+                Ok(()) => report.deleted.push(backup_file_meta),
+                Err(error) => report.failed.push((backup_file_meta, error.to_string())),
+            }
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pruning_strategy::{PruningStrategy, KeepLastN};
+    use std::process;
+
+    /// A directory that is removed, once the test is done.
+    struct TemporaryDirectory {
+        path: PathBuf,
+    }
+
+    impl TemporaryDirectory {
+
+        fn new(name: &str) -> TemporaryDirectory {
+            let path = std::env::temp_dir().join(format!("backups_cleaner-{}-{}", name, process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+
+            TemporaryDirectory {
+                path,
+            }
+        }
+
+        fn create_file(&self, relative_path: &Path, contents: &str) {
+            let path = self.path.join(relative_path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+    }
+
+    impl Drop for TemporaryDirectory {
+
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+
+    fn human_readable_ids(backup_file_metas: &[BackupFileMeta]) -> Vec<String> {
+        let mut ids: Vec<String> = backup_file_metas
+            .iter()
+            .map(|backup_file_meta| backup_file_meta.human_readable_id.clone())
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    fn relative(components: &[&str]) -> PathBuf {
+        components.iter().collect()
+    }
+
+    #[test]
+    fn test_stored_backups() {
+        let directory = TemporaryDirectory::new("stored_backups");
+        directory.create_file(&relative(&["a.dump"]), "a");
+        directory.create_file(&relative(&["nested", "b.dump"]), "bb");
+        directory.create_file(&relative(&["nested", "deeper", "c.dump"]), "ccc");
+        let client = LocalFilesystem::new(directory.path.clone());
+
+        let backup_file_metas = client.stored_backups().unwrap();
+
+        assert_eq!(human_readable_ids(&backup_file_metas), vec![
+            relative(&["a.dump"]).display().to_string(),
+            relative(&["nested", "b.dump"]).display().to_string(),
+            relative(&["nested", "deeper", "c.dump"]).display().to_string(),
+        ]);
+        assert_eq!(backup_file_metas.iter().map(|backup_file_meta| backup_file_meta.size).sum::<u64>(), 6);
+        assert!(backup_file_metas.iter().all(|backup_file_meta| Path::new(&backup_file_meta.id).is_file()));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_stored_backups_with_a_symlink_loop() {
+        let directory = TemporaryDirectory::new("symlink_loop");
+        directory.create_file(&relative(&["nested", "b.dump"]), "b");
+        std::os::unix::fs::symlink("..", directory.path.join("nested").join("loop")).unwrap();
+        let client = LocalFilesystem::new(directory.path.clone());
+
+        let backup_file_metas = client.stored_backups().unwrap();
+
+        assert_eq!(human_readable_ids(&backup_file_metas), vec!["nested/b.dump"]);
+    }
+
+    #[test]
+    fn test_stored_backups_when_the_directory_does_not_exist() {
+        let client = LocalFilesystem::new(std::env::temp_dir().join("backups_cleaner-does-not-exist"));
+
+        match client.stored_backups() {
+            Err(StorageError::BucketNotFound(_)) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_prune() {
+        let directory = TemporaryDirectory::new("prune");
+        directory.create_file(&relative(&["a.dump"]), "a");
+        directory.create_file(&relative(&["nested", "b.dump"]), "b");
+        let client = LocalFilesystem::new(directory.path.clone());
+
+        let mut backup_file_metas = client.stored_backups().unwrap();
+        let expendable_backups = KeepLastN::new(0).expendable_backups(&mut backup_file_metas);
+        let report = client.delete_backups(expendable_backups);
+
+        assert_eq!(report.deleted.len(), 2);
+        assert!(report.failed.is_empty());
+        assert!(client.stored_backups().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_delete_backups_outside_of_the_directory() {
+        let directory = TemporaryDirectory::new("outside");
+        directory.create_file(&relative(&["backups", "a.dump"]), "a");
+        directory.create_file(&relative(&["other", "b.dump"]), "b");
+        let client = LocalFilesystem::new(directory.path.join("backups"));
+        let outside = fs::canonicalize(directory.path.join("other").join("b.dump")).unwrap();
+
+        let report = client.delete_backups(vec![BackupFileMeta {
+            id: outside.to_str().unwrap().to_string(),
+            ..crate::pruning_strategy::tests::build_meta("b", Utc::now())
+        }]);
+
+        assert_eq!(report.failed.len(), 1);
+        assert!(outside.is_file());
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn test_stored_backups_with_long_paths_on_windows() {
+        let directory = TemporaryDirectory::new("long_paths");
+        let long_name = "a".repeat(100);
+        let nested = relative(&[&long_name, &long_name, &long_name, "c.dump"]);
+
+        // Longer than the 260 characters Windows paths are normally limited to.
+        let full_path = fs::canonicalize(&directory.path).unwrap().join(&nested);
+        assert!(full_path.as_os_str().len() > 260);
+        directory.create_file(&nested, "c");
+
+        let client = LocalFilesystem::new(directory.path.clone());
+        let backup_file_metas = client.stored_backups().unwrap();
+
+        assert_eq!(backup_file_metas.len(), 1);
+        assert!(backup_file_metas[0].id.starts_with(r"\\?\"));
+        assert_eq!(backup_file_metas[0].human_readable_id, nested.display().to_string());
+
+        let report = client.delete_backups(backup_file_metas);
+        assert_eq!(report.deleted.len(), 1);
+        assert!(!full_path.exists());
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn test_stored_backups_uses_backslashes_on_windows() {
+        let directory = TemporaryDirectory::new("backslashes");
+        directory.create_file(&relative(&["nested", "b.dump"]), "b");
+        let client = LocalFilesystem::new(directory.path.clone());
+
+        let backup_file_metas = client.stored_backups().unwrap();
+
+        assert_eq!(backup_file_metas[0].human_readable_id, r"nested\b.dump");
+        assert!(!backup_file_metas[0].id.contains('/'));
+    }
+}
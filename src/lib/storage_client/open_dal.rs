@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::time::SystemTime;
+use chrono::{DateTime, Utc};
+use opendal::{Entry, ErrorKind, Operator};
+use tokio::runtime::Runtime;
+use super::{StorageClient, BackupFileMeta, DeletionReport, StorageError};
+
+/// A client for backups stored on any service the `opendal` crate supports (e.g. GCS,
+/// Azure Blob Storage, WebDAV, S3 or a local directory), chosen by the `scheme` of the
+/// service and configured by its `options` (e.g. `bucket` and `root`), see
+/// <https://opendal.apache.org/docs/rust/opendal/services/>.
+///
+/// Each backup's `id` and `human_readable_id` is its path relative to the configured
+/// `root`. The date of a backup is the time it has last been modified. Services that
+/// don't tell (e.g. `memory`) have their backups dated by when they've been listed, so
+/// strategies based on age keep them. Use `stored_backups_with` to date them by name
+/// instead.
+pub struct OpenDal {
+    operator: Operator,
+    runtime: Runtime,
+}
+
+impl OpenDal {
+
+    /// Fails, if `scheme` isn't supported or `options` are invalid for it.
+    pub fn new(scheme: &str, options: HashMap<String, String>) -> Result<OpenDal, String> {
+        let operator = Operator::via_iter(scheme, options)
+            .map_err(|error| format!("Invalid configuration for the scheme '{}': {}", scheme, error))?;
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|error| error.to_string())?;
+
+        Ok(OpenDal {
+            operator,
+            runtime,
+        })
+    }
+
+    fn storage_error(&self, error: opendal::Error) -> StorageError {
+        let info = self.operator.info();
+        let name = format!("{}://{}{}", info.scheme(), info.name(), info.root());
+
+        match error.kind() {
+            ErrorKind::NotFound => StorageError::BucketNotFound(name),
+            ErrorKind::PermissionDenied => StorageError::AccessDenied(name),
+            _ => StorageError::Request(error.to_string()),
+        }
+    }
+
+    /// Returns the backup stored at the path of `entry`, dated by `listed_at`, if the
+    /// service doesn't tell when it's been last modified.
+    fn backup_file_meta(entry: Entry, listed_at: DateTime<Utc>) -> BackupFileMeta {
+        let metadata = entry.metadata();
+
+        BackupFileMeta {
+            id: String::from(entry.path()),
+            human_readable_id: String::from(entry.path()),
+            date: metadata.last_modified().map_or(listed_at, |date| DateTime::<Utc>::from(SystemTime::from(date))),
+            size: metadata.content_length(),
+            storage_class: None,
+            etag: metadata.etag().map(String::from),
+            successful: None,
+            retain_until: None,
+            tier: None,
+            version_id: None,
+        }
+    }
+}
+
+impl StorageClient for OpenDal {
+
+    fn stored_backups(&self) -> Result<Vec<BackupFileMeta>, StorageError> {
+        let listed_at = Utc::now();
+        let entries = self.runtime
+            .block_on(async { self.operator.list_with("").recursive(true).await })
+            .map_err(|error| self.storage_error(error))?;
+
+        let mut backup_file_metas: Vec<BackupFileMeta> = entries
+            .into_iter()
+            .filter(|entry| entry.metadata().is_file())
+            .map(|entry| OpenDal::backup_file_meta(entry, listed_at))
+            .collect();
+        backup_file_metas.sort_by_key(|backup_file_meta| backup_file_meta.date);
+
+        Ok(backup_file_metas)
+    }
+
+    fn delete_backups(&self, backup_file_metas: Vec<BackupFileMeta>) -> DeletionReport {
+        let mut report = DeletionReport::default();
+
+        for backup_file_meta in backup_file_metas {
+            match self.runtime.block_on(self.operator.delete(&backup_file_meta.id)) {
+                Ok(()) => report.deleted.push(backup_file_meta),
+                Err(error) => report.failed.push((backup_file_meta, error.to_string())),
+            }
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pruning_strategy::{PruningStrategy, KeepLastN};
+    use crate::pruning_strategy::tests::collect_ids;
+    use std::fs;
+    use std::process;
+
+    fn in_memory(paths: &[&str]) -> OpenDal {
+        let client = OpenDal::new("memory", HashMap::new()).unwrap();
+        for path in paths {
+            client.runtime.block_on(client.operator.write(path, path.as_bytes().to_vec())).unwrap();
+        }
+
+        client
+    }
+
+    fn sorted_ids(backup_file_metas: Vec<BackupFileMeta>) -> Vec<String> {
+        let mut ids = collect_ids(backup_file_metas);
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn test_stored_backups() {
+        let client = in_memory(&["a.dump", "nested/b.dump", "nested/deeper/c.dump"]);
+        let listed_after = Utc::now();
+
+        let backup_file_metas = client.stored_backups().unwrap();
+
+        assert_eq!(sorted_ids(backup_file_metas.clone()), vec!["a.dump", "nested/b.dump", "nested/deeper/c.dump"]);
+        assert_eq!(backup_file_metas.iter().map(|backup_file_meta| backup_file_meta.size).sum::<u64>(), 39);
+
+        // The memory service doesn't tell when backups have been last modified.
+        assert!(backup_file_metas.iter().all(|backup_file_meta| backup_file_meta.date >= listed_after));
+    }
+
+    #[test]
+    fn test_stored_backups_are_dated_by_last_modification() {
+        let directory = std::env::temp_dir().join(format!("backups_cleaner-open_dal-{}", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("a.dump"), "a").unwrap();
+        let modified = DateTime::<Utc>::from(fs::metadata(directory.join("a.dump")).unwrap().modified().unwrap());
+        let mut options = HashMap::new();
+        options.insert(String::from("root"), directory.display().to_string());
+        let client = OpenDal::new("fs", options).unwrap();
+
+        let backup_file_metas = client.stored_backups();
+        let _ = fs::remove_dir_all(&directory);
+
+        let backup_file_metas = backup_file_metas.unwrap();
+        assert_eq!(collect_ids(backup_file_metas.clone()), vec!["a.dump"]);
+        assert_eq!(backup_file_metas[0].date, modified);
+    }
+
+    #[test]
+    fn test_prune() {
+        let client = in_memory(&["a.dump", "nested/b.dump", "nested/deeper/c.dump"]);
+
+        let mut backup_file_metas = client.stored_backups().unwrap();
+        let expendable_backups = KeepLastN::new(0).expendable_backups(&mut backup_file_metas);
+        let report = client.delete_backups(expendable_backups);
+
+        assert_eq!(sorted_ids(report.deleted), vec!["a.dump", "nested/b.dump", "nested/deeper/c.dump"]);
+        assert!(report.failed.is_empty());
+        assert!(client.stored_backups().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_new_with_an_unknown_scheme() {
+        assert!(OpenDal::new("does-not-exist", HashMap::new()).is_err());
+    }
+}
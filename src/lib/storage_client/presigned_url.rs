@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use super::{StorageClient, BackupFileMeta, DeletionReport, StorageError};
+
+/// How long a `DELETE` request may take by default, see `with_timeout`.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Number of redirects followed per backup, before giving up on it.
+const MAX_REDIRECTS: usize = 5;
+
+/// A client deleting backups by presigned `DELETE` URLs, generated by someone else
+/// holding the credentials. It doesn't need any credentials itself, and can't list
+/// backups, so it's given the candidates to consider instead.
+///
+/// Each backup is deleted using the URL its `id` maps to in `urls`. Any 2xx response
+/// counts as success, 307 and 308 redirects are followed. Requests go through the
+/// proxy given by `ALL_PROXY`, `HTTPS_PROXY` or `HTTP_PROXY`, if set. Backups without
+/// a URL can't be deleted.
+pub struct PresignedUrl {
+    backups: Mutex<Vec<BackupFileMeta>>,
+    urls: HashMap<String, String>,
+    timeout: Duration,
+}
+
+impl PresignedUrl {
+
+    pub fn new(backups: Vec<BackupFileMeta>, urls: HashMap<String, String>) -> PresignedUrl {
+        PresignedUrl {
+            backups: Mutex::new(backups),
+            urls,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Creates a client from a JSON array of candidate backups (as serialized by
+    /// `BackupFileMeta`) and a JSON object mapping their ids to presigned URLs.
+    #[cfg(feature = "serde")]
+    pub fn from_json(backups: &str, urls: &str) -> Result<PresignedUrl, String> {
+        let backups = serde_json::from_str(backups).map_err(|error| format!("Invalid list of backups: {}", error))?;
+        let urls = serde_json::from_str(urls).map_err(|error| format!("Invalid mapping of presigned URLs: {}", error))?;
+
+        Ok(PresignedUrl::new(backups, urls))
+    }
+
+    /// Gives up on deleting a backup, if its request takes longer than `timeout`
+    /// (30 seconds by default).
+    pub fn with_timeout(self, timeout: Duration) -> PresignedUrl {
+        PresignedUrl {
+            timeout,
+            ..self
+        }
+    }
+
+    fn agent(&self) -> Result<ureq::Agent, String> {
+        let tls_connector = native_tls::TlsConnector::new().map_err(|error| error.to_string())?;
+
+        Ok(ureq::AgentBuilder::new()
+            .tls_connector(Arc::new(tls_connector))
+            .try_proxy_from_env(true)
+            .timeout(self.timeout)
+            .redirects(0)
+            .build())
+    }
+}
+
+impl StorageClient for PresignedUrl {
+
+    fn stored_backups(&self) -> Result<Vec<BackupFileMeta>, StorageError> {
+        Ok(self.backups.lock().unwrap().clone())
+    }
+
+    fn delete_backups(&self, backup_file_metas: Vec<BackupFileMeta>) -> DeletionReport {
+        let mut report = DeletionReport::default();
+        let agent = self.agent();
+
+        for backup_file_meta in backup_file_metas {
+            let result = match (&agent, self.urls.get(&backup_file_meta.id)) {
+                (Ok(agent), Some(url)) => delete(agent, url),
+                (Err(reason), _) => Err(reason.clone()),
+                (_, None) => Err(String::from("No presigned URL")),
+            };
+
+            match result {
+                Ok(()) => {
+                    self.backups.lock().unwrap().retain(|backup| backup.id != backup_file_meta.id);
+                    report.deleted.push(backup_file_meta);
+                },
+                Err(reason) => report.failed.push((backup_file_meta, reason)),
+            }
+        }
+
+        report
+    }
+}
+
+/// Sends a `DELETE` request to `url`, failing unless the response has a 2xx status.
+/// Only 307 and 308 redirects are followed, as only they keep the method.
+fn delete(agent: &ureq::Agent, url: &str) -> Result<(), String> {
+    let mut url = url::Url::parse(url).map_err(|error| format!("Invalid URL: {}", error))?;
+
+    for _ in 0..=MAX_REDIRECTS {
+        let response = match agent.request_url("DELETE", &url).call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(status, _)) => return Err(format!("HTTP status {}", status)),
+            Err(error) => return Err(error.to_string()),
+        };
+
+        match (response.status(), response.header("location")) {
+            (200..=299, _) => return Ok(()),
+            (307, Some(location)) | (308, Some(location)) => {
+                url = url.join(location).map_err(|error| format!("Invalid redirect: {}", error))?;
+            },
+            (status, _) => return Err(format!("HTTP status {}", status)),
+        }
+    }
+
+    Err(format!("More than {} redirects", MAX_REDIRECTS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pruning_strategy::tests::{build_meta, collect_ids, as_vector};
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    use chrono::Utc;
+
+    /// Serves `requests` requests on `listener`, responding with 204 to paths ending in
+    /// `ok`, redirecting paths ending in `moved` to the same path ending in `ok`, waiting
+    /// for a second on paths ending in `slow` and responding with 403 to all others.
+    /// Returns the address to send them to.
+    fn serve_on(listener: TcpListener, requests: usize) -> String {
+        let address = listener.local_addr().unwrap().to_string();
+
+        thread::spawn(move || {
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut lines = BufReader::new(stream.try_clone().unwrap()).lines().map(Result::unwrap);
+                let request_line = lines.next().unwrap();
+                lines.take_while(|line| !line.is_empty()).for_each(drop);
+
+                let path = request_line.split_whitespace().nth(1).unwrap();
+                let response = match path.split('?').next().unwrap() {
+                    _ if !request_line.starts_with("DELETE ") => String::from("403 Forbidden\r\n"),
+                    path if path.ends_with("ok") => String::from("204 No Content\r\n"),
+                    path if path.ends_with("moved") => format!("307 Temporary Redirect\r\nLocation: {}\r\n", path.replace("moved", "ok")),
+                    path if path.ends_with("slow") => {
+                        thread::sleep(Duration::from_secs(1));
+                        String::from("204 No Content\r\n")
+                    },
+                    _ => String::from("403 Forbidden\r\n"),
+                };
+                let _ = write!(stream, "HTTP/1.1 {}Content-Length: 0\r\n\r\n", response);
+            }
+        });
+
+        address
+    }
+
+    fn serve(requests: usize) -> String {
+        serve_on(TcpListener::bind("127.0.0.1:0").unwrap(), requests)
+    }
+
+    fn failures(report: &DeletionReport) -> Vec<(&str, &str)> {
+        report.failed.iter().map(|(backup, reason)| (backup.id.as_str(), reason.as_str())).collect()
+    }
+
+    #[test]
+    fn test_delete_backups() {
+        let address = serve(2);
+        let mut urls = HashMap::new();
+        urls.insert(String::from("A"), format!("http://{}/backups/ok?X-Amz-Signature=1", address));
+        urls.insert(String::from("B"), format!("http://{}/backups/expired?X-Amz-Signature=2", address));
+        let client = PresignedUrl::new(
+            vec![build_meta("A", Utc::now()), build_meta("B", Utc::now()), build_meta("C", Utc::now())],
+            urls,
+        );
+
+        let report = client.delete_backups(client.stored_backups().unwrap());
+
+        assert_eq!(collect_ids(report.deleted.clone()), as_vector("A"));
+        assert_eq!(failures(&report), vec![("B", "HTTP status 403"), ("C", "No presigned URL")]);
+        assert_eq!(collect_ids(client.stored_backups().unwrap()), as_vector("BC"));
+    }
+
+    #[test]
+    fn test_delete_backups_following_redirects() {
+        let address = serve(2);
+        let mut urls = HashMap::new();
+        urls.insert(String::from("A"), format!("http://{}/backups/moved?X-Amz-Signature=1", address));
+        let client = PresignedUrl::new(vec![build_meta("A", Utc::now())], urls);
+
+        let report = client.delete_backups(client.stored_backups().unwrap());
+
+        assert_eq!(collect_ids(report.deleted), as_vector("A"));
+        assert!(report.failed.is_empty());
+    }
+
+    #[test]
+    fn test_delete_backups_with_timeout() {
+        let address = serve(1);
+        let mut urls = HashMap::new();
+        urls.insert(String::from("A"), format!("http://{}/backups/slow?X-Amz-Signature=1", address));
+        let client = PresignedUrl::new(vec![build_meta("A", Utc::now())], urls).with_timeout(Duration::from_millis(100));
+
+        let report = client.delete_backups(client.stored_backups().unwrap());
+
+        assert!(report.deleted.is_empty());
+        assert_eq!(collect_ids(client.stored_backups().unwrap()), as_vector("A"));
+    }
+
+    #[test]
+    fn test_delete_backups_by_ipv6_urls() {
+
+        // Not every host (e.g. some containers) has IPv6 enabled.
+        let listener = match TcpListener::bind("[::1]:0") {
+            Ok(listener) => listener,
+            Err(_) => return,
+        };
+        let address = serve_on(listener, 1);
+        let mut urls = HashMap::new();
+        urls.insert(String::from("A"), format!("http://{}/backups/ok?X-Amz-Signature=1", address));
+        let client = PresignedUrl::new(vec![build_meta("A", Utc::now())], urls);
+
+        let report = client.delete_backups(client.stored_backups().unwrap());
+
+        assert_eq!(collect_ids(report.deleted), as_vector("A"));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_from_json() {
+        let backups = r#"[{
+            "id": "backups/2014-07-01.dump",
+            "human_readable_id": "backups/2014-07-01.dump",
+            "date": "2014-07-01T00:00:00Z",
+            "size": 1024,
+            "storage_class": null,
+            "etag": null,
+            "successful": null
+        }]"#;
+        let urls = r#"{"backups/2014-07-01.dump": "https://my-database-backups.s3.amazonaws.com/backups/2014-07-01.dump"}"#;
+
+        let client = PresignedUrl::from_json(backups, urls).unwrap();
+
+        assert_eq!(collect_ids(client.stored_backups().unwrap()), vec!["backups/2014-07-01.dump"]);
+        assert!(PresignedUrl::from_json("{}", urls).is_err());
+    }
+}
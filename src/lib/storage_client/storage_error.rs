@@ -0,0 +1,42 @@
+use std::error::Error;
+use std::fmt;
+
+/// Errors a storage client may encounter while talking to its host.
+#[derive(Debug, PartialEq)]
+pub enum StorageError {
+
+    /// The bucket (or the like) with the given name does not exist.
+    BucketNotFound(String),
+
+    /// The credentials used don't grant access to the bucket with the given name.
+    AccessDenied(String),
+
+    /// No valid credentials could be found, along with the reason.
+    Authentication(String),
+
+    /// The host could not be reached, along with the reason.
+    Network(String),
+
+    /// A request to the host failed, along with the host's description of the failure.
+    Request(String),
+
+    /// Listing has been given up, as the bucket with the given name holds more objects
+    /// than the given cap.
+    TooManyObjects(String, usize),
+}
+
+impl fmt::Display for StorageError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StorageError::BucketNotFound(bucket) => write!(f, "Bucket '{}' does not exist or you lack access to it.", bucket),
+            StorageError::AccessDenied(bucket) => write!(f, "Access to bucket '{}' has been denied.", bucket),
+            StorageError::Authentication(reason) => write!(f, "Authentication failed: {}", reason),
+            StorageError::Network(reason) => write!(f, "Could not reach the host: {}", reason),
+            StorageError::Request(reason) => write!(f, "Request failed: {}", reason),
+            StorageError::TooManyObjects(bucket, max) => write!(f, "Bucket '{}' has more than {} objects; refusing to proceed.", bucket, max),
+        }
+    }
+}
+
+impl Error for StorageError {}
@@ -0,0 +1,180 @@
+use std::path::PathBuf;
+use super::{StorageClient, AwsS3, LocalFilesystem};
+
+/// Where backups are stored, as given by a URL like `s3://BUCKET/PREFIX?region=REGION`
+/// or `file:///PATH` (e.g. `file:///C:/backups` on Windows), so the backend can be
+/// chosen by a single option.
+#[derive(Debug, PartialEq)]
+pub enum StorageUrl {
+
+    /// Backups in an AWS S3 bucket. Without a region, it's asked for.
+    AwsS3 {
+        region: Option<String>,
+        bucket: String,
+        prefix: String,
+    },
+
+    /// Backups in a local directory.
+    LocalFilesystem {
+        directory: PathBuf,
+    },
+}
+
+impl StorageUrl {
+
+    /// Parses `url`. Fails, if its scheme isn't `s3` or `file`, or if it's missing a
+    /// bucket or an absolute path respectively, or has query parameters the scheme
+    /// doesn't take.
+    pub fn parse(url: &str) -> Result<StorageUrl, String> {
+        let index = url.find("://").ok_or_else(|| format!("'{}' is not a URL such as s3://BUCKET/PREFIX", url))?;
+        let (scheme, rest) = (&url[..index], &url[index + 3..]);
+        let (path, query) = match rest.find('?') {
+            Some(index) => (&rest[..index], Some(&rest[index + 1..])),
+            None => (rest, None),
+        };
+        let parameters = parse_query(url, query)?;
+
+        match scheme {
+            "s3" => {
+                let (bucket, prefix) = match path.find('/') {
+                    Some(index) => (&path[..index], &path[index + 1..]),
+                    None => (path, ""),
+                };
+                if bucket.is_empty() {
+                    return Err(format!("'{}' is missing the bucket", url));
+                }
+
+                let mut region = None;
+                for (name, value) in parameters {
+                    match name {
+                        "region" => region = Some(String::from(value)),
+                        _ => return Err(format!("'{}' has the unknown query parameter '{}'", url, name)),
+                    }
+                }
+
+                Ok(StorageUrl::AwsS3 {
+                    region,
+                    bucket: String::from(bucket),
+                    prefix: String::from(prefix),
+                })
+            },
+            "file" => {
+                if let Some((name, _)) = parameters.first() {
+                    return Err(format!("'{}' has the unknown query parameter '{}'", url, name));
+                }
+                if !path.starts_with('/') {
+                    return Err(format!("'{}' is missing an absolute path, such as file:///var/backups", url));
+                }
+
+                Ok(StorageUrl::LocalFilesystem {
+                    directory: local_path(path),
+                })
+            },
+            _ => Err(format!("'{}' has the unknown scheme '{}', expected s3 or file", url, scheme)),
+        }
+    }
+
+    /// Returns a client for the backups stored there. Fails, if the region of a bucket
+    /// can't be detected.
+    pub fn into_client(self) -> Result<Box<dyn StorageClient>, String> {
+        match self {
+            StorageUrl::AwsS3 { region: Some(region), bucket, prefix } => Ok(Box::new(AwsS3::new(region, bucket, prefix))),
+            StorageUrl::AwsS3 { region: None, bucket, prefix } => AwsS3::new_autodetect(bucket, prefix)
+                .map(|client| Box::new(client) as Box<dyn StorageClient>)
+                .map_err(|error| error.to_string()),
+            StorageUrl::LocalFilesystem { directory } => Ok(Box::new(LocalFilesystem::new(directory))),
+        }
+    }
+}
+
+/// Returns the local path of the `path` of a `file` URL. On Windows, that's without the
+/// slash before a drive letter, e.g. `C:/backups` for `file:///C:/backups`.
+fn local_path(path: &str) -> PathBuf {
+    let bytes = path.as_bytes();
+    let has_drive_letter = bytes.len() >= 3 && bytes[1].is_ascii_alphabetic() && bytes[2] == b':';
+
+    if cfg!(windows) && has_drive_letter {
+        PathBuf::from(&path[1..])
+    }
+    else {
+        PathBuf::from(path)
+    }
+}
+
+/// Returns the parameters of the `query` of `url` as pairs of name and value.
+fn parse_query<'a>(url: &str, query: Option<&'a str>) -> Result<Vec<(&'a str, &'a str)>, String> {
+    query
+        .into_iter()
+        .flat_map(|query| query.split('&'))
+        .filter(|parameter| !parameter.is_empty())
+        .map(|parameter| match parameter.find('=') {
+            Some(index) if index > 0 => Ok((&parameter[..index], &parameter[index + 1..])),
+            _ => Err(format!("'{}' has the query parameter '{}', which is not of the form NAME=VALUE", url, parameter)),
+        })
+        .collect()
+}
+
+/// Returns a client for the backups stored at `url`, see `StorageUrl::parse`.
+pub fn from_url(url: &str) -> Result<Box<dyn StorageClient>, String> {
+    StorageUrl::parse(url)?.into_client()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::StorageError;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(StorageUrl::parse("s3://my-database-backups/backups/daily/?region=eu-west-2"), Ok(StorageUrl::AwsS3 {
+            region: Some(String::from("eu-west-2")),
+            bucket: String::from("my-database-backups"),
+            prefix: String::from("backups/daily/"),
+        }));
+        assert_eq!(StorageUrl::parse("s3://my-database-backups"), Ok(StorageUrl::AwsS3 {
+            region: None,
+            bucket: String::from("my-database-backups"),
+            prefix: String::new(),
+        }));
+        assert_eq!(StorageUrl::parse("file:///var/backups"), Ok(StorageUrl::LocalFilesystem {
+            directory: PathBuf::from("/var/backups"),
+        }));
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn test_parse_with_a_drive_letter_on_windows() {
+        assert_eq!(StorageUrl::parse("file:///C:/backups"), Ok(StorageUrl::LocalFilesystem {
+            directory: PathBuf::from(r"C:\backups"),
+        }));
+        assert_eq!(StorageUrl::parse("file:///d:/backups/daily"), Ok(StorageUrl::LocalFilesystem {
+            directory: PathBuf::from("d:/backups/daily"),
+        }));
+    }
+
+    #[test]
+    fn test_parse_invalid_urls() {
+        assert_eq!(
+            StorageUrl::parse("gs://my-database-backups/backups/"),
+            Err(String::from("'gs://my-database-backups/backups/' has the unknown scheme 'gs', expected s3 or file")),
+        );
+        assert!(StorageUrl::parse("my-database-backups/backups/").is_err());
+        assert!(StorageUrl::parse("s3:///backups/").is_err());
+        assert!(StorageUrl::parse("s3://my-database-backups?regoin=eu-west-2").is_err());
+        assert!(StorageUrl::parse("s3://my-database-backups?eu-west-2").is_err());
+        assert!(StorageUrl::parse("file://backups").is_err());
+        assert!(StorageUrl::parse("file:///var/backups?region=eu-west-2").is_err());
+    }
+
+    #[test]
+    fn test_from_url() {
+        let client = from_url("file:///backups_cleaner-does-not-exist").unwrap();
+
+        assert_eq!(
+            client.stored_backups().err(),
+            Some(StorageError::BucketNotFound(String::from("/backups_cleaner-does-not-exist"))),
+        );
+        assert!(from_url("s3://my-database-backups/backups/?region=eu-west-2").is_ok());
+        assert!(from_url("gs://my-database-backups").is_err());
+    }
+}